@@ -0,0 +1,192 @@
+use crate::process_groups::{GroupBy, ProcessGroupState};
+use crate::processes::is_process_protected;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use sysinfo::System;
+
+// * NOTE: Embedded catalog
+// The catalog is compiled into the binary with `include_str!`, so it works offline and
+// cannot be tampered with on disk. Bump `version` in the JSON whenever entries change,
+// so the frontend can tell users that the suggestions were updated.
+const CATALOG_JSON: &str = include_str!("bloatware_catalog.json");
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CatalogCategory {
+    Overlay,
+    Updater,
+    Rgb,
+    Sync,
+    Browser,
+    Launcher,
+    Optimizer,
+    Communication,
+    Media,
+}
+
+/// How likely closing the process breaks something the user cares about.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RecommendedAction {
+    /// Safe to put on the kill list.
+    Kill,
+    /// Better closed by the user (unsaved state such as browser tabs or form input).
+    CloseManually,
+    /// Depends on how the user works; show it, but do not pre-select it.
+    Review,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogEntry {
+    pub id: String,
+    pub display_name: String,
+    pub process_names: Vec<String>,
+    pub category: CatalogCategory,
+    pub risk: RiskLevel,
+    pub action: RecommendedAction,
+    pub description: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BloatwareCatalog {
+    pub version: u32,
+    pub entries: Vec<CatalogEntry>,
+}
+
+impl BloatwareCatalog {
+    /// Finds the entry for a process name (case-insensitive).
+    pub fn find(&self, process_name: &str) -> Option<&CatalogEntry> {
+        self.entries.iter().find(|entry| {
+            entry
+                .process_names
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(process_name))
+        })
+    }
+}
+
+/// A running process that matches a catalog entry.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct KillListSuggestion {
+    /// The name to put on the kill list, exactly as it is running.
+    pub process_name: String,
+    pub entry: CatalogEntry,
+    pub instance_count: usize,
+    pub total_memory: u64,
+    pub total_cpu: f32,
+    pub pids: Vec<u32>,
+    /// Share of total RAM plus share of total CPU time, in percent. Used for ranking.
+    pub resource_score: f32,
+}
+
+/// Returns the parsed embedded catalog.
+///
+/// The JSON is part of the binary, so a parse error is a build mistake, not a runtime
+/// condition. It is still reported as an error instead of panicking.
+pub fn catalog() -> Result<&'static BloatwareCatalog, String> {
+    static CATALOG: OnceLock<Result<BloatwareCatalog, String>> = OnceLock::new();
+    CATALOG
+        .get_or_init(|| {
+            serde_json::from_str(CATALOG_JSON)
+                .map_err(|e| format!("Embedded bloatware catalog is invalid: {}", e))
+        })
+        .as_ref()
+        .map_err(Clone::clone)
+}
+
+/// Returns the full catalog, e.g. to show it in the process management view.
+#[tauri::command]
+pub fn get_bloatware_catalog() -> Result<BloatwareCatalog, String> {
+    catalog().cloned()
+}
+
+/// Matches running processes against the catalog and ranks them by resource usage.
+///
+/// # Arguments
+/// * `exclude` - Process names to leave out, typically the current kill list.
+#[tauri::command]
+pub fn suggest_kill_list(
+    state: tauri::State<ProcessGroupState>,
+    exclude: Option<Vec<String>>,
+) -> Result<Vec<KillListSuggestion>, String> {
+    let catalog = catalog()?;
+    let exclude = exclude.unwrap_or_default();
+    let groups = state.refreshed_groups(GroupBy::Name)?;
+
+    let mut sys = System::new();
+    sys.refresh_memory();
+    let total_memory = sys.total_memory().max(1) as f32;
+    let cpu_count = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1) as f32;
+
+    let mut suggestions: Vec<KillListSuggestion> = groups
+        .into_iter()
+        .filter(|group| !is_process_protected(&group.name))
+        .filter(|group| {
+            !exclude
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(&group.name))
+        })
+        .filter_map(|group| {
+            let entry = catalog.find(&group.name)?.clone();
+            // `cpu_usage` is per core (a busy process on 8 cores reports 800 %).
+            let resource_score =
+                group.total_memory as f32 / total_memory * 100.0 + group.total_cpu / cpu_count;
+            Some(KillListSuggestion {
+                process_name: group.name,
+                entry,
+                instance_count: group.instance_count,
+                total_memory: group.total_memory,
+                total_cpu: group.total_cpu,
+                pids: group.pids,
+                resource_score,
+            })
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| {
+        b.resource_score
+            .partial_cmp(&a.resource_score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(suggestions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn embedded_catalog_is_valid() {
+        let catalog = catalog().expect("catalog parses");
+        assert!(!catalog.entries.is_empty());
+
+        let mut ids = HashSet::new();
+        let mut names = HashSet::new();
+        for entry in &catalog.entries {
+            assert!(ids.insert(entry.id.as_str()), "duplicate id {}", entry.id);
+            assert!(!entry.process_names.is_empty(), "{} has no names", entry.id);
+            for name in &entry.process_names {
+                assert!(
+                    names.insert(name.to_lowercase()),
+                    "{} is listed twice",
+                    name
+                );
+                assert!(!is_process_protected(name), "{} is protected", name);
+            }
+        }
+    }
+}
@@ -1,10 +1,11 @@
+use crate::verification::Verification;
 use serde::{Deserialize, Serialize};
 use std::os::windows::process::CommandExt;
 use std::process::Command;
 
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct CoreParkingState {
     pub ac_value: u32,
@@ -54,8 +55,12 @@ pub fn get_core_parking_status() -> Result<CoreParkingState, String> {
 
 /// Sets the CPU core parking minimal cores value (0-100) for the active power plan.
 /// A value of 100% means core parking is deactivated (all cores are unparked).
+/// The values are read back afterwards, since policies can pin them.
 #[tauri::command]
-pub fn set_core_parking_status(ac_value: u32, dc_value: u32) -> Result<(), String> {
+pub fn set_core_parking_status(
+    ac_value: u32,
+    dc_value: u32,
+) -> Result<Verification<CoreParkingState>, String> {
     let subgroups = [
         "54533251-82be-4824-96c1-47b60b740d00",
         "0cc5b647-c1df-4637-891a-dec35c318583",
@@ -106,5 +111,8 @@ pub fn set_core_parking_status(ac_value: u32, dc_value: u32) -> Result<(), Strin
         return Err(String::from_utf8_lossy(&apply_output.stderr).to_string());
     }
 
-    Ok(())
+    Ok(Verification::compare(
+        CoreParkingState { ac_value, dc_value },
+        get_core_parking_status()?,
+    ))
 }
@@ -1,75 +1,629 @@
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::thread;
-use std::time::Duration;
-use sysinfo::{ProcessesToUpdate, System};
-use tauri::{AppHandle, Emitter};
+use crate::game_window;
+use crate::games::GameState;
+use crate::process_stream::sleep_unless_stopped;
+use crate::process_watcher::{ProcessWatcher, WatchEvent};
+use crate::session::{ForegroundWindow, SessionEngine, SessionMessage, SessionStatus};
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{AppHandle, Emitter, Manager};
+use winapi::shared::windef::HWND;
 use winapi::um::winuser::{GetForegroundWindow, GetWindowThreadProcessId};
 
-/// Startet den Hintergrund-Thread, der überwacht, ob CS2 im Vordergrund läuft.
+const INTERVAL_KEY: &str = "monitorIntervalMs";
+const FOCUS_KEY: &str = "focusConfig";
+
+/// Standard-Wartezeit pro Durchlauf.
+/// Bestimmt, wie schnell der Vordergrund-Wechsel und das Beenden der App bemerkt werden.
+const DEFAULT_INTERVAL_MS: u64 = 500;
+
+/// Erlaubter Bereich für `set_monitor_interval`.
+const MIN_INTERVAL_MS: u64 = 100;
+const MAX_INTERVAL_MS: u64 = 5000;
+
+/// Obergrenze für die Verzögerungen in `FocusConfig`.
+const MAX_FOCUS_DELAY_MS: u64 = 10_000;
+
+/// Entprellung des Vordergrund-Zustands.
 ///
-/// # Funktionsweise
-/// 1. Läuft so lange, bis das `shutdown`-Flag auf `true` gesetzt wird.
-/// 2. Prüft jede Sekunde das aktive Fenster und laufende Prozesse.
-/// 3. Informiert das Frontend, ob CS2 läuft und fokussiert ist.
-pub fn start_monitor_thread(app: AppHandle, shutdown: Arc<AtomicBool>) {
-    thread::spawn(move || {
-        let _ = app.emit("log-info", "CS2 Monitor Thread started!");
-
-        let mut sys = System::new();
-
-        let mut last_process_running = false;
-        let mut last_window_foreground = false;
-
-        while !shutdown.load(Ordering::Relaxed) {
-            thread::sleep(Duration::from_millis(1000));
-
-            // Refresh processes to ensure we have the latest state
-            sys.refresh_processes(ProcessesToUpdate::All, true);
-
-            // 1. Detect Process State
-            let mut cs2_is_running = false;
-            for (_pid, process) in sys.processes() {
-                let name = process.name().to_string_lossy().to_lowercase();
-                if name == "cs2.exe" || name == "cs2" {
-                    cs2_is_running = true;
-                    break;
-                }
+/// Ein Alt-Tab oder ein Popup würde sonst sofort `background` melden und damit jedes Mal
+/// die Vibrance umschalten (inklusive kompletter NvAPI-Initialisierung).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FocusConfig {
+    /// So lange muss ein anderes Fenster aktiv sein, bevor `background` gemeldet wird.
+    pub background_delay_ms: u64,
+    /// So lange muss das Spiel wieder aktiv sein, bevor `foreground` gemeldet wird.
+    pub foreground_delay_ms: u64,
+    /// Fensterklassen, die nicht als Fokusverlust zählen (Flyouts, Benachrichtigungen).
+    pub ignored_window_classes: Vec<String>,
+    /// Prozesse, deren Fenster nicht als Fokusverlust zählen (Overlays).
+    pub ignored_processes: Vec<String>,
+}
+
+impl Default for FocusConfig {
+    fn default() -> Self {
+        Self {
+            background_delay_ms: 750,
+            foreground_delay_ms: 0,
+            ignored_window_classes: vec![
+                // Lautstärke-/Helligkeits-Flyout (Windows 10)
+                "NativeHWNDHost".to_string(),
+                // Info-Center, Benachrichtigungen
+                "Windows.UI.Core.CoreWindow".to_string(),
+                // Flyouts und Alt-Tab (Windows 11)
+                "XamlExplorerHostIslandWindow".to_string(),
+            ],
+            ignored_processes: vec![
+                "GameOverlayUI.exe".to_string(),
+                "ShellExperienceHost.exe".to_string(),
+                "NVIDIA Overlay.exe".to_string(),
+            ],
+        }
+    }
+}
+
+impl FocusConfig {
+    fn ignores(&self, class: Option<&str>, process: Option<&str>) -> bool {
+        class.is_some_and(|class| self.ignored_window_classes.iter().any(|c| c == class))
+            || process.is_some_and(|process| {
+                self.ignored_processes
+                    .iter()
+                    .any(|p| p.eq_ignore_ascii_case(process))
+            })
+    }
+}
+
+/// Zustand des Monitor-Threads, wie ihn `get_game_status` meldet.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorStatus {
+    /// `false`, sobald der Thread beendet ist.
+    pub running: bool,
+    pub paused: bool,
+    pub interval_ms: u64,
+    pub focus: FocusConfig,
+    /// Woher der Watcher neue Prozesse erfährt (WMI oder Prozessliste).
+    pub source: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GameStatus {
+    pub monitor: MonitorStatus,
+    /// Laufende Spiele und das Spiel im Vordergrund.
+    pub session: SessionStatus,
+}
+
+/// Von Thread und Commands geteilte Steuerung.
+struct MonitorControl {
+    paused: AtomicBool,
+    interval_ms: AtomicU64,
+    focus: Mutex<FocusConfig>,
+    source: Mutex<Option<String>>,
+}
+
+/// Der Monitor-Thread als verwalteter Tauri-State.
+///
+/// Hält den `JoinHandle`, damit das Beenden der App auf den Thread warten kann
+/// (der Watcher gibt dabei seine WMI-/COM-Objekte sauber frei).
+pub struct MonitorService {
+    control: Arc<MonitorControl>,
+    shutdown: Arc<AtomicBool>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl MonitorService {
+    /// Startet den Hintergrund-Thread, der die konfigurierten Spiele überwacht.
+    ///
+    /// # Funktionsweise
+    /// 1. Läuft so lange, bis das `shutdown`-Flag auf `true` gesetzt wird.
+    /// 2. Wartet über den `ProcessWatcher` auf Start und Ende der Spiele. Welche Prozesse zu
+    ///    welchem Spiel gehören, entscheidet `GameState::classify`.
+    /// 3. Prüft das aktive Fenster gegen die bekannten Spiel-PIDs und verfolgt, auf welchem
+    ///    Monitor das Spiel im Vordergrund liegt.
+    /// 4. Meldet alles an die `SessionEngine`, die die Profile anwendet und die Events sendet.
+    pub fn start(app: AppHandle, shutdown: Arc<AtomicBool>) -> Self {
+        let interval_ms = settings::load::<_, u64>(&app, INTERVAL_KEY)
+            .filter(|ms| (MIN_INTERVAL_MS..=MAX_INTERVAL_MS).contains(ms))
+            .unwrap_or(DEFAULT_INTERVAL_MS);
+        let control = Arc::new(MonitorControl {
+            paused: AtomicBool::new(false),
+            interval_ms: AtomicU64::new(interval_ms),
+            focus: Mutex::new(settings::load(&app, FOCUS_KEY).unwrap_or_default()),
+            source: Mutex::new(None),
+        });
+
+        let thread_control = control.clone();
+        let thread_shutdown = shutdown.clone();
+        let handle = thread::spawn(move || run_monitor(&app, &thread_control, &thread_shutdown));
+
+        Self {
+            control,
+            shutdown,
+            thread: Mutex::new(Some(handle)),
+        }
+    }
+
+    /// Beendet den Thread und wartet höchstens `timeout` darauf. Gibt `false` zurück,
+    /// wenn er bis dahin nicht fertig war; er endet dann von selbst beim nächsten
+    /// Durchlauf. Mehrfache Aufrufe sind harmlos.
+    pub fn stop(&self, timeout: Duration) -> bool {
+        self.shutdown.store(true, Ordering::Relaxed);
+        let Some(handle) = self.thread.lock().ok().and_then(|mut t| t.take()) else {
+            return true;
+        };
+        let deadline = Instant::now() + timeout;
+        while !handle.is_finished() {
+            if Instant::now() >= deadline {
+                return false;
             }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = handle.join();
+        true
+    }
+
+    fn status(&self) -> MonitorStatus {
+        MonitorStatus {
+            running: self
+                .thread
+                .lock()
+                .map(|t| t.as_ref().is_some_and(|h| !h.is_finished()))
+                .unwrap_or(false),
+            paused: self.control.paused.load(Ordering::Relaxed),
+            interval_ms: self.control.interval_ms.load(Ordering::Relaxed),
+            focus: self
+                .control
+                .focus
+                .lock()
+                .map(|f| f.clone())
+                .unwrap_or_default(),
+            source: self.control.source.lock().ok().and_then(|s| s.clone()),
+        }
+    }
+}
+
+/// Was im aktuellen Durchlauf im Vordergrund ist.
+enum Focus {
+    Game {
+        game_id: String,
+        pid: u32,
+    },
+    Other,
+    /// Overlay, Flyout oder gar kein Fenster (mitten im Alt-Tab): ändert nichts.
+    Transient,
+}
+
+/// Merkt sich die Prüfung des letzten fremden Fensters, damit nicht in jedem
+/// Durchlauf Klasse und Prozessname gelesen werden.
+struct OverlayCheck {
+    sys: System,
+    last: Option<(usize, bool)>,
+}
 
-            if cs2_is_running != last_process_running {
-                let status = if cs2_is_running { "started" } else { "stopped" };
-                let _ = app.emit("cs2process", status);
-                last_process_running = cs2_is_running;
+impl OverlayCheck {
+    fn is_transient(&mut self, hwnd: HWND, pid: u32, config: &FocusConfig) -> bool {
+        if let Some((last_hwnd, transient)) = self.last {
+            if last_hwnd == hwnd as usize {
+                return transient;
             }
+        }
+        let pid = Pid::from_u32(pid);
+        self.sys.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[pid]),
+            true,
+            ProcessRefreshKind::nothing(),
+        );
+        let process = self
+            .sys
+            .process(pid)
+            .map(|p| p.name().to_string_lossy().into_owned());
+        let transient = config.ignores(
+            game_window::window_class(hwnd).as_deref(),
+            process.as_deref(),
+        );
+        self.last = Some((hwnd as usize, transient));
+        transient
+    }
+}
 
-            // 2. Detect Foreground Window State
-            let hwnd = unsafe { GetForegroundWindow() };
-            let mut is_cs2_foreground = false;
+fn current_focus(
+    watcher: &ProcessWatcher,
+    hwnd: HWND,
+    config: &FocusConfig,
+    overlays: &mut OverlayCheck,
+) -> Focus {
+    if !watcher.is_running() {
+        return Focus::Other;
+    }
+    if hwnd.is_null() {
+        return Focus::Transient;
+    }
+    let mut pid = 0;
+    unsafe { GetWindowThreadProcessId(hwnd, &mut pid) };
+    match watcher.target_of(pid) {
+        Some(game_id) => Focus::Game {
+            game_id: game_id.to_string(),
+            pid,
+        },
+        None if overlays.is_transient(hwnd, pid, config) => Focus::Transient,
+        None => Focus::Other,
+    }
+}
 
-            if !hwnd.is_null() {
-                let mut pid_u32 = 0;
-                unsafe { GetWindowThreadProcessId(hwnd, &mut pid_u32) };
+/// Ergebnis von `FocusDebounce::observe`.
+#[derive(Debug, PartialEq)]
+enum FocusUpdate {
+    /// Beobachtet wird der bereits gemeldete Zustand; ein offener Wechsel verfällt.
+    Unchanged,
+    /// Ein Wechsel läuft, seine Verzögerung ist aber noch nicht abgelaufen.
+    Pending,
+    /// Der Wechsel hat lange genug angehalten und muss gemeldet werden.
+    Changed,
+}
 
-                let fg_pid = (pid_u32 as usize).into();
-                let process_name = sys
-                    .process(fg_pid)
-                    .map(|p| p.name().to_string_lossy().to_string())
-                    .unwrap_or_default();
+/// Entprellung des gemeldeten Vordergrund-Spiels.
+///
+/// Bekommt in jedem Durchlauf den Zeitpunkt und das Spiel im Vordergrund (`None` für
+/// ein fremdes Fenster) und entscheidet, ob der Wechsel schon gemeldet wird.
+#[derive(Debug, Default)]
+struct FocusDebounce {
+    /// Zuletzt gemeldetes Spiel im Vordergrund.
+    reported: Option<String>,
+    /// Beobachteter, aber noch nicht gemeldeter Wechsel: (Spiel, seit, Verzögerung)
+    pending: Option<(Option<String>, Instant, Duration)>,
+}
 
-                let name_lower = process_name.to_lowercase();
-                is_cs2_foreground = name_lower == "cs2.exe" || name_lower == "cs2";
+impl FocusDebounce {
+    /// Wartezeit für den nächsten Durchlauf.
+    ///
+    /// Mit offenem Wechsel nur bis zu dessen Ablauf warten, damit die Verzögerung stimmt.
+    /// Nie kürzer als `MIN_INTERVAL_MS`: Ein abgelaufener Wechsel, der wegen eines
+    /// Overlays nicht gemeldet wird, würde die Schleife sonst ohne Pause drehen lassen.
+    fn wait(&self, now: Instant, interval: Duration) -> Duration {
+        match &self.pending {
+            Some((_, since, delay)) => interval
+                .min(delay.saturating_sub(now.saturating_duration_since(*since)))
+                .max(Duration::from_millis(MIN_INTERVAL_MS)),
+            None => interval,
+        }
+    }
+
+    /// Verwirft einen offenen Wechsel, z. B. während einer Pause.
+    fn cancel(&mut self) {
+        self.pending = None;
+    }
+
+    fn observe(
+        &mut self,
+        now: Instant,
+        focused: Option<String>,
+        config: &FocusConfig,
+    ) -> FocusUpdate {
+        if focused == self.reported {
+            // Zurück zum gemeldeten Zustand, bevor die Verzögerung abgelaufen ist.
+            self.pending = None;
+            return FocusUpdate::Unchanged;
+        }
+        let delay_ms = if focused.is_some() {
+            config.foreground_delay_ms
+        } else {
+            config.background_delay_ms
+        };
+        let since = match &self.pending {
+            Some((id, since, _)) if *id == focused => *since,
+            _ => now,
+        };
+        let delay = Duration::from_millis(delay_ms);
+        if now.saturating_duration_since(since) < delay {
+            self.pending = Some((focused, since, delay));
+            return FocusUpdate::Pending;
+        }
+        self.pending = None;
+        self.reported = focused;
+        FocusUpdate::Changed
+    }
+}
+
+fn run_monitor(app: &AppHandle, control: &MonitorControl, shutdown: &AtomicBool) {
+    let _ = app.emit("log-info", "Game Monitor Thread started!");
+
+    let games = app.state::<GameState>();
+    let session = app.state::<SessionEngine>();
+
+    // * HINWEIS: Der Watcher wird im Thread erstellt, weil die WMI-Quelle COM-Objekte
+    // hält, die an den erzeugenden Thread gebunden sind.
+    let classifier_app = app.clone();
+    let mut sys = System::new();
+    let mut watcher = ProcessWatcher::new(Box::new(move |process| {
+        classifier_app
+            .state::<GameState>()
+            .classify(&mut sys, process.pid, &process.name)
+    }));
+
+    let mut revision = games.revision();
+    let mut debounce = FocusDebounce::default();
+    // Monitor, auf dem das Spielfenster im Vordergrund zuletzt war.
+    let mut last_display: Option<String> = None;
+    let mut overlays = OverlayCheck {
+        sys: System::new(),
+        last: None,
+    };
+    let mut was_paused = false;
+
+    while !shutdown.load(Ordering::Relaxed) {
+        let interval = debounce.wait(
+            Instant::now(),
+            Duration::from_millis(control.interval_ms.load(Ordering::Relaxed)),
+        );
+
+        // Pausiert: keine Events, die Sitzung bleibt wie sie ist. Ein offener Wechsel
+        // verfällt, nach der Pause zählt der dann aktuelle Vordergrund.
+        if control.paused.load(Ordering::Relaxed) {
+            was_paused = true;
+            debounce.cancel();
+            sleep_unless_stopped(interval, shutdown);
+            continue;
+        }
+
+        // Geänderte Spiele-Liste oder Ende einer Pause: laufende Prozesse neu zuordnen.
+        // Beendete Prozesse meldet der nächste `next_events`-Aufruf.
+        if games.revision() != revision || was_paused {
+            revision = games.revision();
+            was_paused = false;
+            watcher.reclassify();
+        }
+
+        // 1. Detect Process State
+        for event in watcher.next_events(interval) {
+            let message = match event {
+                WatchEvent::Started { pid, name, target } => {
+                    let _ = app.emit("log-info", format!("{} started (PID {})", name, pid));
+                    SessionMessage::ProcessStarted {
+                        game_id: target,
+                        pid,
+                    }
+                }
+                WatchEvent::Exited { pid, name, target } => {
+                    let _ = app.emit("log-info", format!("{} exited (PID {})", name, pid));
+                    SessionMessage::ProcessExited {
+                        game_id: target,
+                        pid,
+                    }
+                }
+            };
+            session.send(message);
+        }
+        let notices = watcher.drain_notices();
+        if !notices.is_empty() {
+            // Die Quelle kann zur Laufzeit wechseln (WMI -> Prozessliste).
+            if let Ok(mut source) = control.source.lock() {
+                *source = Some(watcher.source_description().to_string());
             }
+        }
+        for notice in notices {
+            let _ = app.emit("log-info", notice);
+        }
+
+        // 2. Detect Foreground Window State
+        // Kein Prozess-Lookup nötig: Die PIDs der Spiele kennt der Watcher bereits.
+        let config = control.focus.lock().map(|f| f.clone()).unwrap_or_default();
+        let hwnd = unsafe { GetForegroundWindow() };
+        let foreground = match current_focus(&watcher, hwnd, &config, &mut overlays) {
+            Focus::Game { game_id, pid } => Some((game_id, pid)),
+            Focus::Other => None,
+            // Zählt weder als Fokusverlust noch als Rückkehr, ein offener Wechsel läuft weiter.
+            Focus::Transient => continue,
+        };
 
-            if is_cs2_foreground != last_window_foreground {
-                let status = if is_cs2_foreground {
-                    "foreground"
-                } else {
-                    "background"
-                };
-                let _ = app.emit("cs2window", status);
-                last_window_foreground = is_cs2_foreground;
+        let observed = foreground.as_ref().map(|(id, _)| id.clone());
+        match debounce.observe(Instant::now(), observed, &config) {
+            FocusUpdate::Unchanged => {
+                // 3. Detect Display Changes
+                // Das Spielfenster wurde auf einen anderen Monitor verschoben, oder die
+                // Monitor-Anordnung hat sich geändert.
+                if let Some((game_id, pid)) = foreground {
+                    let window = game_window::window_info(hwnd);
+                    let display = window.as_ref().map(|w| w.monitor.clone());
+                    if display != last_display {
+                        last_display = display;
+                        if let Some(window) = window {
+                            session.send(SessionMessage::DisplayChanged(ForegroundWindow {
+                                game_id,
+                                pid,
+                                window: Some(window),
+                            }));
+                        }
+                    }
+                }
+            }
+            FocusUpdate::Pending => {}
+            FocusUpdate::Changed => {
+                let window = foreground
+                    .as_ref()
+                    .and_then(|_| game_window::window_info(hwnd));
+                last_display = window.as_ref().map(|w| w.monitor.clone());
+                session.send(SessionMessage::Foreground(foreground.map(
+                    |(game_id, pid)| ForegroundWindow {
+                        game_id,
+                        pid,
+                        window,
+                    },
+                )));
             }
         }
-    });
+    }
+
+    let _ = app.emit("log-info", "Game Monitor Thread stopped");
+}
+
+/// Hält die Überwachung an. Bereits angewendete Einstellungen bleiben aktiv.
+#[tauri::command]
+pub fn pause_monitor(app: AppHandle, state: tauri::State<MonitorService>) {
+    if !state.control.paused.swap(true, Ordering::Relaxed) {
+        let _ = app.emit("log-info", "Game monitor paused");
+    }
+}
+
+/// Setzt die Überwachung fort und gleicht verpasste Starts und Beenden ab.
+#[tauri::command]
+pub fn resume_monitor(app: AppHandle, state: tauri::State<MonitorService>) {
+    if state.control.paused.swap(false, Ordering::Relaxed) {
+        let _ = app.emit("log-info", "Game monitor resumed");
+    }
+}
+
+/// Setzt die maximale Wartezeit pro Durchlauf (100-5000 ms) und speichert sie.
+#[tauri::command]
+pub fn set_monitor_interval(
+    app: AppHandle,
+    state: tauri::State<MonitorService>,
+    interval_ms: u64,
+) -> Result<(), String> {
+    if !(MIN_INTERVAL_MS..=MAX_INTERVAL_MS).contains(&interval_ms) {
+        let err_msg = format!(
+            "Monitor interval must be between {} and {} ms, got {}",
+            MIN_INTERVAL_MS, MAX_INTERVAL_MS, interval_ms
+        );
+        let _ = app.emit("log-error", &err_msg);
+        return Err(err_msg);
+    }
+    settings::save(&app, INTERVAL_KEY, &interval_ms).inspect_err(|err_msg| {
+        let _ = app.emit("log-error", err_msg);
+    })?;
+    state
+        .control
+        .interval_ms
+        .store(interval_ms, Ordering::Relaxed);
+    let _ = app.emit(
+        "log-info",
+        format!("Game monitor interval set to {} ms", interval_ms),
+    );
+    Ok(())
+}
+
+/// Setzt Entprellung und ignorierte Fenster für die Vordergrund-Erkennung und speichert sie.
+#[tauri::command]
+pub fn set_focus_config(
+    app: AppHandle,
+    state: tauri::State<MonitorService>,
+    config: FocusConfig,
+) -> Result<(), String> {
+    if config.background_delay_ms > MAX_FOCUS_DELAY_MS
+        || config.foreground_delay_ms > MAX_FOCUS_DELAY_MS
+    {
+        let err_msg = format!("Focus delays must not exceed {} ms", MAX_FOCUS_DELAY_MS);
+        let _ = app.emit("log-error", &err_msg);
+        return Err(err_msg);
+    }
+    settings::save(&app, FOCUS_KEY, &config).inspect_err(|err_msg| {
+        let _ = app.emit("log-error", err_msg);
+    })?;
+    *state.control.focus.lock().map_err(|e| e.to_string())? = config;
+    Ok(())
+}
+
+/// Liefert den aktuellen Zustand, z. B. für ein neu geladenes Frontend.
+#[tauri::command]
+pub fn get_game_status(
+    monitor: tauri::State<MonitorService>,
+    session: tauri::State<SessionEngine>,
+) -> Result<GameStatus, String> {
+    Ok(GameStatus {
+        monitor: monitor.status(),
+        session: session.status()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn cs2() -> Option<String> {
+        Some("cs2".to_string())
+    }
+
+    #[test]
+    fn reports_the_game_immediately() {
+        let config = FocusConfig::default();
+        let mut debounce = FocusDebounce::default();
+        let now = Instant::now();
+
+        assert_eq!(debounce.observe(now, cs2(), &config), FocusUpdate::Changed);
+        assert_eq!(debounce.reported, cs2());
+        assert_eq!(
+            debounce.observe(now, cs2(), &config),
+            FocusUpdate::Unchanged
+        );
+    }
+
+    #[test]
+    fn reports_the_background_after_the_delay() {
+        let config = FocusConfig::default();
+        let mut debounce = FocusDebounce::default();
+        let start = Instant::now();
+        debounce.observe(start, cs2(), &config);
+
+        assert_eq!(debounce.observe(start, None, &config), FocusUpdate::Pending);
+        assert_eq!(
+            debounce.observe(start + ms(749), None, &config),
+            FocusUpdate::Pending
+        );
+        assert_eq!(
+            debounce.observe(start + ms(750), None, &config),
+            FocusUpdate::Changed
+        );
+        assert_eq!(debounce.reported, None);
+    }
+
+    #[test]
+    fn returning_early_cancels_the_change() {
+        let config = FocusConfig::default();
+        let mut debounce = FocusDebounce::default();
+        let start = Instant::now();
+        debounce.observe(start, cs2(), &config);
+
+        debounce.observe(start, None, &config);
+        assert_eq!(
+            debounce.observe(start + ms(500), cs2(), &config),
+            FocusUpdate::Unchanged
+        );
+        // Beim nächsten Fokusverlust beginnt die Verzögerung von vorn.
+        assert_eq!(
+            debounce.observe(start + ms(1000), None, &config),
+            FocusUpdate::Pending
+        );
+        assert_eq!(debounce.reported, cs2());
+    }
+
+    #[test]
+    fn waits_until_the_pending_change_is_due() {
+        let config = FocusConfig::default();
+        let mut debounce = FocusDebounce::default();
+        let start = Instant::now();
+        assert_eq!(debounce.wait(start, ms(500)), ms(500));
+
+        debounce.observe(start, cs2(), &config);
+        debounce.observe(start, None, &config);
+        assert_eq!(debounce.wait(start, ms(500)), ms(500));
+        assert_eq!(debounce.wait(start + ms(600), ms(500)), ms(150));
+        // Nie kürzer als das Mindestintervall, auch wenn der Wechsel überfällig ist.
+        assert_eq!(debounce.wait(start + ms(700), ms(500)), ms(MIN_INTERVAL_MS));
+        assert_eq!(
+            debounce.wait(start + ms(2000), ms(500)),
+            ms(MIN_INTERVAL_MS)
+        );
+
+        debounce.cancel();
+        assert_eq!(debounce.wait(start + ms(700), ms(500)), ms(500));
+    }
 }
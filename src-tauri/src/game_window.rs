@@ -0,0 +1,80 @@
+use serde::Serialize;
+use winapi::shared::windef::{HWND, RECT};
+use winapi::um::winuser::{
+    GetClassNameW, GetMonitorInfoA, GetWindowRect, MonitorFromWindow, MONITORINFO, MONITORINFOEXA,
+    MONITOR_DEFAULTTONEAREST,
+};
+
+/// Screen rectangle in physical pixels.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Bounds {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl From<RECT> for Bounds {
+    fn from(rect: RECT) -> Self {
+        Self {
+            x: rect.left,
+            y: rect.top,
+            width: rect.right - rect.left,
+            height: rect.bottom - rect.top,
+        }
+    }
+}
+
+/// Where a window is and how it is shown.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowInfo {
+    /// GDI device name of the monitor (`\\.\DISPLAY1`), as used by the vibrance commands.
+    pub monitor: String,
+    pub monitor_bounds: Bounds,
+    pub bounds: Bounds,
+    /// `true` if the window covers the whole monitor (exclusive or borderless fullscreen).
+    pub fullscreen: bool,
+}
+
+/// Reads position, monitor and fullscreen state of a window.
+pub fn window_info(hwnd: HWND) -> Option<WindowInfo> {
+    if hwnd.is_null() {
+        return None;
+    }
+
+    let mut window_rect: RECT = unsafe { std::mem::zeroed() };
+    if unsafe { GetWindowRect(hwnd, &mut window_rect) } == 0 {
+        return None;
+    }
+
+    let hmonitor = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) };
+    let mut monitor_info: MONITORINFOEXA = unsafe { std::mem::zeroed() };
+    monitor_info.cbSize = std::mem::size_of::<MONITORINFOEXA>() as u32;
+    if unsafe { GetMonitorInfoA(hmonitor, &mut monitor_info as *mut _ as *mut MONITORINFO) } == 0 {
+        return None;
+    }
+
+    let monitor_rect = monitor_info.rcMonitor;
+    let monitor = unsafe { std::ffi::CStr::from_ptr(monitor_info.szDevice.as_ptr()) }
+        .to_string_lossy()
+        .into_owned();
+
+    Some(WindowInfo {
+        monitor,
+        monitor_bounds: monitor_rect.into(),
+        bounds: window_rect.into(),
+        fullscreen: window_rect.left <= monitor_rect.left
+            && window_rect.top <= monitor_rect.top
+            && window_rect.right >= monitor_rect.right
+            && window_rect.bottom >= monitor_rect.bottom,
+    })
+}
+
+/// Window class name, e.g. `NativeHWNDHost` for the Windows 10 volume flyout.
+pub fn window_class(hwnd: HWND) -> Option<String> {
+    let mut buffer = [0u16; 256];
+    let len = unsafe { GetClassNameW(hwnd, buffer.as_mut_ptr(), buffer.len() as i32) };
+    (len > 0).then(|| String::from_utf16_lossy(&buffer[..len as usize]))
+}
@@ -0,0 +1,587 @@
+use crate::processes::{self, is_process_protected, ProcessPriority};
+use crate::profiles::ProfileState;
+use crate::session::SessionStep;
+use crate::settings::{self, FrontendSettings};
+use crate::throttle::ThrottleLimits;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use sysinfo::{Pid, Process, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+use tauri::{AppHandle, Emitter, Manager};
+
+const TARGETS_KEY: &str = "gameTargets";
+
+/// Environment variables Steam sets for every game it launches.
+const STEAM_APP_ID_VARIABLES: &[&str] = &["SteamAppId", "SteamGameId"];
+
+const MAX_STEP_DELAY_MS: u64 = 10 * 60 * 1000;
+const MAX_STEP_RETRIES: u32 = 10;
+const MAX_STEP_BACKOFF_MS: u64 = 60 * 1000;
+
+/// How a game process is recognized.
+///
+/// Serialised like `RuleMatcher`, as `{ "kind": "exeName", "value": "cs2.exe" }`. The
+/// aliases read targets stored before the names were camelCase.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", content = "value", rename_all = "camelCase")]
+pub enum GameMatcher {
+    /// Exact executable name, case-insensitive (`cs2.exe`).
+    #[serde(alias = "ExeName")]
+    ExeName(String),
+    /// Substring of the full executable path, case-insensitive.
+    #[serde(alias = "PathContains")]
+    PathContains(String),
+    /// Steam AppID, read from the environment Steam passes to the game.
+    #[serde(alias = "SteamAppId")]
+    SteamAppId(u32),
+}
+
+/// When and how often a session step is tried.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StepPolicy {
+    pub step: SessionStep,
+    /// Earliest time after the game started, e.g. to let the engine finish loading
+    /// before the affinity is set.
+    #[serde(default)]
+    pub delay_ms: u64,
+    /// Further attempts after a failure.
+    #[serde(default)]
+    pub retries: u32,
+    /// Wait before the first retry, doubled for every further one.
+    #[serde(default)]
+    pub backoff_ms: u64,
+    /// Lower runs first. Steps with the same order keep the built-in order.
+    #[serde(default)]
+    pub order: i32,
+}
+
+impl StepPolicy {
+    fn immediate(step: SessionStep) -> Self {
+        Self {
+            step,
+            delay_ms: 0,
+            retries: 0,
+            backoff_ms: 0,
+            order: 0,
+        }
+    }
+
+    /// Wait before the given retry (1 for the first).
+    pub fn retry_delay(&self, retry: u32) -> Duration {
+        let factor = 1u64 << retry.saturating_sub(1).min(MAX_STEP_RETRIES);
+        Duration::from_millis(self.backoff_ms.saturating_mul(factor))
+    }
+}
+
+/// What is applied while a game runs.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OptimizationProfile {
+    pub power_plan_guid: Option<String>,
+    /// Cores for the game's processes. Empty leaves the affinity alone.
+    pub affinity_cores: Vec<u32>,
+    /// Priority class for the game's processes.
+    pub priority: Option<ProcessPriority>,
+    pub prevent_parking: bool,
+    /// Vibrance while the game is in the foreground.
+    pub vibrance: Option<u32>,
+    /// Killed when the game starts.
+    pub kill_processes: Vec<String>,
+    /// Throttled with `throttle_limits` until the last game exits.
+    pub throttle_processes: Vec<String>,
+    pub throttle_limits: ThrottleLimits,
+    /// Steps whose failure undoes the ones already applied.
+    pub critical_steps: Vec<SessionStep>,
+    /// Delay, retries and order per step. Steps without a policy run right away, once.
+    pub step_policies: Vec<StepPolicy>,
+}
+
+impl OptimizationProfile {
+    /// The profile configured on the regular settings pages.
+    pub fn from_settings(settings: &FrontendSettings) -> Self {
+        let cpu = &settings.cpu_management;
+        let power_plan = &settings.power_plan_cs2;
+        Self {
+            power_plan_guid: (settings.power_plan_management_active && !power_plan.guid.is_empty())
+                .then(|| power_plan.guid.clone()),
+            affinity_cores: if cpu.enabled {
+                cpu.selected_cores.clone()
+            } else {
+                Vec::new()
+            },
+            priority: None,
+            prevent_parking: cpu.enabled && cpu.prevent_parking,
+            vibrance: settings
+                .vibrance
+                .enabled
+                .then_some(settings.vibrance.cs2_vibrance),
+            kill_processes: if settings.process_management_active {
+                settings.processes_to_kill.clone()
+            } else {
+                Vec::new()
+            },
+            throttle_processes: if settings.throttle.enabled {
+                settings.throttle.processes.clone()
+            } else {
+                Vec::new()
+            },
+            throttle_limits: ThrottleLimits {
+                cpu_percent: settings.throttle.cpu_percent,
+                memory_mb: settings.throttle.memory_mb,
+            },
+            critical_steps: Vec::new(),
+            step_policies: Vec::new(),
+        }
+    }
+
+    /// Whether the profile sets anything for the step.
+    pub fn uses(&self, step: SessionStep) -> bool {
+        match step {
+            SessionStep::PowerPlan => self.power_plan_guid.is_some(),
+            SessionStep::CoreParking => self.prevent_parking,
+            SessionStep::Affinity => !self.affinity_cores.is_empty(),
+            SessionStep::Priority => self.priority.is_some(),
+            SessionStep::KillProcesses => !self.kill_processes.is_empty(),
+            SessionStep::Throttle => !self.throttle_processes.is_empty(),
+        }
+    }
+
+    pub fn policy(&self, step: SessionStep) -> StepPolicy {
+        self.step_policies
+            .iter()
+            .find(|policy| policy.step == step)
+            .copied()
+            .unwrap_or_else(|| StepPolicy::immediate(step))
+    }
+
+    /// Steps in the order the session runs them.
+    pub fn ordered_steps(&self) -> Vec<StepPolicy> {
+        let mut policies: Vec<StepPolicy> = SessionStep::ALL
+            .iter()
+            .map(|step| self.policy(*step))
+            .collect();
+        // Stable, so equal orders keep the built-in order.
+        policies.sort_by_key(|policy| policy.order);
+        policies
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(guid) = &self.power_plan_guid {
+            if !crate::power::is_valid_guid(guid) {
+                return Err(format!("Invalid power plan GUID {}", guid));
+            }
+        }
+        if !self.affinity_cores.is_empty() {
+            processes::affinity_mask(0, &self.affinity_cores)?;
+        }
+        if let Some(level) = self.vibrance {
+            if level > 100 {
+                return Err(format!("Vibrance must be between 0 and 100, got {}", level));
+            }
+        }
+        if let Some(name) = self
+            .kill_processes
+            .iter()
+            .chain(&self.throttle_processes)
+            .find(|name| is_process_protected(name))
+        {
+            return Err(format!("{} is a protected process", name));
+        }
+        if !self.throttle_processes.is_empty() {
+            self.throttle_limits.validate()?;
+        }
+        let mut steps = HashSet::new();
+        for policy in &self.step_policies {
+            if !steps.insert(policy.step) {
+                return Err(format!("Duplicate policy for step {:?}", policy.step));
+            }
+            if policy.delay_ms > MAX_STEP_DELAY_MS {
+                return Err(format!(
+                    "Delay of {:?} must be at most {} ms",
+                    policy.step, MAX_STEP_DELAY_MS
+                ));
+            }
+            if policy.retries > MAX_STEP_RETRIES {
+                return Err(format!(
+                    "{:?} can be retried at most {} times",
+                    policy.step, MAX_STEP_RETRIES
+                ));
+            }
+            if policy.backoff_ms > MAX_STEP_BACKOFF_MS {
+                return Err(format!(
+                    "Backoff of {:?} must be at most {} ms",
+                    policy.step, MAX_STEP_BACKOFF_MS
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// A watched game.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GameTarget {
+    /// Stable ID used in events (`cs2`, `deadlock`, ...).
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// A process belongs to the game if any matcher applies. Processes the game starts
+    /// itself belong to it as well.
+    pub matchers: Vec<GameMatcher>,
+    /// `None` uses the active profile, or without one the settings pages (power plan,
+    /// CPU, vibrance, process management, throttling), so every game without its own
+    /// profile behaves like CS2.
+    #[serde(default)]
+    pub profile: Option<OptimizationProfile>,
+    /// Named profile for the game; takes precedence over `profile`.
+    #[serde(default)]
+    pub profile_id: Option<String>,
+}
+
+impl GameTarget {
+    /// The profile to apply right now.
+    pub fn resolve_profile(&self, settings: &FrontendSettings) -> OptimizationProfile {
+        self.profile
+            .clone()
+            .unwrap_or_else(|| OptimizationProfile::from_settings(settings))
+    }
+}
+
+fn builtin_target(id: &str, name: &str, enabled: bool, matchers: Vec<GameMatcher>) -> GameTarget {
+    GameTarget {
+        id: id.to_string(),
+        name: name.to_string(),
+        enabled,
+        matchers,
+        profile: None,
+        profile_id: None,
+    }
+}
+
+/// Targets used until the user saves their own list. Only CS2 is enabled, as before.
+pub fn default_targets() -> Vec<GameTarget> {
+    use GameMatcher::{ExeName, SteamAppId};
+    vec![
+        builtin_target(
+            "cs2",
+            "Counter-Strike 2",
+            true,
+            vec![ExeName("cs2.exe".into()), ExeName("cs2".into())],
+        ),
+        builtin_target(
+            "deadlock",
+            "Deadlock",
+            false,
+            vec![ExeName("project8.exe".into()), SteamAppId(1422450)],
+        ),
+        builtin_target(
+            "valorant",
+            "Valorant",
+            false,
+            vec![ExeName("VALORANT-Win64-Shipping.exe".into())],
+        ),
+        builtin_target(
+            "apex",
+            "Apex Legends",
+            false,
+            vec![
+                ExeName("r5apex.exe".into()),
+                ExeName("r5apex_dx12.exe".into()),
+                SteamAppId(1172470),
+            ],
+        ),
+    ]
+}
+
+fn validate_targets(targets: &[GameTarget]) -> Result<(), String> {
+    let mut ids = HashSet::new();
+    for target in targets {
+        if target.id.trim().is_empty() {
+            return Err(format!("Game '{}' has no ID", target.name));
+        }
+        if !ids.insert(target.id.as_str()) {
+            return Err(format!("Duplicate game ID '{}'", target.id));
+        }
+        if target.matchers.is_empty() {
+            return Err(format!("Game '{}' has no matchers", target.name));
+        }
+        for matcher in &target.matchers {
+            let valid = match matcher {
+                // Protected names are fine here: a matcher only recognizes the game, it
+                // never kills it.
+                GameMatcher::ExeName(name) => !name.trim().is_empty(),
+                GameMatcher::PathContains(part) => !part.trim().is_empty(),
+                GameMatcher::SteamAppId(id) => *id != 0,
+            };
+            if !valid {
+                return Err(format!(
+                    "Game '{}' has an invalid matcher {:?}",
+                    target.name, matcher
+                ));
+            }
+        }
+        if let Some(profile) = &target.profile {
+            profile
+                .validate()
+                .map_err(|e| format!("Game '{}': {}", target.name, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Path and environment of a process, read only if a matcher needs them.
+///
+/// Both require opening the process, which is too slow to do for every process on every
+/// start event when only executable names are configured.
+struct ProcessDetails<'a> {
+    sys: &'a mut System,
+    pid: Pid,
+    loaded: bool,
+}
+
+impl ProcessDetails<'_> {
+    fn get(&mut self) -> Option<&Process> {
+        if !self.loaded {
+            self.sys.refresh_processes_specifics(
+                ProcessesToUpdate::Some(&[self.pid]),
+                true,
+                ProcessRefreshKind::nothing()
+                    .with_exe(UpdateKind::Always)
+                    .with_environ(UpdateKind::Always),
+            );
+            self.loaded = true;
+        }
+        self.sys.process(self.pid)
+    }
+}
+
+fn matches(matcher: &GameMatcher, name: &str, details: &mut ProcessDetails) -> bool {
+    match matcher {
+        GameMatcher::ExeName(exe) => exe.eq_ignore_ascii_case(name),
+        GameMatcher::PathContains(part) => details.get().and_then(|p| p.exe()).is_some_and(|exe| {
+            exe.to_string_lossy()
+                .to_lowercase()
+                .contains(&part.to_lowercase())
+        }),
+        GameMatcher::SteamAppId(app_id) => details.get().is_some_and(|p| {
+            p.environ().iter().any(|variable| {
+                let variable = variable.to_string_lossy();
+                variable.split_once('=').is_some_and(|(key, value)| {
+                    STEAM_APP_ID_VARIABLES.contains(&key) && value.trim() == app_id.to_string()
+                })
+            })
+        }),
+    }
+}
+
+pub struct GameState {
+    targets: Mutex<Vec<GameTarget>>,
+    /// Bumped on every change so the monitor knows to classify running processes again.
+    revision: AtomicU64,
+}
+
+impl GameState {
+    pub fn load(app: &AppHandle) -> Self {
+        Self {
+            targets: Mutex::new(settings::load(app, TARGETS_KEY).unwrap_or_else(default_targets)),
+            revision: AtomicU64::new(0),
+        }
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Relaxed)
+    }
+
+    pub fn targets(&self) -> Vec<GameTarget> {
+        self.targets.lock().map(|t| t.clone()).unwrap_or_default()
+    }
+
+    pub fn target(&self, id: &str) -> Option<GameTarget> {
+        self.targets
+            .lock()
+            .ok()?
+            .iter()
+            .find(|target| target.id == id)
+            .cloned()
+    }
+
+    /// Returns the ID of the first enabled game the process belongs to.
+    pub fn classify(&self, sys: &mut System, pid: u32, name: &str) -> Option<String> {
+        let targets = self.targets.lock().ok()?;
+        let mut details = ProcessDetails {
+            sys,
+            pid: Pid::from_u32(pid),
+            loaded: false,
+        };
+        targets
+            .iter()
+            .filter(|target| target.enabled)
+            .find(|target| {
+                target
+                    .matchers
+                    .iter()
+                    .any(|matcher| matches(matcher, name, &mut details))
+            })
+            .map(|target| target.id.clone())
+    }
+}
+
+#[tauri::command]
+pub fn get_game_targets(state: tauri::State<GameState>) -> Result<Vec<GameTarget>, String> {
+    Ok(state.targets.lock().map_err(|e| e.to_string())?.clone())
+}
+
+/// Stores the watched games. Running processes are classified again right away.
+#[tauri::command]
+pub fn set_game_targets(
+    app: AppHandle,
+    state: tauri::State<GameState>,
+    targets: Vec<GameTarget>,
+) -> Result<(), String> {
+    validate_targets(&targets)
+        .and_then(|()| {
+            let profiles = app.state::<ProfileState>();
+            match targets.iter().find_map(|target| {
+                target
+                    .profile_id
+                    .as_ref()
+                    .filter(|id| profiles.get(id).is_none())
+                    .map(|id| (target, id))
+            }) {
+                Some((target, id)) => Err(format!(
+                    "Game '{}' uses the unknown profile '{}'",
+                    target.name, id
+                )),
+                None => Ok(()),
+            }
+        })
+        .inspect_err(|err_msg| {
+            let _ = app.emit("log-error", err_msg);
+        })?;
+    settings::save(&app, TARGETS_KEY, &targets).inspect_err(|err_msg| {
+        let _ = app.emit("log-error", err_msg);
+    })?;
+
+    let enabled = targets.iter().filter(|t| t.enabled).count();
+    *state.targets.lock().map_err(|e| e.to_string())? = targets;
+    state.revision.fetch_add(1, Ordering::Relaxed);
+
+    let _ = app.emit(
+        "log-info",
+        format!("Saved game targets, {} enabled", enabled),
+    );
+    Ok(())
+}
+
+/// Restores the built-in game list.
+#[tauri::command]
+pub fn reset_game_targets(app: AppHandle) -> Result<Vec<GameTarget>, String> {
+    let targets = default_targets();
+    set_game_targets(app.clone(), app.state::<GameState>(), targets.clone())?;
+    Ok(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(step: SessionStep, order: i32) -> StepPolicy {
+        StepPolicy {
+            order,
+            ..StepPolicy::immediate(step)
+        }
+    }
+
+    #[test]
+    fn default_targets_are_valid() {
+        assert_eq!(validate_targets(&default_targets()), Ok(()));
+    }
+
+    #[test]
+    fn matcher_uses_the_rule_shape() {
+        let matcher = GameMatcher::ExeName("cs2.exe".to_string());
+        let json = serde_json::to_value(&matcher).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "kind": "exeName", "value": "cs2.exe" })
+        );
+        let rule_json =
+            serde_json::to_value(crate::rules::RuleMatcher::ExeName("cs2.exe".to_string()))
+                .unwrap();
+        assert_eq!(json, rule_json);
+
+        // Targets stored with the old names still load.
+        let old: GameMatcher =
+            serde_json::from_value(serde_json::json!({ "kind": "SteamAppId", "value": 730 }))
+                .unwrap();
+        assert_eq!(old, GameMatcher::SteamAppId(730));
+    }
+
+    #[test]
+    fn retry_delay_doubles() {
+        let policy = StepPolicy {
+            retries: 3,
+            backoff_ms: 500,
+            ..StepPolicy::immediate(SessionStep::Affinity)
+        };
+        assert_eq!(policy.retry_delay(1), Duration::from_millis(500));
+        assert_eq!(policy.retry_delay(2), Duration::from_millis(1000));
+        assert_eq!(policy.retry_delay(3), Duration::from_millis(2000));
+        // Capped instead of overflowing.
+        assert_eq!(
+            policy.retry_delay(u32::MAX),
+            Duration::from_millis(500 << MAX_STEP_RETRIES)
+        );
+        assert_eq!(
+            StepPolicy::immediate(SessionStep::Affinity).retry_delay(1),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn steps_keep_the_built_in_order() {
+        let steps: Vec<SessionStep> = OptimizationProfile::default()
+            .ordered_steps()
+            .iter()
+            .map(|policy| policy.step)
+            .collect();
+        assert_eq!(steps, SessionStep::ALL);
+    }
+
+    #[test]
+    fn steps_sort_stably_by_order() {
+        let profile = OptimizationProfile {
+            step_policies: vec![
+                policy(SessionStep::Throttle, -1),
+                policy(SessionStep::PowerPlan, 1),
+                policy(SessionStep::Priority, -1),
+            ],
+            ..Default::default()
+        };
+        let steps: Vec<SessionStep> = profile
+            .ordered_steps()
+            .iter()
+            .map(|policy| policy.step)
+            .collect();
+        assert_eq!(
+            steps,
+            [
+                SessionStep::Priority,
+                SessionStep::Throttle,
+                SessionStep::CoreParking,
+                SessionStep::Affinity,
+                SessionStep::KillProcesses,
+                SessionStep::PowerPlan,
+            ]
+        );
+    }
+}
@@ -0,0 +1,387 @@
+use crate::process_stream::sleep_unless_stopped;
+use crate::processes::{self, is_process_protected, ProcessPriority};
+use crate::session::SessionEngine;
+use crate::settings;
+use crate::throttle::{ThrottleLimits, ThrottleState};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+use tauri::{AppHandle, Emitter, Manager};
+
+const CONFIG_KEY: &str = "cpuHogDetection";
+
+/// Time between two CPU samples while a session is active.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What happens automatically once a process is flagged.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum HogAction {
+    /// Only emit the `cpu-hog` event.
+    #[default]
+    Notify,
+    /// Set the priority class to Idle; restored when the session ends.
+    LowerPriority,
+    /// Suspend the process; resumed when the session ends.
+    Suspend,
+    /// Throttle with `throttle_limits`; released when the session ends.
+    Throttle,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HogDetectionConfig {
+    pub enabled: bool,
+    /// CPU usage in percent of the whole machine (all cores together).
+    pub threshold_percent: f32,
+    /// How long a process has to stay above the threshold before it is flagged.
+    pub duration_secs: u64,
+    pub action: HogAction,
+    /// Limits used by `HogAction::Throttle`.
+    pub throttle_limits: ThrottleLimits,
+    /// Process names that are never flagged, in addition to the protected processes.
+    pub ignore: Vec<String>,
+}
+
+impl Default for HogDetectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold_percent: 25.0,
+            duration_secs: 10,
+            action: HogAction::Notify,
+            throttle_limits: ThrottleLimits {
+                cpu_percent: Some(5),
+                memory_mb: None,
+            },
+            ignore: Vec::new(),
+        }
+    }
+}
+
+/// Payload of the `cpu-hog` event.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CpuHog {
+    /// Unix time in milliseconds.
+    pub timestamp: u64,
+    pub pid: u32,
+    pub name: String,
+    pub exe_path: Option<String>,
+    /// Usage at the moment it was flagged, in percent of the whole machine.
+    pub cpu_percent: f32,
+    pub above_threshold_secs: u64,
+    pub action: HogAction,
+    /// Set if the automatic action failed.
+    pub action_error: Option<String>,
+}
+
+/// An automatic action that has to be undone when the session ends.
+#[derive(Debug, PartialEq)]
+enum Mitigation {
+    Suspended(u32),
+    Priority { pid: u32, original: ProcessPriority },
+}
+
+impl Mitigation {
+    fn pid(&self) -> u32 {
+        match self {
+            Mitigation::Suspended(pid) | Mitigation::Priority { pid, .. } => *pid,
+        }
+    }
+}
+
+pub struct HogDetectionState {
+    config: Mutex<HogDetectionConfig>,
+    session_active: AtomicBool,
+    /// Processes flagged in the current session.
+    detected: Mutex<Vec<CpuHog>>,
+    mitigations: Mutex<Vec<Mitigation>>,
+}
+
+impl HogDetectionState {
+    /// Loads the stored config. Managed before the session starts, so a game that is
+    /// already running at launch gets hog detection too.
+    pub fn load(app: &AppHandle) -> Self {
+        let config: HogDetectionConfig = settings::load(app, CONFIG_KEY).unwrap_or_default();
+        Self {
+            config: Mutex::new(config),
+            session_active: AtomicBool::new(false),
+            detected: Mutex::new(Vec::new()),
+            mitigations: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+/// Starts the sampling thread. `HogDetectionState` has to be managed already.
+pub fn start_hog_detection(app: AppHandle, shutdown: Arc<AtomicBool>) {
+    thread::spawn(move || run_detection(&app, &shutdown));
+}
+
+/// Called by the session engine when the first game starts and when the last one exits.
+pub fn set_session_active(app: &AppHandle, active: bool) {
+    let Some(state) = app.try_state::<HogDetectionState>() else {
+        return;
+    };
+    if active {
+        if let Ok(mut detected) = state.detected.lock() {
+            detected.clear();
+        }
+        state.session_active.store(true, Ordering::Relaxed);
+    } else {
+        state.session_active.store(false, Ordering::Relaxed);
+        revert_mitigations(app, &state);
+    }
+}
+
+fn run_detection(app: &AppHandle, shutdown: &AtomicBool) {
+    let state = app.state::<HogDetectionState>();
+    let refresh_kind = ProcessRefreshKind::nothing()
+        .with_cpu()
+        .with_exe(UpdateKind::OnlyIfNotSet);
+    let cpu_count = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1) as f32;
+
+    let mut sys = System::new();
+    // PID -> (start of the current streak above the threshold, already flagged)
+    let mut streaks: HashMap<u32, (Instant, bool)> = HashMap::new();
+    let own_pid = std::process::id();
+
+    while !shutdown.load(Ordering::Relaxed) {
+        let config = state.config.lock().map(|c| c.clone()).unwrap_or_default();
+        if !config.enabled || !state.session_active.load(Ordering::Relaxed) {
+            // A fresh `System` next session, so old CPU times do not produce a spike.
+            streaks.clear();
+            sys = System::new();
+            sleep_unless_stopped(SAMPLE_INTERVAL, shutdown);
+            continue;
+        }
+
+        sys.refresh_processes_specifics(ProcessesToUpdate::All, true, refresh_kind);
+        let now = Instant::now();
+        let min_duration = Duration::from_secs(config.duration_secs);
+        // The games themselves are never flagged, however much CPU they use.
+        let game_pids: HashSet<u32> = app
+            .try_state::<SessionEngine>()
+            .map(|session| session.game_pids())
+            .unwrap_or_default();
+
+        let mut above = Vec::new();
+        for (pid, process) in sys.processes() {
+            let pid = pid.as_u32();
+            // `cpu_usage` is per core, the threshold is for the whole machine.
+            let cpu_percent = process.cpu_usage() / cpu_count;
+            if cpu_percent < config.threshold_percent || pid == own_pid || game_pids.contains(&pid)
+            {
+                continue;
+            }
+            let name = process.name().to_string_lossy();
+            if is_process_protected(&name)
+                || config.ignore.iter().any(|i| i.eq_ignore_ascii_case(&name))
+            {
+                continue;
+            }
+
+            above.push(pid);
+            let (since, flagged) = streaks.entry(pid).or_insert((now, false));
+            if *flagged || now.duration_since(*since) < min_duration {
+                continue;
+            }
+            *flagged = true;
+
+            let hog = CpuHog {
+                timestamp: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_millis() as u64)
+                    .unwrap_or(0),
+                pid,
+                name: name.into_owned(),
+                exe_path: process.exe().map(|p| p.to_string_lossy().into_owned()),
+                cpu_percent,
+                above_threshold_secs: now.duration_since(*since).as_secs(),
+                action: config.action,
+                action_error: mitigate(app, &state, pid, &config).err(),
+            };
+            report(app, &state, hog);
+        }
+
+        // A process that drops below the threshold starts a new streak next time.
+        streaks.retain(|pid, _| above.contains(pid));
+
+        sleep_unless_stopped(SAMPLE_INTERVAL, shutdown);
+    }
+}
+
+fn mitigate(
+    app: &AppHandle,
+    state: &HogDetectionState,
+    pid: u32,
+    config: &HogDetectionConfig,
+) -> Result<(), String> {
+    let mitigation = match config.action {
+        HogAction::Notify => return Ok(()),
+        HogAction::LowerPriority => {
+            let original = processes::read_priority(pid)?;
+            processes::apply_priority(pid, ProcessPriority::Idle)?;
+            Mitigation::Priority { pid, original }
+        }
+        HogAction::Suspend => {
+            processes::set_suspended(pid, true)?;
+            Mitigation::Suspended(pid)
+        }
+        HogAction::Throttle => {
+            // Released together with the session's other throttled processes.
+            return app
+                .state::<ThrottleState>()
+                .throttle(pid, &config.throttle_limits);
+        }
+    };
+    let mut mitigations = state.mitigations.lock().map_err(|e| e.to_string())?;
+    record_mitigation(&mut mitigations, mitigation);
+    Ok(())
+}
+
+/// Keeps only the first mitigation per process. A process flagged again is already at
+/// Idle, and restoring that "original" after the real one would leave it at Idle.
+fn record_mitigation(mitigations: &mut Vec<Mitigation>, mitigation: Mitigation) {
+    if !mitigations.iter().any(|m| m.pid() == mitigation.pid()) {
+        mitigations.push(mitigation);
+    }
+}
+
+fn report(app: &AppHandle, state: &HogDetectionState, hog: CpuHog) {
+    let message = format!(
+        "{} (PID {}) used {:.0} % CPU for {} s during the session",
+        hog.name, hog.pid, hog.cpu_percent, hog.above_threshold_secs
+    );
+    match &hog.action_error {
+        None => {
+            let _ = app.emit("log-warn", format!("{}, action: {:?}", message, hog.action));
+        }
+        Some(error) => {
+            let _ = app.emit(
+                "log-error",
+                format!("{}, action {:?} failed: {}", message, hog.action, error),
+            );
+        }
+    }
+    let _ = app.emit("cpu-hog", &hog);
+    if let Ok(mut detected) = state.detected.lock() {
+        detected.push(hog);
+    }
+}
+
+/// Resumes suspended processes and restores lowered priorities.
+///
+/// Runs when the last game exits and when the app quits, so nothing stays suspended.
+pub fn revert_mitigations(app: &AppHandle, state: &HogDetectionState) {
+    let mitigations = match state.mitigations.lock() {
+        Ok(mut mitigations) => std::mem::take(&mut *mitigations),
+        Err(_) => return,
+    };
+
+    for mitigation in mitigations {
+        let (pid, result) = match mitigation {
+            Mitigation::Suspended(pid) => (pid, processes::set_suspended(pid, false)),
+            Mitigation::Priority { pid, original } => {
+                (pid, processes::apply_priority(pid, original))
+            }
+        };
+        match result {
+            Ok(()) => {
+                let _ = app.emit(
+                    "log-info",
+                    format!("Reverted CPU-hog action for PID {}", pid),
+                );
+            }
+            // The process may have exited in the meantime, which is fine.
+            Err(error) => {
+                let _ = app.emit(
+                    "log-warn",
+                    format!("Could not revert CPU-hog action for PID {}: {}", pid, error),
+                );
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_hog_detection_config(
+    state: tauri::State<HogDetectionState>,
+) -> Result<HogDetectionConfig, String> {
+    Ok(state.config.lock().map_err(|e| e.to_string())?.clone())
+}
+
+/// Stores the detection config. Takes effect with the next sample.
+#[tauri::command]
+pub fn set_hog_detection_config(
+    app: AppHandle,
+    state: tauri::State<HogDetectionState>,
+    config: HogDetectionConfig,
+) -> Result<(), String> {
+    if !(1.0..=100.0).contains(&config.threshold_percent) || config.duration_secs == 0 {
+        let err_msg = format!(
+            "Invalid CPU-hog config: threshold {} %, duration {} s",
+            config.threshold_percent, config.duration_secs
+        );
+        let _ = app.emit("log-error", &err_msg);
+        return Err(err_msg);
+    }
+    if config.action == HogAction::Throttle {
+        config.throttle_limits.validate().inspect_err(|err_msg| {
+            let _ = app.emit("log-error", err_msg);
+        })?;
+    }
+    settings::save(&app, CONFIG_KEY, &config).inspect_err(|err_msg| {
+        let _ = app.emit("log-error", err_msg);
+    })?;
+    *state.config.lock().map_err(|e| e.to_string())? = config;
+    Ok(())
+}
+
+/// Returns the processes flagged in the current (or last) session.
+#[tauri::command]
+pub fn get_cpu_hogs(state: tauri::State<HogDetectionState>) -> Result<Vec<CpuHog>, String> {
+    Ok(state.detected.lock().map_err(|e| e.to_string())?.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_first_mitigation_per_process() {
+        let mut mitigations = Vec::new();
+        record_mitigation(
+            &mut mitigations,
+            Mitigation::Priority {
+                pid: 1,
+                original: ProcessPriority::Normal,
+            },
+        );
+        record_mitigation(&mut mitigations, Mitigation::Suspended(2));
+        // Flagged again after dropping below the threshold, now at Idle.
+        record_mitigation(
+            &mut mitigations,
+            Mitigation::Priority {
+                pid: 1,
+                original: ProcessPriority::Idle,
+            },
+        );
+        assert_eq!(
+            mitigations,
+            [
+                Mitigation::Priority {
+                    pid: 1,
+                    original: ProcessPriority::Normal,
+                },
+                Mitigation::Suspended(2),
+            ]
+        );
+    }
+}
@@ -0,0 +1,473 @@
+use crate::session::{SessionEngine, SessionFeature, SessionMessage};
+use crate::{profiles, settings};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::mpsc::{self, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use winapi::shared::winerror::ERROR_HOTKEY_ALREADY_REGISTERED;
+use winapi::um::processthreadsapi::GetCurrentThreadId;
+use winapi::um::winuser::{
+    GetMessageW, PeekMessageW, PostThreadMessageW, RegisterHotKey, UnregisterHotKey, MOD_ALT,
+    MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT, MOD_WIN, MSG, PM_NOREMOVE, VK_DELETE, VK_DOWN, VK_END,
+    VK_F1, VK_HOME, VK_INSERT, VK_LEFT, VK_NEXT, VK_NUMPAD0, VK_PAUSE, VK_PRIOR, VK_RIGHT,
+    VK_SPACE, VK_UP, WM_APP, WM_HOTKEY,
+};
+
+const HOTKEYS_KEY: &str = "hotkeys";
+
+/// How long `set_hotkeys` waits for the hotkey thread to register the new shortcuts.
+const REGISTER_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum HotkeyAction {
+    ToggleVibrance,
+    CycleProfile,
+    /// Snoozes the automation until pressed again or the next game starts.
+    PauseAutomation,
+    ForceApply,
+    ForceRevert,
+}
+
+impl HotkeyAction {
+    fn label(self) -> &'static str {
+        match self {
+            HotkeyAction::ToggleVibrance => "toggle vibrance",
+            HotkeyAction::CycleProfile => "cycle profile",
+            HotkeyAction::PauseAutomation => "pause automation",
+            HotkeyAction::ForceApply => "force apply",
+            HotkeyAction::ForceRevert => "force revert",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HotkeyBinding {
+    pub action: HotkeyAction,
+    /// E.g. "Ctrl+Shift+F9". Modifiers are Ctrl, Alt, Shift and Win.
+    pub shortcut: String,
+}
+
+/// A shortcut that could not be registered, usually because another app owns it.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HotkeyConflict {
+    pub action: HotkeyAction,
+    pub shortcut: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HotkeyStatus {
+    pub bindings: Vec<HotkeyBinding>,
+    /// Bindings that are stored but not active.
+    pub conflicts: Vec<HotkeyConflict>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Shortcut {
+    modifiers: u32,
+    key: u32,
+}
+
+/// Parses "Ctrl+Alt+V". Letters, digits and navigation keys need a modifier, so a
+/// hotkey never swallows plain typing; F1 to F24 work alone.
+fn parse_shortcut(text: &str) -> Result<Shortcut, String> {
+    let mut modifiers = 0;
+    let mut key = None;
+    for part in text.split('+').map(str::trim) {
+        let upper = part.to_ascii_uppercase();
+        let modifier = match upper.as_str() {
+            "CTRL" | "CONTROL" => MOD_CONTROL,
+            "ALT" => MOD_ALT,
+            "SHIFT" => MOD_SHIFT,
+            "WIN" | "SUPER" | "META" => MOD_WIN,
+            _ => 0,
+        };
+        if modifier != 0 {
+            modifiers |= modifier as u32;
+            continue;
+        }
+        if key.is_some() {
+            return Err(format!("'{}' has more than one key", text));
+        }
+        key =
+            Some(parse_key(&upper).ok_or_else(|| format!("Unknown key '{}' in '{}'", part, text))?);
+    }
+    let key = key.ok_or_else(|| format!("'{}' has no key", text))?;
+    let function_key = (VK_F1 as u32..VK_F1 as u32 + 24).contains(&key);
+    if modifiers == 0 && !function_key {
+        return Err(format!(
+            "'{}' needs Ctrl, Alt, Shift or Win, only F1 to F24 work alone",
+            text
+        ));
+    }
+    Ok(Shortcut { modifiers, key })
+}
+
+/// Virtual key code of an upper case key name.
+fn parse_key(name: &str) -> Option<u32> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.clone().next()) {
+        if c.is_ascii_uppercase() || c.is_ascii_digit() {
+            // Virtual key codes of letters and digits are their ASCII codes.
+            return Some(c as u32);
+        }
+    }
+    if let Some(n) = name.strip_prefix('F').and_then(|n| n.parse::<u32>().ok()) {
+        return (1..=24).contains(&n).then(|| VK_F1 as u32 + n - 1);
+    }
+    if let Some(n) = name
+        .strip_prefix("NUMPAD")
+        .and_then(|n| n.parse::<u32>().ok())
+    {
+        return (n <= 9).then(|| VK_NUMPAD0 as u32 + n);
+    }
+    let key = match name {
+        "SPACE" => VK_SPACE,
+        "INSERT" => VK_INSERT,
+        "DELETE" => VK_DELETE,
+        "HOME" => VK_HOME,
+        "END" => VK_END,
+        "PAGEUP" => VK_PRIOR,
+        "PAGEDOWN" => VK_NEXT,
+        "UP" => VK_UP,
+        "DOWN" => VK_DOWN,
+        "LEFT" => VK_LEFT,
+        "RIGHT" => VK_RIGHT,
+        "PAUSE" => VK_PAUSE,
+        _ => return None,
+    };
+    Some(key as u32)
+}
+
+/// Checks the bindings and parses their shortcuts.
+fn validate_bindings(bindings: &[HotkeyBinding]) -> Result<Vec<Shortcut>, String> {
+    let mut actions = HashSet::new();
+    let mut shortcuts = HashSet::new();
+    bindings
+        .iter()
+        .map(|binding| {
+            if !actions.insert(binding.action) {
+                return Err(format!(
+                    "More than one shortcut for {}",
+                    binding.action.label()
+                ));
+            }
+            let shortcut = parse_shortcut(&binding.shortcut)?;
+            if !shortcuts.insert(shortcut) {
+                return Err(format!("'{}' is used twice", binding.shortcut));
+            }
+            Ok(shortcut)
+        })
+        .collect()
+}
+
+type RegisterRequest = (Vec<(HotkeyAction, Shortcut)>, Sender<Vec<Option<String>>>);
+
+pub struct HotkeyState {
+    bindings: Mutex<Vec<HotkeyBinding>>,
+    conflicts: Mutex<Vec<HotkeyConflict>>,
+    requests: Sender<RegisterRequest>,
+    /// Hotkeys belong to the thread that registered them, so all registration happens
+    /// on that thread; it is woken with `WM_APP` to pick up a request.
+    thread_id: u32,
+}
+
+impl HotkeyState {
+    /// Starts the hotkey thread and registers the stored bindings.
+    pub fn start(app: AppHandle) -> Self {
+        let (requests, receiver) = mpsc::channel::<RegisterRequest>();
+        let (id_sender, id_receiver) = mpsc::channel();
+        let thread_app = app.clone();
+        thread::spawn(move || unsafe {
+            let mut msg: MSG = std::mem::zeroed();
+            // Creates the thread's message queue, so posts are not lost.
+            PeekMessageW(&mut msg, std::ptr::null_mut(), WM_APP, WM_APP, PM_NOREMOVE);
+            let _ = id_sender.send(GetCurrentThreadId());
+
+            // The hotkey ID is the index.
+            let mut registered: Vec<HotkeyAction> = Vec::new();
+            while GetMessageW(&mut msg, std::ptr::null_mut(), 0, 0) > 0 {
+                match msg.message {
+                    WM_HOTKEY => {
+                        if let Some(&action) = registered.get(msg.wParam) {
+                            trigger(&thread_app, action);
+                        }
+                    }
+                    WM_APP => {
+                        while let Ok((shortcuts, reply)) = receiver.try_recv() {
+                            for id in 0..registered.len() {
+                                UnregisterHotKey(std::ptr::null_mut(), id as i32);
+                            }
+                            registered.clear();
+                            let results = shortcuts
+                                .into_iter()
+                                .enumerate()
+                                .map(|(id, (action, shortcut))| {
+                                    registered.push(action);
+                                    register(id as i32, shortcut)
+                                })
+                                .collect();
+                            let _ = reply.send(results);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        let state = Self {
+            bindings: Mutex::new(Vec::new()),
+            conflicts: Mutex::new(Vec::new()),
+            requests,
+            thread_id: id_receiver.recv().unwrap_or(0),
+        };
+        let stored: Vec<HotkeyBinding> = settings::load(&app, HOTKEYS_KEY).unwrap_or_default();
+        // If a stored binding no longer parses, none are registered until they are saved
+        // again, rather than a partial set.
+        match validate_bindings(&stored) {
+            Ok(shortcuts) => {
+                if let Err(e) = state.apply(&app, stored, shortcuts) {
+                    let _ = app.emit("log-warn", format!("Failed to register hotkeys: {}", e));
+                }
+            }
+            Err(e) => {
+                let _ = app.emit("log-warn", format!("Ignoring stored hotkeys: {}", e));
+            }
+        }
+        state
+    }
+
+    /// Replaces the registered shortcuts and returns the ones that failed.
+    fn apply(
+        &self,
+        app: &AppHandle,
+        bindings: Vec<HotkeyBinding>,
+        shortcuts: Vec<Shortcut>,
+    ) -> Result<Vec<HotkeyConflict>, String> {
+        let (reply, results) = mpsc::channel();
+        let request = bindings
+            .iter()
+            .map(|binding| binding.action)
+            .zip(shortcuts)
+            .collect();
+        self.requests
+            .send((request, reply))
+            .map_err(|e| e.to_string())?;
+        if unsafe { PostThreadMessageW(self.thread_id, WM_APP, 0, 0) } == 0 {
+            return Err("Hotkey thread is not running".to_string());
+        }
+        let results = results
+            .recv_timeout(REGISTER_TIMEOUT)
+            .map_err(|_| "Hotkey thread did not respond".to_string())?;
+
+        let conflicts: Vec<HotkeyConflict> = bindings
+            .iter()
+            .zip(results)
+            .filter_map(|(binding, result)| {
+                result.map(|reason| HotkeyConflict {
+                    action: binding.action,
+                    shortcut: binding.shortcut.clone(),
+                    reason,
+                })
+            })
+            .collect();
+        for conflict in &conflicts {
+            let _ = app.emit(
+                "log-warn",
+                format!(
+                    "Hotkey {} for {} is not active: {}",
+                    conflict.shortcut,
+                    conflict.action.label(),
+                    conflict.reason
+                ),
+            );
+        }
+        *self.bindings.lock().map_err(|e| e.to_string())? = bindings;
+        *self.conflicts.lock().map_err(|e| e.to_string())? = conflicts.clone();
+        Ok(conflicts)
+    }
+}
+
+/// Registers one shortcut on the current thread; returns why it failed.
+unsafe fn register(id: i32, shortcut: Shortcut) -> Option<String> {
+    let modifiers = shortcut.modifiers | MOD_NOREPEAT as u32;
+    if RegisterHotKey(std::ptr::null_mut(), id, modifiers, shortcut.key) != 0 {
+        return None;
+    }
+    let error = std::io::Error::last_os_error();
+    Some(
+        if error.raw_os_error() == Some(ERROR_HOTKEY_ALREADY_REGISTERED as i32) {
+            "already used by another application".to_string()
+        } else {
+            error.to_string()
+        },
+    )
+}
+
+fn trigger(app: &AppHandle, action: HotkeyAction) {
+    let Some(session) = app.try_state::<SessionEngine>() else {
+        return;
+    };
+    let _ = app.emit("log-info", format!("Hotkey: {}", action.label()));
+    match action {
+        HotkeyAction::ToggleVibrance => {
+            session.send(SessionMessage::ToggleFeature(SessionFeature::Vibrance))
+        }
+        HotkeyAction::CycleProfile => profiles::cycle(app),
+        HotkeyAction::PauseAutomation => {
+            let snoozed = session.status().is_ok_and(|status| status.snooze.is_some());
+            session.send(if snoozed {
+                SessionMessage::Unsnooze
+            } else {
+                SessionMessage::Snooze(None)
+            });
+        }
+        HotkeyAction::ForceApply => session.send(SessionMessage::ForceApply),
+        HotkeyAction::ForceRevert => session.send(SessionMessage::RevertNow),
+    }
+}
+
+#[tauri::command]
+pub fn get_hotkeys(state: tauri::State<HotkeyState>) -> Result<HotkeyStatus, String> {
+    Ok(HotkeyStatus {
+        bindings: state.bindings.lock().map_err(|e| e.to_string())?.clone(),
+        conflicts: state.conflicts.lock().map_err(|e| e.to_string())?.clone(),
+    })
+}
+
+/// Stores and registers the bindings. Shortcuts taken by other apps are stored anyway
+/// and returned as conflicts; they are tried again on the next start.
+#[tauri::command]
+pub fn set_hotkeys(
+    app: AppHandle,
+    state: tauri::State<HotkeyState>,
+    bindings: Vec<HotkeyBinding>,
+) -> Result<Vec<HotkeyConflict>, String> {
+    let shortcuts = validate_bindings(&bindings).inspect_err(|err_msg| {
+        let _ = app.emit("log-error", err_msg);
+    })?;
+    settings::save(&app, HOTKEYS_KEY, &bindings).inspect_err(|err_msg| {
+        let _ = app.emit("log-error", err_msg);
+    })?;
+    let count = bindings.len();
+    let conflicts = state
+        .apply(&app, bindings, shortcuts)
+        .inspect_err(|err_msg| {
+            let _ = app.emit("log-error", err_msg);
+        })?;
+    let _ = app.emit(
+        "log-info",
+        format!(
+            "Saved hotkeys, {} of {} active",
+            count - conflicts.len(),
+            count
+        ),
+    );
+    Ok(conflicts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(action: HotkeyAction, shortcut: &str) -> HotkeyBinding {
+        HotkeyBinding {
+            action,
+            shortcut: shortcut.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_modifiers_and_keys() {
+        let shortcut = parse_shortcut("Ctrl+Shift+V").unwrap();
+        assert_eq!(shortcut.modifiers, (MOD_CONTROL | MOD_SHIFT) as u32);
+        assert_eq!(shortcut.key, 'V' as u32);
+
+        // Case, spaces and order do not matter.
+        assert_eq!(parse_shortcut(" shift + ctrl + v ").unwrap(), shortcut);
+        assert_eq!(
+            parse_shortcut("Win+Numpad5").unwrap(),
+            Shortcut {
+                modifiers: MOD_WIN as u32,
+                key: VK_NUMPAD0 as u32 + 5,
+            }
+        );
+        assert_eq!(parse_shortcut("Alt+PageUp").unwrap().key, VK_PRIOR as u32);
+    }
+
+    #[test]
+    fn letters_and_digits_need_a_modifier() {
+        assert!(parse_shortcut("V").is_err());
+        assert!(parse_shortcut("7").is_err());
+        assert!(parse_shortcut("Space").is_err());
+        assert!(parse_shortcut("Alt+7").is_ok());
+    }
+
+    #[test]
+    fn function_keys_work_alone() {
+        assert_eq!(parse_shortcut("F1").unwrap().key, VK_F1 as u32);
+        assert_eq!(parse_shortcut("F13").unwrap().key, VK_F1 as u32 + 12);
+        assert_eq!(
+            parse_shortcut("F24").unwrap(),
+            Shortcut {
+                modifiers: 0,
+                key: VK_F1 as u32 + 23,
+            }
+        );
+        assert!(parse_shortcut("F25").is_err());
+        assert!(parse_shortcut("F0").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_shortcuts() {
+        assert!(parse_shortcut("").is_err());
+        assert!(parse_shortcut("Ctrl+Alt").is_err());
+        assert!(parse_shortcut("Ctrl+A+B").is_err());
+        assert!(parse_shortcut("Ctrl+Escape").is_err());
+        assert!(parse_shortcut("Ctrl+Numpad10").is_err());
+    }
+
+    #[test]
+    fn validates_bindings() {
+        let shortcuts = validate_bindings(&[
+            binding(HotkeyAction::ToggleVibrance, "Ctrl+Shift+V"),
+            binding(HotkeyAction::ForceRevert, "F13"),
+        ])
+        .unwrap();
+        assert_eq!(shortcuts.len(), 2);
+        assert!(validate_bindings(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_duplicate_actions() {
+        let error = validate_bindings(&[
+            binding(HotkeyAction::CycleProfile, "F13"),
+            binding(HotkeyAction::CycleProfile, "F14"),
+        ])
+        .unwrap_err();
+        assert_eq!(error, "More than one shortcut for cycle profile");
+    }
+
+    #[test]
+    fn rejects_duplicate_shortcuts() {
+        // The same shortcut written differently.
+        let error = validate_bindings(&[
+            binding(HotkeyAction::ForceApply, "Ctrl+Alt+A"),
+            binding(HotkeyAction::ForceRevert, "alt+ctrl+a"),
+        ])
+        .unwrap_err();
+        assert_eq!(error, "'alt+ctrl+a' is used twice");
+    }
+
+    #[test]
+    fn rejects_invalid_shortcuts_in_bindings() {
+        assert!(validate_bindings(&[binding(HotkeyAction::PauseAutomation, "P")]).is_err());
+    }
+}
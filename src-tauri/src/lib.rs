@@ -1,13 +1,29 @@
+mod bloatware;
 mod cpu_parking;
 mod cs2monitoring;
+mod game_window;
+mod games;
+mod hog_detection;
+mod hotkeys;
+mod pe_version;
 mod power;
+mod process_groups;
+mod process_stream;
+mod process_watcher;
 mod processes;
+mod profiles;
+mod rules;
+mod session;
+mod settings;
+mod shutdown;
+mod system_events;
+mod throttle;
+mod tray;
+mod verification;
 mod vibrance;
 
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
-use tauri::menu::{Menu, MenuItem};
-use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
 use tauri::{Manager, WindowEvent};
 
 struct AppSettingsState {
@@ -38,7 +54,12 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_process::init())
-        .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            // `--profile` switches the running instance instead of opening a window.
+            if args.iter().any(|arg| arg.starts_with("--profile")) {
+                profiles::handle_args(app, &args);
+                return;
+            }
             let _ = app
                 .get_webview_window("main")
                 .expect("no main window")
@@ -50,52 +71,34 @@ pub fn run() {
         ))
         .plugin(tauri_plugin_store::Builder::default().build())
         .setup(move |app| {
-            let shutdown_for_thread = shutdown.clone();
-            cs2monitoring::start_monitor_thread(app.handle().clone(), shutdown_for_thread);
-
+            // Everything the worker threads look up has to be managed before they start.
+            app.manage(games::GameState::load(app.handle()));
+            app.manage(profiles::ProfileState::load(app.handle()));
+            app.manage(process_stream::ProcessStreamState::default());
+            app.manage(process_groups::ProcessGroupState::default());
+            app.manage(pe_version::ExecutableMetadataCache::default());
+            app.manage(throttle::ThrottleState::default());
+            app.manage(rules::RuleEngineState::load(app.handle()));
+            app.manage(hog_detection::HogDetectionState::load(app.handle()));
             app.manage(AppSettingsState {
                 minimize_to_tray: Mutex::new(true),
             });
+            app.manage(ShutdownState {
+                flag: shutdown.clone(),
+            });
+            profiles::handle_args(app.handle(), &std::env::args().collect::<Vec<_>>());
 
-            app.manage(ShutdownState { flag: shutdown });
-
-            let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-            let show_i = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&show_i, &quit_i])?;
+            app.manage(session::SessionEngine::start(app.handle().clone()));
+            app.manage(cs2monitoring::MonitorService::start(
+                app.handle().clone(),
+                shutdown.clone(),
+            ));
+            rules::start_rule_engine(app.handle().clone(), shutdown.clone());
+            hog_detection::start_hog_detection(app.handle().clone(), shutdown);
+            system_events::start(app.handle().clone());
 
-            let _tray = TrayIconBuilder::with_id("tray")
-                .icon(app.default_window_icon().unwrap().clone())
-                .menu(&menu)
-                .show_menu_on_left_click(false)
-                .on_menu_event(|app, event| match event.id().as_ref() {
-                    "quit" => {
-                        app.exit(0);
-                    }
-                    "show" => {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.unminimize();
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                        }
-                    }
-                    _ => {}
-                })
-                .on_tray_icon_event(|tray, event| {
-                    if let TrayIconEvent::Click {
-                        button: MouseButton::Left,
-                        button_state: MouseButtonState::Up,
-                        ..
-                    } = event
-                    {
-                        let app = tray.app_handle();
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.unminimize();
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                        }
-                    }
-                })
-                .build(app)?;
+            tray::create(app)?;
+            app.manage(hotkeys::HotkeyState::start(app.handle().clone()));
 
             let app_handle = app.handle().clone();
             let Some(window) = app.get_webview_window("main") else {
@@ -128,7 +131,52 @@ pub fn run() {
             processes::get_processes,
             processes::terminate_process,
             processes::get_cpu_count,
+            processes::get_protected_processes,
             processes::set_process_affinity,
+            processes::set_process_priority,
+            processes::suspend_process,
+            processes::resume_process,
+            process_groups::get_process_groups,
+            process_groups::terminate_process_group,
+            process_groups::suspend_process_group,
+            process_groups::resume_process_group,
+            process_groups::set_process_group_priority,
+            bloatware::get_bloatware_catalog,
+            bloatware::suggest_kill_list,
+            rules::get_process_rules,
+            rules::set_process_rules,
+            rules::set_rule_engine_enabled,
+            rules::get_rule_engine_status,
+            rules::get_rule_hits,
+            rules::clear_rule_hits,
+            throttle::throttle_processes,
+            throttle::release_throttled_processes,
+            throttle::get_throttled_processes,
+            hog_detection::get_hog_detection_config,
+            hog_detection::set_hog_detection_config,
+            hog_detection::get_cpu_hogs,
+            games::get_game_targets,
+            games::set_game_targets,
+            games::reset_game_targets,
+            cs2monitoring::pause_monitor,
+            cs2monitoring::resume_monitor,
+            cs2monitoring::set_monitor_interval,
+            cs2monitoring::set_focus_config,
+            cs2monitoring::get_game_status,
+            session::preview_session_plan,
+            session::set_session_feature,
+            session::snooze_automation,
+            session::resume_automation,
+            session::revert_session,
+            hotkeys::get_hotkeys,
+            hotkeys::set_hotkeys,
+            profiles::get_profiles,
+            profiles::set_profiles,
+            profiles::set_active_profile,
+            #[cfg(debug_assertions)]
+            system_events::simulate_system_event,
+            process_stream::subscribe_processes,
+            process_stream::unsubscribe_processes,
             vibrance::apply_vibrance_to_focused_display,
             vibrance::apply_vibrance,
             vibrance::check_nvidia_gpu,
@@ -139,9 +187,7 @@ pub fn run() {
         .expect("error while building tauri application")
         .run(|app, event| {
             if let tauri::RunEvent::Exit = event {
-                if let Some(state) = app.try_state::<ShutdownState>() {
-                    state.flag.store(true, Ordering::Relaxed);
-                }
+                shutdown::revert_all(app);
             }
         });
 }
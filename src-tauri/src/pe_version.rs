@@ -0,0 +1,533 @@
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// ? ALTERNATIVE: Why not `GetFileVersionInfoW`/`VerQueryValueW`?
+// A pure Rust parser works on every platform and can be tested against sample binaries
+// without a Windows machine. It only reads what it needs (headers, section table,
+// resource tree), never the whole file.
+
+/// Resource type ID of `VS_VERSIONINFO` (`RT_VERSION`).
+const RT_VERSION: u32 = 16;
+const IMAGE_DIRECTORY_ENTRY_RESOURCE: usize = 2;
+const VS_FIXEDFILEINFO_SIGNATURE: u32 = 0xFEEF_04BD;
+
+/// Upper bound for a single `VS_VERSIONINFO` block. Real ones are a few KB.
+const MAX_VERSION_INFO_SIZE: usize = 64 * 1024;
+/// Guards against malformed files with absurd section or entry counts.
+const MAX_SECTIONS: usize = 96;
+const MAX_RESOURCE_ENTRIES: usize = 4096;
+
+/// Version metadata from the `VS_VERSIONINFO` resource of an executable.
+///
+/// Every field is optional: many binaries only ship some of them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutableMetadata {
+    pub file_description: Option<String>,
+    pub company_name: Option<String>,
+    pub product_name: Option<String>,
+    /// `FileVersion` string, or the numeric version from `VS_FIXEDFILEINFO` if it is missing.
+    pub file_version: Option<String>,
+    pub product_version: Option<String>,
+}
+
+/// Random access to the bytes of a PE file.
+///
+/// Implemented for in-memory buffers and for files, so the parser never has to load a
+/// multi-hundred-megabyte game executable into memory.
+pub trait ReadAt {
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<()>;
+}
+
+impl ReadAt for &[u8] {
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<()> {
+        let start = usize::try_from(offset)?;
+        let end = start
+            .checked_add(buf.len())
+            .ok_or_else(|| anyhow!("Read out of bounds"))?;
+        let src = self
+            .get(start..end)
+            .ok_or_else(|| anyhow!("Unexpected end of file at offset {}", offset))?;
+        buf.copy_from_slice(src);
+        Ok(())
+    }
+}
+
+impl ReadAt for File {
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<()> {
+        self.seek(SeekFrom::Start(offset))?;
+        self.read_exact(buf)?;
+        Ok(())
+    }
+}
+
+/// Reads the version metadata of the executable at `path`.
+pub fn read_from_path(path: &Path) -> Result<ExecutableMetadata> {
+    let mut file = File::open(path)?;
+    read_metadata(&mut file)
+}
+
+/// Parses the PE headers, walks the resource tree to `RT_VERSION` and decodes it.
+///
+/// Works on any `ReadAt` source, e.g. `&mut &bytes[..]` for an image held in memory.
+pub fn read_metadata<R: ReadAt>(source: &mut R) -> Result<ExecutableMetadata> {
+    let image = PeImage::parse(source)?;
+    let version_info = image.read_version_resource(source)?;
+    parse_version_info(&version_info)
+}
+
+/// Section table of a PE file plus the location of the resource directory.
+struct PeImage {
+    sections: Vec<Section>,
+    resource_rva: u32,
+}
+
+struct Section {
+    virtual_address: u32,
+    virtual_size: u32,
+    raw_offset: u32,
+    raw_size: u32,
+}
+
+impl PeImage {
+    fn parse<R: ReadAt>(source: &mut R) -> Result<Self> {
+        let dos = read_vec(source, 0, 64)?;
+        if &dos[0..2] != b"MZ" {
+            bail!("Not a PE file (missing MZ signature)");
+        }
+        let pe_offset = u64::from(le_u32(&dos, 0x3C)?);
+
+        // Signature (4) + COFF file header (20)
+        let coff = read_vec(source, pe_offset, 24)?;
+        if &coff[0..4] != b"PE\0\0" {
+            bail!("Not a PE file (missing PE signature)");
+        }
+        let section_count = usize::from(le_u16(&coff, 6)?);
+        let optional_header_size = usize::from(le_u16(&coff, 20)?);
+        if section_count > MAX_SECTIONS {
+            bail!("Too many sections ({})", section_count);
+        }
+
+        let optional_offset = pe_offset + 24;
+        let optional = read_vec(source, optional_offset, optional_header_size)?;
+        // PE32 and PE32+ differ in the size of a few fields before the data directories.
+        let (rva_count_offset, directories_offset) = match le_u16(&optional, 0)? {
+            0x10B => (92, 96),
+            0x20B => (108, 112),
+            magic => bail!("Unknown optional header magic {:#x}", magic),
+        };
+        let rva_count = le_u32(&optional, rva_count_offset)? as usize;
+        if rva_count <= IMAGE_DIRECTORY_ENTRY_RESOURCE {
+            bail!("No resource directory");
+        }
+        let resource_rva = le_u32(
+            &optional,
+            directories_offset + IMAGE_DIRECTORY_ENTRY_RESOURCE * 8,
+        )?;
+        if resource_rva == 0 {
+            bail!("No resource directory");
+        }
+
+        let table = read_vec(
+            source,
+            optional_offset + optional_header_size as u64,
+            section_count * 40,
+        )?;
+        let sections = table
+            .chunks_exact(40)
+            .map(|s| {
+                Ok(Section {
+                    virtual_size: le_u32(s, 8)?,
+                    virtual_address: le_u32(s, 12)?,
+                    raw_size: le_u32(s, 16)?,
+                    raw_offset: le_u32(s, 20)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            sections,
+            resource_rva,
+        })
+    }
+
+    /// Translates a relative virtual address into a file offset.
+    fn rva_to_offset(&self, rva: u32) -> Result<u64> {
+        self.sections
+            .iter()
+            .find(|s| {
+                let size = s.virtual_size.max(s.raw_size);
+                rva >= s.virtual_address && rva - s.virtual_address < size
+            })
+            .map(|s| u64::from(s.raw_offset) + u64::from(rva - s.virtual_address))
+            .ok_or_else(|| anyhow!("RVA {:#x} is not inside any section", rva))
+    }
+
+    /// Offsets inside the resource tree are relative to the resource directory.
+    fn resource_address(&self, offset: u32) -> Result<u32> {
+        self.resource_rva
+            .checked_add(offset)
+            .ok_or_else(|| anyhow!("Resource offset {:#x} out of range", offset))
+    }
+
+    fn read_rva<R: ReadAt>(&self, source: &mut R, rva: u32, len: usize) -> Result<Vec<u8>> {
+        let offset = self.rva_to_offset(rva)?;
+        read_vec(source, offset, len)
+    }
+
+    /// Follows type -> name -> language in the resource tree. The first name and the first
+    /// language are used; executables practically always carry exactly one version resource.
+    fn read_version_resource<R: ReadAt>(&self, source: &mut R) -> Result<Vec<u8>> {
+        let types = self.read_directory(source, 0)?;
+        let name_dir = types
+            .into_iter()
+            .find_map(|(id, dir)| if id == Some(RT_VERSION) { dir } else { None })
+            .ok_or_else(|| anyhow!("No version resource"))?;
+
+        let names = self.read_directory(source, name_dir)?;
+        let lang_dir = names
+            .into_iter()
+            .find_map(|(_, dir)| dir)
+            .ok_or_else(|| anyhow!("Malformed version resource (no language directory)"))?;
+
+        let data_entry = self
+            .first_leaf(source, lang_dir)?
+            .ok_or_else(|| anyhow!("Malformed version resource (no data entry)"))?;
+
+        let entry = self.read_rva(source, self.resource_address(data_entry)?, 16)?;
+        let data_rva = le_u32(&entry, 0)?;
+        let size = le_u32(&entry, 4)? as usize;
+        if size == 0 || size > MAX_VERSION_INFO_SIZE {
+            bail!("Version resource has an invalid size ({} bytes)", size);
+        }
+        self.read_rva(source, data_rva, size)
+    }
+
+    /// Returns the offset (relative to the resource root) of the first data entry in a
+    /// language directory.
+    fn first_leaf<R: ReadAt>(&self, source: &mut R, dir_offset: u32) -> Result<Option<u32>> {
+        Ok(self
+            .read_raw_entries(source, dir_offset)?
+            .into_iter()
+            .find(|(_, target)| target & 0x8000_0000 == 0)
+            .map(|(_, target)| target))
+    }
+
+    /// Reads a resource directory and returns `(id, subdirectory offset)` per entry.
+    /// Named entries have no numeric ID (`None`); leaves have no subdirectory (`None`).
+    fn read_directory<R: ReadAt>(
+        &self,
+        source: &mut R,
+        dir_offset: u32,
+    ) -> Result<Vec<(Option<u32>, Option<u32>)>> {
+        Ok(self
+            .read_raw_entries(source, dir_offset)?
+            .into_iter()
+            .map(|(name, target)| {
+                let id = (name & 0x8000_0000 == 0).then_some(name);
+                let dir = (target & 0x8000_0000 != 0).then_some(target & 0x7FFF_FFFF);
+                (id, dir)
+            })
+            .collect())
+    }
+
+    fn read_raw_entries<R: ReadAt>(
+        &self,
+        source: &mut R,
+        dir_offset: u32,
+    ) -> Result<Vec<(u32, u32)>> {
+        let header = self.read_rva(source, self.resource_address(dir_offset)?, 16)?;
+        let count = usize::from(le_u16(&header, 12)?) + usize::from(le_u16(&header, 14)?);
+        if count > MAX_RESOURCE_ENTRIES {
+            bail!("Too many resource entries ({})", count);
+        }
+        let entries = self.read_rva(source, self.resource_address(dir_offset + 16)?, count * 8)?;
+        entries
+            .chunks_exact(8)
+            .map(|e| Ok((le_u32(e, 0)?, le_u32(e, 4)?)))
+            .collect()
+    }
+}
+
+/// A node of the `VS_VERSIONINFO` tree (`VS_VERSIONINFO`, `StringFileInfo`,
+/// `StringTable`, `String`, ...). They all share the same header layout.
+struct VersionNode<'a> {
+    key: String,
+    value: &'a [u8],
+    /// `wType == 1`: the value is UTF-16 text, otherwise binary.
+    is_text: bool,
+    children: &'a [u8],
+}
+
+/// Parses one node starting at the beginning of `data`.
+/// Returns the node and the number of bytes it occupies (including padding).
+fn parse_node(data: &[u8]) -> Result<(VersionNode<'_>, usize)> {
+    let length = usize::from(le_u16(data, 0)?);
+    let value_length = usize::from(le_u16(data, 2)?);
+    let is_text = le_u16(data, 4)? == 1;
+    if length < 6 || length > data.len() {
+        bail!("Invalid version node length {}", length);
+    }
+    let node = &data[..length];
+
+    let (key, key_end) = read_utf16z(node, 6)?;
+    let value_start = align4(key_end);
+    // For text values `wValueLength` counts UTF-16 code units, otherwise bytes.
+    let value_bytes = if is_text {
+        value_length * 2
+    } else {
+        value_length
+    };
+    let value_end = (value_start + value_bytes).min(length);
+    let value = node.get(value_start..value_end).unwrap_or_default();
+    let children_start = align4(value_end).min(length);
+
+    Ok((
+        VersionNode {
+            key,
+            value,
+            is_text,
+            children: &node[children_start..],
+        },
+        align4(length).min(data.len()),
+    ))
+}
+
+/// Iterates over the child nodes contained in `data`.
+fn parse_children(mut data: &[u8]) -> Vec<VersionNode<'_>> {
+    let mut nodes = Vec::new();
+    while data.len() >= 6 {
+        match parse_node(data) {
+            Ok((node, consumed)) if consumed > 0 => {
+                nodes.push(node);
+                data = &data[consumed..];
+            }
+            _ => break,
+        }
+    }
+    nodes
+}
+
+fn parse_version_info(data: &[u8]) -> Result<ExecutableMetadata> {
+    let (root, _) = parse_node(data)?;
+    if root.key != "VS_VERSION_INFO" {
+        bail!("Unexpected version resource key '{}'", root.key);
+    }
+
+    let mut strings: Option<HashMap<String, String>> = None;
+    for child in parse_children(root.children) {
+        if child.key != "StringFileInfo" {
+            continue;
+        }
+        // Prefer an English (0x0409) table if there are several languages, otherwise
+        // take the first one.
+        let tables = parse_children(child.children);
+        let table = tables
+            .iter()
+            .find(|t| t.key.to_lowercase().starts_with("0409"))
+            .or_else(|| tables.first());
+        if let Some(table) = table {
+            strings = Some(
+                parse_children(table.children)
+                    .into_iter()
+                    .filter(|s| s.is_text)
+                    .filter_map(|s| {
+                        let value = decode_utf16(s.value);
+                        let value = value.trim();
+                        (!value.is_empty()).then(|| (s.key, value.to_string()))
+                    })
+                    .collect(),
+            );
+        }
+    }
+    let mut strings = strings.unwrap_or_default();
+    let fixed = parse_fixed_file_info(root.value);
+
+    Ok(ExecutableMetadata {
+        file_description: strings.remove("FileDescription"),
+        company_name: strings.remove("CompanyName"),
+        product_name: strings.remove("ProductName"),
+        file_version: strings
+            .remove("FileVersion")
+            .or_else(|| fixed.as_ref().map(|(file, _)| file.clone())),
+        product_version: strings
+            .remove("ProductVersion")
+            .or_else(|| fixed.map(|(_, product)| product)),
+    })
+}
+
+/// Decodes `VS_FIXEDFILEINFO` into `(file version, product version)` strings.
+fn parse_fixed_file_info(value: &[u8]) -> Option<(String, String)> {
+    if le_u32(value, 0).ok()? != VS_FIXEDFILEINFO_SIGNATURE {
+        return None;
+    }
+    let version =
+        |ms: u32, ls: u32| format!("{}.{}.{}.{}", ms >> 16, ms & 0xFFFF, ls >> 16, ls & 0xFFFF);
+    Some((
+        version(le_u32(value, 8).ok()?, le_u32(value, 12).ok()?),
+        version(le_u32(value, 16).ok()?, le_u32(value, 20).ok()?),
+    ))
+}
+
+/// Reads a null-terminated UTF-16LE string starting at `offset`.
+/// Returns the string and the offset directly after the terminator.
+fn read_utf16z(data: &[u8], offset: usize) -> Result<(String, usize)> {
+    let mut units = Vec::new();
+    let mut pos = offset;
+    loop {
+        let unit = le_u16(data, pos)?;
+        pos += 2;
+        if unit == 0 {
+            break;
+        }
+        units.push(unit);
+    }
+    Ok((String::from_utf16_lossy(&units), pos))
+}
+
+fn decode_utf16(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&u| u != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn align4(value: usize) -> usize {
+    (value + 3) & !3
+}
+
+fn read_vec<R: ReadAt>(source: &mut R, offset: u64, len: usize) -> Result<Vec<u8>> {
+    let mut buf = vec![0u8; len];
+    source.read_at(offset, &mut buf)?;
+    Ok(buf)
+}
+
+fn le_u16(data: &[u8], offset: usize) -> Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| anyhow!("Unexpected end of data at offset {}", offset))
+}
+
+fn le_u32(data: &[u8], offset: usize) -> Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| anyhow!("Unexpected end of data at offset {}", offset))
+}
+
+/// Caches metadata per executable path.
+///
+/// Reading the resources costs a few file reads per executable. The process list is
+/// refreshed every second or two, and the same executables show up again and again.
+/// Files without version info are cached too (as `None`), so they are not re-read.
+#[derive(Default)]
+pub struct ExecutableMetadataCache {
+    entries: Mutex<HashMap<PathBuf, Option<ExecutableMetadata>>>,
+}
+
+impl ExecutableMetadataCache {
+    pub fn get(&self, path: &Path) -> Option<ExecutableMetadata> {
+        if let Ok(entries) = self.entries.lock() {
+            if let Some(cached) = entries.get(path) {
+                return cached.clone();
+            }
+        }
+
+        let metadata = read_from_path(path).ok();
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(path.to_path_buf(), metadata.clone());
+        }
+        metadata
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Generated by `tests/fixtures/make_pe_fixtures.py`.
+    const VERSION_INFO: &[u8] = include_bytes!("../tests/fixtures/version_info.exe");
+    const NO_RESOURCES: &[u8] = include_bytes!("../tests/fixtures/no_resources.exe");
+    const TRUNCATED: &[u8] = include_bytes!("../tests/fixtures/truncated.exe");
+
+    /// Offset of the resource section in the fixtures.
+    const SECTION_RAW: usize = 0x200;
+
+    fn parse(bytes: &[u8]) -> Result<ExecutableMetadata> {
+        read_metadata(&mut &bytes[..])
+    }
+
+    #[test]
+    fn reads_version_info() {
+        let metadata = parse(VERSION_INFO).unwrap();
+        assert_eq!(
+            metadata,
+            ExecutableMetadata {
+                file_description: Some("Fixture Game".to_string()),
+                company_name: Some("Example Corp".to_string()),
+                product_name: Some("Fixture".to_string()),
+                file_version: Some("1.2.3.4".to_string()),
+                // No `ProductVersion` string, so it comes from `VS_FIXEDFILEINFO`.
+                product_version: Some("5.6.7.8".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn reads_from_file() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/version_info.exe");
+        assert_eq!(read_from_path(&path).unwrap(), parse(VERSION_INFO).unwrap());
+    }
+
+    #[test]
+    fn no_resource_section() {
+        let error = parse(NO_RESOURCES).unwrap_err();
+        assert_eq!(error.to_string(), "No resource directory");
+    }
+
+    #[test]
+    fn truncated_file() {
+        assert!(parse(TRUNCATED).is_err());
+    }
+
+    #[test]
+    fn every_truncation_fails() {
+        for len in 0..VERSION_INFO.len() {
+            assert!(parse(&VERSION_INFO[..len]).is_err(), "length {}", len);
+        }
+    }
+
+    #[test]
+    fn corrupt_resource_tree() {
+        // The first type entry points far outside the section.
+        let mut bytes = VERSION_INFO.to_vec();
+        bytes[SECTION_RAW + 20..SECTION_RAW + 24].copy_from_slice(&0xFFFF_FFF0u32.to_le_bytes());
+        assert!(parse(&bytes).is_err());
+
+        // Absurd entry count in the root directory.
+        let mut bytes = VERSION_INFO.to_vec();
+        bytes[SECTION_RAW + 14..SECTION_RAW + 16].copy_from_slice(&u16::MAX.to_le_bytes());
+        assert!(parse(&bytes).is_err());
+
+        // Not a PE file at all.
+        assert!(parse(b"MZ").is_err());
+        assert!(parse(&[0xFF; 512]).is_err());
+    }
+
+    #[test]
+    fn corrupt_version_nodes() {
+        // Every byte of the version resource overwritten in turn must not panic.
+        let start = SECTION_RAW + 0x58;
+        for offset in start..VERSION_INFO.len() {
+            let mut bytes = VERSION_INFO.to_vec();
+            bytes[offset] = 0xFF;
+            let _ = parse(&bytes);
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::verification::Verification;
 use serde::{Deserialize, Serialize};
 use std::os::windows::process::CommandExt;
 use std::process::Command;
@@ -107,7 +108,7 @@ pub fn get_power_plans() -> Result<Vec<PowerPlan>, String> {
 /// Checks exact length (36), hyphens at positions 8/13/18/23, and that all
 /// remaining characters are ASCII hex digits. This prevents malformed or
 /// injected values from ever reaching `Command::arg`.
-fn is_valid_guid(guid: &str) -> bool {
+pub(crate) fn is_valid_guid(guid: &str) -> bool {
     if guid.len() != 36 {
         return false;
     }
@@ -128,12 +129,15 @@ fn is_valid_guid(guid: &str) -> bool {
     true
 }
 
-/// Setzt den aktiven Energiesparplan.
+/// Setzt den aktiven Energiesparplan und liest ihn danach zurück.
 ///
 /// # Arguments
 /// * `guid` - Die GUID des zu aktivierenden Plans als String.
+///
+/// # Returns
+/// * `Verification::Mismatch`, wenn danach ein anderer Plan aktiv ist (z.B. per Gruppenrichtlinie erzwungen).
 #[tauri::command]
-pub fn set_active_power_plan(guid: String) -> Result<(), String> {
+pub fn set_active_power_plan(guid: String) -> Result<Verification<String>, String> {
     // ! SECURITY: Validate GUID format before passing to any system call.
     // Command::arg() quotes arguments, but edge cases in Windows
     // CommandLineToArgvW parsing mean rejecting malformed input is
@@ -155,7 +159,13 @@ pub fn set_active_power_plan(guid: String) -> Result<(), String> {
         return Err(String::from_utf8_lossy(&output.stderr).to_string());
     }
 
-    // `Ok(())` ist der "Unit Type" in Rust (ähnlich `void` in C/Java),
-    // bedeutet hier: "Erfolgreich, aber kein Rückgabewert".
-    Ok(())
+    // * HINWEIS: Rücklesen
+    // `powercfg` meldet auch dann Erfolg, wenn eine Richtlinie den Plan sofort wieder umstellt.
+    // GUIDs werden kleingeschrieben verglichen, da `powercfg /LIST` sie so ausgibt.
+    let active = get_power_plans()?
+        .into_iter()
+        .find(|plan| plan.is_active)
+        .map(|plan| plan.guid.to_lowercase())
+        .unwrap_or_default();
+    Ok(Verification::compare(guid.to_lowercase(), active))
 }
@@ -0,0 +1,316 @@
+use crate::processes::{self, is_process_protected, ProcessPriority};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use sysinfo::{Process, ProcessesToUpdate, System};
+use tauri::{AppHandle, Emitter};
+
+/// How processes are combined into groups.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum GroupBy {
+    /// All processes with the same (case-insensitive) name, e.g. every `chrome.exe`.
+    #[default]
+    Name,
+    /// All processes started from the same executable file.
+    /// Falls back to the name if the path cannot be read (e.g. protected system processes).
+    Path,
+}
+
+/// Aggregated view of all instances of one application.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessGroup {
+    /// Normalised group key. Pass it back unchanged to the group actions.
+    pub key: String,
+    pub name: String,
+    pub exe_path: Option<String>,
+    pub instance_count: usize,
+    pub total_memory: u64,
+    pub total_cpu: f32,
+    pub pids: Vec<u32>,
+}
+
+/// Per-PID outcome of an action that targets a whole group.
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupActionResult {
+    pub succeeded: Vec<u32>,
+    pub failed: Vec<GroupActionFailure>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupActionFailure {
+    pub pid: u32,
+    pub error: String,
+}
+
+/// Keeps one `System` alive between calls.
+///
+/// CPU usage is the difference between two refreshes, so a fresh `System` per call
+/// would always report 0 %.
+#[derive(Default)]
+pub struct ProcessGroupState {
+    sys: Mutex<System>,
+}
+
+impl ProcessGroupState {
+    /// Refreshes the shared `System` and returns the current groups.
+    pub fn refreshed_groups(&self, group_by: GroupBy) -> Result<Vec<ProcessGroup>, String> {
+        let mut sys = self.sys.lock().map_err(|e| e.to_string())?;
+        sys.refresh_processes(ProcessesToUpdate::All, true);
+        Ok(collect_groups(&sys, group_by))
+    }
+}
+
+/// Returns the running processes grouped by name or executable path,
+/// sorted by total memory (largest first).
+#[tauri::command]
+pub fn get_process_groups(
+    state: tauri::State<ProcessGroupState>,
+    group_by: Option<GroupBy>,
+) -> Result<Vec<ProcessGroup>, String> {
+    state.refreshed_groups(group_by.unwrap_or_default())
+}
+
+/// The parts of a process the grouping looks at.
+struct GroupEntry {
+    pid: u32,
+    name: String,
+    exe_path: Option<String>,
+    memory: u64,
+    cpu: f32,
+}
+
+impl GroupEntry {
+    fn new(pid: u32, process: &Process) -> Self {
+        Self {
+            pid,
+            name: process.name().to_string_lossy().into_owned(),
+            exe_path: process.exe().map(|p| p.to_string_lossy().into_owned()),
+            memory: process.memory(),
+            cpu: process.cpu_usage(),
+        }
+    }
+
+    fn key(&self, group_by: GroupBy) -> String {
+        match (group_by, &self.exe_path) {
+            (GroupBy::Path, Some(path)) => path.to_lowercase(),
+            _ => self.name.to_lowercase(),
+        }
+    }
+}
+
+/// Groups the processes of an already refreshed `System`,
+/// sorted by total memory (largest first).
+fn collect_groups(sys: &System, group_by: GroupBy) -> Vec<ProcessGroup> {
+    group_entries(
+        sys.processes()
+            .iter()
+            .map(|(pid, process)| GroupEntry::new(pid.as_u32(), process)),
+        group_by,
+    )
+}
+
+fn group_entries(
+    entries: impl Iterator<Item = GroupEntry>,
+    group_by: GroupBy,
+) -> Vec<ProcessGroup> {
+    let mut groups: HashMap<String, ProcessGroup> = HashMap::new();
+    for entry in entries {
+        let key = entry.key(group_by);
+        let group = groups.entry(key.clone()).or_insert_with(|| ProcessGroup {
+            key,
+            name: entry.name.clone(),
+            exe_path: entry.exe_path.clone(),
+            instance_count: 0,
+            total_memory: 0,
+            total_cpu: 0.0,
+            pids: Vec::new(),
+        });
+        group.instance_count += 1;
+        group.total_memory += entry.memory;
+        group.total_cpu += entry.cpu;
+        group.pids.push(entry.pid);
+    }
+
+    let mut result: Vec<ProcessGroup> = groups.into_values().collect();
+    for group in &mut result {
+        group.pids.sort_unstable();
+    }
+    result.sort_by_key(|group| Reverse(group.total_memory));
+    result
+}
+
+/// Terminates every process of a group.
+#[tauri::command]
+pub fn terminate_process_group(
+    app: AppHandle,
+    state: tauri::State<ProcessGroupState>,
+    group_by: GroupBy,
+    key: String,
+) -> Result<GroupActionResult, String> {
+    run_group_action(
+        &app,
+        &state,
+        group_by,
+        &key,
+        "terminate",
+        processes::kill_process,
+    )
+}
+
+/// Suspends every process of a group.
+#[tauri::command]
+pub fn suspend_process_group(
+    app: AppHandle,
+    state: tauri::State<ProcessGroupState>,
+    group_by: GroupBy,
+    key: String,
+) -> Result<GroupActionResult, String> {
+    run_group_action(&app, &state, group_by, &key, "suspend", |pid| {
+        processes::set_suspended(pid, true)
+    })
+}
+
+/// Resumes every process of a group.
+#[tauri::command]
+pub fn resume_process_group(
+    app: AppHandle,
+    state: tauri::State<ProcessGroupState>,
+    group_by: GroupBy,
+    key: String,
+) -> Result<GroupActionResult, String> {
+    run_group_action(&app, &state, group_by, &key, "resume", |pid| {
+        processes::set_suspended(pid, false)
+    })
+}
+
+/// Sets the priority class of every process of a group.
+#[tauri::command]
+pub fn set_process_group_priority(
+    app: AppHandle,
+    state: tauri::State<ProcessGroupState>,
+    group_by: GroupBy,
+    key: String,
+    priority: ProcessPriority,
+) -> Result<GroupActionResult, String> {
+    run_group_action(&app, &state, group_by, &key, "set priority for", |pid| {
+        processes::apply_priority(pid, priority)
+    })
+}
+
+/// Resolves the group's PIDs right before acting, so the action never hits a PID the
+/// frontend saw seconds ago that may already belong to another process.
+///
+/// Protected processes are never acted on; they are reported in `failed`.
+fn run_group_action(
+    app: &AppHandle,
+    state: &ProcessGroupState,
+    group_by: GroupBy,
+    key: &str,
+    action_name: &str,
+    action: impl Fn(u32) -> Result<(), String>,
+) -> Result<GroupActionResult, String> {
+    let key = key.to_lowercase();
+    let entries: Vec<GroupEntry> = {
+        let mut sys = state.sys.lock().map_err(|e| e.to_string())?;
+        sys.refresh_processes(ProcessesToUpdate::All, true);
+        sys.processes()
+            .iter()
+            .map(|(pid, process)| GroupEntry::new(pid.as_u32(), process))
+            .filter(|entry| entry.key(group_by) == key)
+            .collect()
+    };
+
+    if entries.is_empty() {
+        let err_msg = format!("No running processes found for group '{}'", key);
+        let _ = app.emit("log-error", &err_msg);
+        return Err(err_msg);
+    }
+
+    let mut result = GroupActionResult::default();
+    for GroupEntry { pid, name, .. } in entries {
+        // * SECURITY: Group keys come from the frontend and may name system processes
+        // (svchost.exe, csrss.exe, ...), which must never be killed, frozen or reprioritised.
+        if is_process_protected(&name) {
+            result.failed.push(GroupActionFailure {
+                pid,
+                error: format!("{} is a protected process", name),
+            });
+            continue;
+        }
+        match action(pid) {
+            Ok(()) => result.succeeded.push(pid),
+            Err(error) => result.failed.push(GroupActionFailure { pid, error }),
+        }
+    }
+
+    let _ = app.emit(
+        "log-info",
+        format!(
+            "Tried to {} group '{}': {} succeeded, {} failed",
+            action_name,
+            key,
+            result.succeeded.len(),
+            result.failed.len()
+        ),
+    );
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(pid: u32, name: &str, exe_path: Option<&str>, memory: u64, cpu: f32) -> GroupEntry {
+        GroupEntry {
+            pid,
+            name: name.to_string(),
+            exe_path: exe_path.map(str::to_string),
+            memory,
+            cpu,
+        }
+    }
+
+    fn entries() -> Vec<GroupEntry> {
+        vec![
+            entry(30, "chrome.exe", Some(r"C:\Chrome\chrome.exe"), 300, 1.5),
+            entry(10, "Chrome.exe", Some(r"C:\Chrome\chrome.exe"), 200, 2.0),
+            entry(20, "chrome.exe", Some(r"D:\Portable\chrome.exe"), 100, 0.5),
+            entry(40, "svchost.exe", None, 50, 0.0),
+        ]
+    }
+
+    #[test]
+    fn groups_by_name_ignoring_case() {
+        let groups = group_entries(entries().into_iter(), GroupBy::Name);
+        assert_eq!(groups.len(), 2);
+        let chrome = &groups[0];
+        assert_eq!(chrome.key, "chrome.exe");
+        assert_eq!(chrome.instance_count, 3);
+        assert_eq!(chrome.total_memory, 600);
+        assert!((chrome.total_cpu - 4.0).abs() < f32::EPSILON);
+        assert_eq!(chrome.pids, [10, 20, 30]);
+    }
+
+    #[test]
+    fn groups_by_path_and_falls_back_to_the_name() {
+        let groups = group_entries(entries().into_iter(), GroupBy::Path);
+        let keys: Vec<&str> = groups.iter().map(|group| group.key.as_str()).collect();
+        // Largest total memory first.
+        assert_eq!(
+            keys,
+            [
+                r"c:\chrome\chrome.exe",
+                r"d:\portable\chrome.exe",
+                "svchost.exe"
+            ]
+        );
+        assert_eq!(groups[0].pids, [10, 30]);
+        assert_eq!(groups[0].total_memory, 500);
+        assert_eq!(groups[2].exe_path, None);
+    }
+}
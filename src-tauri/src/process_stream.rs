@@ -0,0 +1,378 @@
+use crate::processes::ProcessInfo;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use sysinfo::{ProcessesToUpdate, System};
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Lower bound for the update interval so a bad argument cannot spin the CPU.
+const MIN_INTERVAL_MS: u64 = 250;
+const DEFAULT_INTERVAL_MS: u64 = 1000;
+
+/// CPU changes below this threshold (in percentage points) are not reported.
+/// Without it almost every process would show up as "changed" on every tick.
+const CPU_CHANGE_THRESHOLD: f32 = 0.1;
+
+/// Memory changes below this many bytes are not reported either; the working set of a
+/// busy process moves by a few pages on every tick.
+const MEMORY_CHANGE_THRESHOLD: u64 = 1024 * 1024;
+
+/// Column used to sort the process list on the backend side.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ProcessSortKey {
+    Pid,
+    Name,
+    Memory,
+    CpuUsage,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Options for a process list subscription.
+///
+/// Every field is optional so the frontend only has to send what it needs.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessStreamOptions {
+    /// Time between two updates in milliseconds (default: 1000, minimum: 250).
+    pub interval_ms: Option<u64>,
+    /// Case-insensitive substring the process name must contain.
+    pub filter: Option<String>,
+    pub sort_key: Option<ProcessSortKey>,
+    #[serde(default)]
+    pub sort_order: SortOrder,
+}
+
+/// A message sent to the frontend over the `Channel`.
+///
+/// The first message is always a full `Snapshot`, followed by `Delta`s only.
+/// `order` is only present if sorting was requested and the order changed. It then
+/// contains every (filtered) PID in the requested order.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ProcessUpdate {
+    Snapshot {
+        processes: Vec<ProcessInfo>,
+    },
+    Delta {
+        added: Vec<ProcessInfo>,
+        changed: Vec<ProcessInfo>,
+        removed: Vec<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        order: Option<Vec<u32>>,
+    },
+}
+
+/// Keeps track of all active subscriptions.
+///
+/// Every subscription owns a thread and its own `System`, so CPU usage can be computed
+/// correctly between two ticks.
+#[derive(Default)]
+pub struct ProcessStreamState {
+    next_id: AtomicU32,
+    subscriptions: Mutex<HashMap<u32, Arc<AtomicBool>>>,
+}
+
+impl ProcessStreamState {
+    /// Stops every running subscription (e.g. when the app exits).
+    pub fn stop_all(&self) {
+        if let Ok(mut subs) = self.subscriptions.lock() {
+            for (_, stop) in subs.drain() {
+                stop.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Starts streaming the process list to the frontend.
+///
+/// # Returns
+/// * `u32`: The subscription ID to pass to `unsubscribe_processes`.
+#[tauri::command]
+pub fn subscribe_processes(
+    app: AppHandle,
+    state: tauri::State<ProcessStreamState>,
+    channel: Channel<ProcessUpdate>,
+    options: Option<ProcessStreamOptions>,
+) -> Result<u32, String> {
+    let options = options.unwrap_or_default();
+    let id = state.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+    let stop = Arc::new(AtomicBool::new(false));
+
+    state
+        .subscriptions
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(id, stop.clone());
+
+    let _ = app.emit(
+        "log-info",
+        format!("Process stream {} subscribed ({:?})", id, options),
+    );
+
+    thread::spawn(move || {
+        run_subscription(&channel, &options, &stop);
+
+        // The thread can also end on its own (e.g. the webview was reloaded and the channel
+        // is gone), so the entry is cleaned up here as well.
+        if let Some(state) = app.try_state::<ProcessStreamState>() {
+            if let Ok(mut subs) = state.subscriptions.lock() {
+                subs.remove(&id);
+            }
+        }
+        let _ = app.emit("log-info", format!("Process stream {} ended", id));
+    });
+
+    Ok(id)
+}
+
+/// Stops a process list subscription.
+#[tauri::command]
+pub fn unsubscribe_processes(state: tauri::State<ProcessStreamState>, id: u32) -> bool {
+    let removed = state
+        .subscriptions
+        .lock()
+        .ok()
+        .and_then(|mut subs| subs.remove(&id));
+
+    match removed {
+        Some(stop) => {
+            stop.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+fn run_subscription(
+    channel: &Channel<ProcessUpdate>,
+    options: &ProcessStreamOptions,
+    stop: &AtomicBool,
+) {
+    let interval = Duration::from_millis(
+        options
+            .interval_ms
+            .unwrap_or(DEFAULT_INTERVAL_MS)
+            .max(MIN_INTERVAL_MS),
+    );
+    let filter = options
+        .filter
+        .as_ref()
+        .map(|f| f.trim().to_lowercase())
+        .filter(|f| !f.is_empty());
+
+    let mut sys = System::new();
+    let mut previous: Option<HashMap<u32, ProcessInfo>> = None;
+    let mut previous_order: Vec<u32> = Vec::new();
+
+    while !stop.load(Ordering::Relaxed) {
+        sys.refresh_processes(ProcessesToUpdate::All, true);
+
+        let mut current: Vec<ProcessInfo> = sys
+            .processes()
+            .iter()
+            .map(|(pid, process)| ProcessInfo::from_process(pid, process))
+            .filter(|p| {
+                filter
+                    .as_ref()
+                    .map_or(true, |f| p.name.to_lowercase().contains(f.as_str()))
+            })
+            .collect();
+
+        if let Some(key) = options.sort_key {
+            sort_processes(&mut current, key, options.sort_order);
+        }
+
+        let order: Vec<u32> = current.iter().map(|p| p.pid).collect();
+
+        let update = match previous.as_ref() {
+            None => Some(ProcessUpdate::Snapshot {
+                processes: current.clone(),
+            }),
+            Some(prev) => {
+                let order_changed = options.sort_key.is_some() && order != previous_order;
+                diff(prev, &current, order_changed.then(|| order.clone()))
+            }
+        };
+
+        if let Some(update) = update {
+            // If sending fails nobody is listening anymore, so the subscription ends.
+            if channel.send(update).is_err() {
+                return;
+            }
+        }
+
+        previous = Some(reported(previous.take(), current));
+        previous_order = order;
+
+        sleep_unless_stopped(interval, stop);
+    }
+}
+
+/// Compares two states of the process list.
+///
+/// Returns `None` if nothing changed, so no empty messages are sent.
+fn diff(
+    previous: &HashMap<u32, ProcessInfo>,
+    current: &[ProcessInfo],
+    order: Option<Vec<u32>>,
+) -> Option<ProcessUpdate> {
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+
+    for process in current {
+        match previous.get(&process.pid) {
+            None => added.push(process.clone()),
+            Some(old) if has_changed(old, process) => changed.push(process.clone()),
+            Some(_) => {}
+        }
+    }
+
+    let current_pids: HashSet<u32> = current.iter().map(|p| p.pid).collect();
+    let removed: Vec<u32> = previous
+        .keys()
+        .filter(|pid| !current_pids.contains(pid))
+        .copied()
+        .collect();
+
+    if added.is_empty() && changed.is_empty() && removed.is_empty() && order.is_none() {
+        return None;
+    }
+
+    Some(ProcessUpdate::Delta {
+        added,
+        changed,
+        removed,
+        order,
+    })
+}
+
+fn has_changed(old: &ProcessInfo, new: &ProcessInfo) -> bool {
+    // The OS may reuse a PID, so a different name counts as a change as well.
+    old.name != new.name
+        || old.memory.abs_diff(new.memory) >= MEMORY_CHANGE_THRESHOLD
+        || (old.cpu_usage - new.cpu_usage).abs() >= CPU_CHANGE_THRESHOLD
+}
+
+/// The process list as the frontend has it after the update for `current`.
+///
+/// Processes whose change stayed below the thresholds keep their last reported values,
+/// so a slow drift is still reported once it adds up.
+fn reported(
+    previous: Option<HashMap<u32, ProcessInfo>>,
+    current: Vec<ProcessInfo>,
+) -> HashMap<u32, ProcessInfo> {
+    let mut previous = previous.unwrap_or_default();
+    current
+        .into_iter()
+        .map(|process| match previous.remove(&process.pid) {
+            Some(old) if !has_changed(&old, &process) => (old.pid, old),
+            _ => (process.pid, process),
+        })
+        .collect()
+}
+
+fn sort_processes(processes: &mut [ProcessInfo], key: ProcessSortKey, order: SortOrder) {
+    processes.sort_by(|a, b| {
+        let cmp = match key {
+            ProcessSortKey::Pid => a.pid.cmp(&b.pid),
+            ProcessSortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            ProcessSortKey::Memory => a.memory.cmp(&b.memory),
+            ProcessSortKey::CpuUsage => a
+                .cpu_usage
+                .partial_cmp(&b.cpu_usage)
+                .unwrap_or(CmpOrdering::Equal),
+        }
+        // PID as tie-breaker keeps the order stable instead of "jumping" on every tick.
+        .then_with(|| a.pid.cmp(&b.pid));
+
+        match order {
+            SortOrder::Asc => cmp,
+            SortOrder::Desc => cmp.reverse(),
+        }
+    });
+}
+
+/// Sleeps in small steps so `unsubscribe_processes` does not have to wait for the
+/// end of a (possibly long) interval.
+pub(crate) fn sleep_unless_stopped(total: Duration, stop: &AtomicBool) {
+    let step = Duration::from_millis(50);
+    let mut slept = Duration::ZERO;
+    while slept < total && !stop.load(Ordering::Relaxed) {
+        thread::sleep(step);
+        slept += step;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, memory: u64, cpu_usage: f32) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: format!("process{}.exe", pid),
+            memory,
+            cpu_usage,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn small_changes_are_not_reported() {
+        let old = process(1, 100 * 1024 * 1024, 5.0);
+        assert!(!has_changed(
+            &old,
+            &process(1, 100 * 1024 * 1024 + 4096, 5.05)
+        ));
+        assert!(has_changed(&old, &process(1, 102 * 1024 * 1024, 5.0)));
+        assert!(has_changed(&old, &process(1, 99 * 1024 * 1024, 5.0)));
+        assert!(has_changed(&old, &process(1, 100 * 1024 * 1024, 6.0)));
+        assert!(has_changed(
+            &old,
+            &ProcessInfo {
+                name: "other.exe".to_string(),
+                ..old.clone()
+            }
+        ));
+    }
+
+    #[test]
+    fn slow_drift_is_reported_once_it_adds_up() {
+        let step = MEMORY_CHANGE_THRESHOLD / 4;
+        let mut previous = reported(None, vec![process(1, 0, 0.0)]);
+        let mut updates = 0;
+        for tick in 1..=8 {
+            let current = vec![process(1, tick * step, 0.0)];
+            if diff(&previous, &current, None).is_some() {
+                updates += 1;
+            }
+            previous = reported(Some(previous), current);
+        }
+        assert_eq!(updates, 2);
+        assert_eq!(previous[&1].memory, 8 * step);
+    }
+
+    #[test]
+    fn exited_processes_are_dropped() {
+        let previous = reported(None, vec![process(1, 0, 0.0), process(2, 0, 0.0)]);
+        let current = vec![process(2, 0, 0.0)];
+        let Some(ProcessUpdate::Delta { removed, .. }) = diff(&previous, &current, None) else {
+            panic!("expected a delta");
+        };
+        assert_eq!(removed, [1]);
+        assert_eq!(reported(Some(previous), current).len(), 1);
+    }
+}
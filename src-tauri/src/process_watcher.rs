@@ -0,0 +1,1194 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+#[cfg(target_os = "windows")]
+use std::ffi::OsStr;
+#[cfg(target_os = "windows")]
+use std::os::windows::ffi::OsStrExt;
+#[cfg(target_os = "windows")]
+use std::ptr::null_mut;
+#[cfg(target_os = "windows")]
+use winapi::shared::minwindef::FALSE;
+#[cfg(target_os = "windows")]
+use winapi::shared::ntdef::HANDLE;
+#[cfg(target_os = "windows")]
+use winapi::shared::rpcdce::{
+    RPC_C_AUTHN_LEVEL_CALL, RPC_C_AUTHN_LEVEL_DEFAULT, RPC_C_AUTHN_WINNT, RPC_C_AUTHZ_NONE,
+    RPC_C_IMP_LEVEL_IMPERSONATE,
+};
+#[cfg(target_os = "windows")]
+use winapi::shared::winerror::{RPC_E_CHANGED_MODE, RPC_E_TOO_LATE};
+#[cfg(target_os = "windows")]
+use winapi::shared::wtypes::{BSTR, VT_BSTR, VT_I4, VT_UI4, VT_UNKNOWN};
+#[cfg(target_os = "windows")]
+use winapi::shared::wtypesbase::CLSCTX_INPROC_SERVER;
+#[cfg(target_os = "windows")]
+use winapi::um::combaseapi::{
+    CoCreateInstance, CoInitializeEx, CoInitializeSecurity, CoSetProxyBlanket, CoUninitialize,
+};
+#[cfg(target_os = "windows")]
+use winapi::um::handleapi::CloseHandle;
+#[cfg(target_os = "windows")]
+use winapi::um::oaidl::VARIANT;
+#[cfg(target_os = "windows")]
+use winapi::um::objbase::COINIT_MULTITHREADED;
+#[cfg(target_os = "windows")]
+use winapi::um::objidlbase::EOAC_NONE;
+#[cfg(target_os = "windows")]
+use winapi::um::oleauto::{SysAllocString, SysFreeString, SysStringLen, VariantClear};
+#[cfg(target_os = "windows")]
+use winapi::um::processthreadsapi::OpenProcess;
+#[cfg(target_os = "windows")]
+use winapi::um::synchapi::{WaitForMultipleObjects, WaitForSingleObject};
+#[cfg(target_os = "windows")]
+use winapi::um::unknwnbase::IUnknown;
+#[cfg(target_os = "windows")]
+use winapi::um::wbemcli::{
+    CLSID_WbemLocator, IEnumWbemClassObject, IWbemClassObject, IWbemLocator, IWbemServices,
+    WBEM_FLAG_FORWARD_ONLY, WBEM_FLAG_RETURN_IMMEDIATELY, WBEM_S_TIMEDOUT,
+};
+#[cfg(target_os = "windows")]
+use winapi::um::winbase::{WAIT_FAILED, WAIT_OBJECT_0};
+#[cfg(target_os = "windows")]
+use winapi::um::winnt::SYNCHRONIZE;
+#[cfg(target_os = "windows")]
+use winapi::Interface;
+
+#[cfg(target_os = "linux")]
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+/// How often the fallback source scans the process list.
+const SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A process reported by a start source.
+#[derive(Debug, Clone)]
+pub struct StartedProcess {
+    pub pid: u32,
+    pub name: String,
+    pub parent: Option<u32>,
+}
+
+/// `target` is the ID the classifier returned for the process.
+#[derive(Debug, Clone)]
+pub enum WatchEvent {
+    Started {
+        pid: u32,
+        name: String,
+        target: String,
+    },
+    Exited {
+        pid: u32,
+        name: String,
+        target: String,
+    },
+}
+
+/// Decides whether a process is watched and returns the ID of its target.
+///
+/// Called once per process, so it may look at more than the name (path, environment).
+pub type Classifier = Box<dyn FnMut(&StartedProcess) -> Option<String>>;
+
+/// Reports newly started processes.
+///
+/// Sources are not `Send`: the WMI source holds COM pointers that belong to the thread
+/// that created them, so a watcher is created and used on the same thread.
+pub trait ProcessStartSource {
+    fn description(&self) -> &'static str;
+
+    /// `true` if asking for starts is cheap (the OS pushes them), `false` if every call
+    /// scans the process list.
+    fn is_event_driven(&self) -> bool;
+
+    /// Waits up to `timeout` and returns the processes started since the last call.
+    fn next_starts(&mut self, timeout: Duration) -> Result<Vec<StartedProcess>, String>;
+}
+
+/// Fallback source: diffs a name-only process list.
+///
+/// `ProcessRefreshKind::nothing()` skips CPU, memory, paths and command lines, so a scan
+/// is a single `NtQuerySystemInformation` call on Windows (a `/proc` listing on Linux).
+/// The first call reports every running process.
+pub struct NameScanSource {
+    sys: System,
+    known: HashSet<u32>,
+    last_scan: Option<Instant>,
+}
+
+impl NameScanSource {
+    pub fn new() -> Self {
+        Self {
+            sys: System::new(),
+            known: HashSet::new(),
+            last_scan: None,
+        }
+    }
+}
+
+impl ProcessStartSource for NameScanSource {
+    fn description(&self) -> &'static str {
+        "process list scan"
+    }
+
+    fn is_event_driven(&self) -> bool {
+        false
+    }
+
+    fn next_starts(&mut self, timeout: Duration) -> Result<Vec<StartedProcess>, String> {
+        if let Some(last_scan) = self.last_scan {
+            let due = SCAN_INTERVAL.saturating_sub(last_scan.elapsed());
+            if due > timeout {
+                std::thread::sleep(timeout);
+                return Ok(Vec::new());
+            }
+            std::thread::sleep(due);
+        }
+
+        self.sys.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing(),
+        );
+        self.last_scan = Some(Instant::now());
+
+        let mut started = Vec::new();
+        let mut current = HashSet::with_capacity(self.known.len());
+        for (pid, process) in self.sys.processes() {
+            let pid = pid.as_u32();
+            current.insert(pid);
+            if !self.known.contains(&pid) {
+                started.push(StartedProcess {
+                    pid,
+                    name: process.name().to_string_lossy().into_owned(),
+                    parent: process.parent().map(|p| p.as_u32()),
+                });
+            }
+        }
+        self.known = current;
+        Ok(started)
+    }
+}
+
+// * NOTE: WMI process start notifications
+// `Win32_ProcessStartTrace` is pushed by the kernel without any polling but needs admin
+// rights. Without them we subscribe to `__InstanceCreationEvent`, which WMI evaluates
+// itself once per second inside its own service, so this app does not scan anything.
+// If WMI is unavailable (service disabled, broken repository) the watcher falls back to
+// `NameScanSource`.
+#[cfg(target_os = "windows")]
+const WMI_START_TRACE_QUERY: &str =
+    "SELECT ProcessID, ProcessName, ParentProcessID FROM Win32_ProcessStartTrace";
+#[cfg(target_os = "windows")]
+const WMI_INSTANCE_CREATION_QUERY: &str =
+    "SELECT * FROM __InstanceCreationEvent WITHIN 1 WHERE TargetInstance ISA 'Win32_Process'";
+
+#[cfg(target_os = "windows")]
+pub struct WmiStartSource {
+    services: *mut IWbemServices,
+    events: *mut IEnumWbemClassObject,
+    com_initialized: bool,
+    description: &'static str,
+}
+
+#[cfg(target_os = "windows")]
+fn to_wide(value: &str) -> Vec<u16> {
+    OsStr::new(value).encode_wide().chain(Some(0)).collect()
+}
+
+/// Owned `BSTR` that is freed on drop.
+#[cfg(target_os = "windows")]
+struct Bstr(BSTR);
+
+#[cfg(target_os = "windows")]
+impl Bstr {
+    fn new(value: &str) -> Self {
+        let wide = to_wide(value);
+        Self(unsafe { SysAllocString(wide.as_ptr()) })
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for Bstr {
+    fn drop(&mut self) {
+        unsafe { SysFreeString(self.0) };
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl WmiStartSource {
+    /// Subscribes to process start events, preferring the kernel trace.
+    pub fn connect() -> Result<Self, String> {
+        // Semi-synchronous queries report errors such as "access denied" only on the first
+        // `Next`, so every subscription is probed once. Events consumed by the probe are
+        // not lost: `ProcessWatcher` runs its initial scan after connecting.
+        let probe = |mut source: Self| source.next_starts(Duration::ZERO).map(|_| source);
+        Self::connect_query(WMI_START_TRACE_QUERY, "WMI process start trace")
+            .and_then(probe)
+            .or_else(|_| {
+                Self::connect_query(WMI_INSTANCE_CREATION_QUERY, "WMI instance events")
+                    .and_then(probe)
+            })
+    }
+
+    fn connect_query(query: &str, description: &'static str) -> Result<Self, String> {
+        unsafe {
+            let hr = CoInitializeEx(null_mut(), COINIT_MULTITHREADED);
+            // `RPC_E_CHANGED_MODE`: The thread is already STA, COM is usable anyway.
+            if hr < 0 && hr != RPC_E_CHANGED_MODE {
+                return Err(format!("CoInitializeEx failed ({:#x})", hr));
+            }
+
+            // From here on `Drop` releases whatever has been created so far.
+            let mut source = Self {
+                services: null_mut(),
+                events: null_mut(),
+                com_initialized: hr >= 0,
+                description,
+            };
+
+            // Process-wide; fails with `RPC_E_TOO_LATE` if someone (e.g. WebView2) was first.
+            let hr = CoInitializeSecurity(
+                null_mut(),
+                -1,
+                null_mut(),
+                null_mut(),
+                RPC_C_AUTHN_LEVEL_DEFAULT,
+                RPC_C_IMP_LEVEL_IMPERSONATE,
+                null_mut(),
+                EOAC_NONE,
+                null_mut(),
+            );
+            if hr < 0 && hr != RPC_E_TOO_LATE {
+                return Err(format!("CoInitializeSecurity failed ({:#x})", hr));
+            }
+
+            let mut locator: *mut IWbemLocator = null_mut();
+            let hr = CoCreateInstance(
+                &CLSID_WbemLocator,
+                null_mut(),
+                CLSCTX_INPROC_SERVER,
+                &IWbemLocator::uuidof(),
+                &mut locator as *mut _ as *mut _,
+            );
+            if hr < 0 {
+                return Err(format!("Failed to create the WMI locator ({:#x})", hr));
+            }
+
+            let namespace = Bstr::new("ROOT\\CIMV2");
+            let hr = (*locator).ConnectServer(
+                namespace.0,
+                null_mut(),
+                null_mut(),
+                null_mut(),
+                0,
+                null_mut(),
+                null_mut(),
+                &mut source.services,
+            );
+            (*locator).Release();
+            if hr < 0 {
+                return Err(format!("Failed to connect to ROOT\\CIMV2 ({:#x})", hr));
+            }
+
+            let hr = CoSetProxyBlanket(
+                source.services as *mut IUnknown,
+                RPC_C_AUTHN_WINNT,
+                RPC_C_AUTHZ_NONE,
+                null_mut(),
+                RPC_C_AUTHN_LEVEL_CALL,
+                RPC_C_IMP_LEVEL_IMPERSONATE,
+                null_mut(),
+                EOAC_NONE,
+            );
+            if hr < 0 {
+                return Err(format!("CoSetProxyBlanket failed ({:#x})", hr));
+            }
+
+            let language = Bstr::new("WQL");
+            let query = Bstr::new(query);
+            let hr = (*source.services).ExecNotificationQuery(
+                language.0,
+                query.0,
+                (WBEM_FLAG_RETURN_IMMEDIATELY | WBEM_FLAG_FORWARD_ONLY) as i32,
+                null_mut(),
+                &mut source.events,
+            );
+            if hr < 0 {
+                return Err(format!("WMI notification query failed ({:#x})", hr));
+            }
+
+            Ok(source)
+        }
+    }
+
+    /// Reads PID and name from either event class.
+    unsafe fn read_started(event: *mut IWbemClassObject) -> Option<StartedProcess> {
+        let mut target: VARIANT = std::mem::zeroed();
+        let name = to_wide("TargetInstance");
+        let hr = (*event).Get(name.as_ptr(), 0, &mut target, null_mut(), null_mut());
+
+        let result = if hr >= 0 && target.n1.n2().vt == VT_UNKNOWN as u16 {
+            let unknown = *target.n1.n2().n3.punkVal();
+            let mut instance: *mut IWbemClassObject = null_mut();
+            if !unknown.is_null()
+                && (*unknown).QueryInterface(
+                    &IWbemClassObject::uuidof(),
+                    &mut instance as *mut _ as *mut _,
+                ) >= 0
+            {
+                let started = Self::read_process(instance, "ProcessId", "Name", "ParentProcessId");
+                (*instance).Release();
+                started
+            } else {
+                None
+            }
+        } else {
+            Self::read_process(event, "ProcessID", "ProcessName", "ParentProcessID")
+        };
+
+        VariantClear(&mut target);
+        result
+    }
+
+    unsafe fn read_u32(object: *mut IWbemClassObject, property: &str) -> Option<u32> {
+        let mut value: VARIANT = std::mem::zeroed();
+        let property = to_wide(property);
+        if (*object).Get(property.as_ptr(), 0, &mut value, null_mut(), null_mut()) < 0 {
+            return None;
+        }
+        let result = match value.n1.n2().vt as u32 {
+            VT_I4 => Some(*value.n1.n2().n3.lVal() as u32),
+            VT_UI4 => Some(*value.n1.n2().n3.ulVal()),
+            _ => None,
+        };
+        VariantClear(&mut value);
+        result
+    }
+
+    unsafe fn read_process(
+        object: *mut IWbemClassObject,
+        pid_property: &str,
+        name_property: &str,
+        parent_property: &str,
+    ) -> Option<StartedProcess> {
+        let pid = Self::read_u32(object, pid_property);
+
+        let mut value: VARIANT = std::mem::zeroed();
+        let property = to_wide(name_property);
+        if (*object).Get(property.as_ptr(), 0, &mut value, null_mut(), null_mut()) < 0 {
+            return None;
+        }
+        let name = if value.n1.n2().vt == VT_BSTR as u16 {
+            let bstr = *value.n1.n2().n3.bstrVal();
+            let len = SysStringLen(bstr) as usize;
+            Some(String::from_utf16_lossy(std::slice::from_raw_parts(
+                bstr, len,
+            )))
+        } else {
+            None
+        };
+        VariantClear(&mut value);
+
+        Some(StartedProcess {
+            pid: pid?,
+            name: name?,
+            parent: Self::read_u32(object, parent_property),
+        })
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl ProcessStartSource for WmiStartSource {
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn is_event_driven(&self) -> bool {
+        true
+    }
+
+    fn next_starts(&mut self, timeout: Duration) -> Result<Vec<StartedProcess>, String> {
+        let mut started = Vec::new();
+        let mut wait_ms = timeout.as_millis().min(i32::MAX as u128) as i32;
+        loop {
+            let mut event: *mut IWbemClassObject = null_mut();
+            let mut returned = 0;
+            let hr = unsafe { (*self.events).Next(wait_ms, 1, &mut event, &mut returned) };
+            if hr < 0 {
+                return Err(format!("WMI event query failed ({:#x})", hr));
+            }
+            if hr == WBEM_S_TIMEDOUT as i32 || returned == 0 || event.is_null() {
+                break;
+            }
+            unsafe {
+                if let Some(process) = Self::read_started(event) {
+                    started.push(process);
+                }
+                (*event).Release();
+            }
+            // Drain what is already queued, but only block for the first event.
+            wait_ms = 0;
+        }
+        Ok(started)
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for WmiStartSource {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.events.is_null() {
+                (*self.events).Release();
+            }
+            if !self.services.is_null() {
+                (*self.services).Release();
+            }
+            if self.com_initialized {
+                CoUninitialize();
+            }
+        }
+    }
+}
+
+// * NOTE: Linux proc connector
+// The kernel multicasts fork/exec/exit events over a `NETLINK_CONNECTOR` socket. Only
+// `exec` is used: that is when a process gets the name the classifier looks at.
+// Subscribing needs `CAP_NET_ADMIN`, so an unprivileged app falls back to
+// `NameScanSource`, like the WMI source on Windows.
+#[cfg(target_os = "linux")]
+const CN_IDX_PROC: u32 = 1;
+#[cfg(target_os = "linux")]
+const CN_VAL_PROC: u32 = 1;
+#[cfg(target_os = "linux")]
+const PROC_CN_MCAST_LISTEN: u32 = 1;
+#[cfg(target_os = "linux")]
+const PROC_EVENT_NONE: u32 = 0;
+#[cfg(target_os = "linux")]
+const PROC_EVENT_EXEC: u32 = 2;
+/// `struct nlmsghdr`.
+#[cfg(target_os = "linux")]
+const NLMSG_HEADER_LEN: usize = 16;
+/// `struct cn_msg` without its payload.
+#[cfg(target_os = "linux")]
+const CN_MSG_HEADER_LEN: usize = 20;
+/// `what`, `cpu` and `timestamp_ns` of `struct proc_event`.
+#[cfg(target_os = "linux")]
+const PROC_EVENT_HEADER_LEN: usize = 16;
+
+#[cfg(target_os = "linux")]
+pub struct ProcConnectorSource {
+    socket: OwnedFd,
+    /// Looks up name and parent of a PID from an `exec` event.
+    sys: System,
+}
+
+#[cfg(target_os = "linux")]
+impl ProcConnectorSource {
+    /// Subscribes to the proc connector.
+    pub fn connect() -> Result<Self, String> {
+        let os_error = |what: &str| format!("{} failed: {}", what, std::io::Error::last_os_error());
+        unsafe {
+            let fd = libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                libc::NETLINK_CONNECTOR,
+            );
+            if fd < 0 {
+                return Err(os_error("Creating the netlink socket"));
+            }
+            // Closes the socket on every early return below.
+            let socket = OwnedFd::from_raw_fd(fd);
+
+            let mut address: libc::sockaddr_nl = std::mem::zeroed();
+            address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+            address.nl_groups = CN_IDX_PROC;
+            if libc::bind(
+                fd,
+                &address as *const libc::sockaddr_nl as *const libc::sockaddr,
+                std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            ) < 0
+            {
+                return Err(os_error("Joining the proc connector group"));
+            }
+
+            let request = Self::listen_request();
+            if libc::send(
+                fd,
+                request.as_ptr() as *const libc::c_void,
+                request.len(),
+                0,
+            ) < 0
+            {
+                return Err(os_error("Subscribing to process events"));
+            }
+
+            // The kernel answers the subscription right away; a refused one (missing
+            // capability) is only visible in that answer.
+            let mut source = Self {
+                socket,
+                sys: System::new(),
+            };
+            source.next_starts(Duration::ZERO)?;
+            Ok(source)
+        }
+    }
+
+    /// `nlmsghdr` + `cn_msg` + `PROC_CN_MCAST_LISTEN`.
+    fn listen_request() -> Vec<u8> {
+        let len = NLMSG_HEADER_LEN + CN_MSG_HEADER_LEN + 4;
+        let mut request = Vec::with_capacity(len);
+        request.extend_from_slice(&(len as u32).to_ne_bytes());
+        request.extend_from_slice(&(libc::NLMSG_DONE as u16).to_ne_bytes());
+        request.extend_from_slice(&0u16.to_ne_bytes()); // flags
+        request.extend_from_slice(&0u32.to_ne_bytes()); // sequence
+        request.extend_from_slice(&std::process::id().to_ne_bytes());
+        request.extend_from_slice(&CN_IDX_PROC.to_ne_bytes());
+        request.extend_from_slice(&CN_VAL_PROC.to_ne_bytes());
+        request.extend_from_slice(&0u32.to_ne_bytes()); // sequence
+        request.extend_from_slice(&0u32.to_ne_bytes()); // ack
+        request.extend_from_slice(&4u16.to_ne_bytes()); // payload length
+        request.extend_from_slice(&0u16.to_ne_bytes()); // flags
+        request.extend_from_slice(&PROC_CN_MCAST_LISTEN.to_ne_bytes());
+        request
+    }
+
+    /// Waits until the socket is readable. `false` on timeout.
+    fn wait_readable(&self, timeout: Duration) -> Result<bool, String> {
+        let mut poll = libc::pollfd {
+            fd: self.socket.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout_ms = timeout.as_millis().min(i32::MAX as u128) as libc::c_int;
+        match unsafe { libc::poll(&mut poll, 1, timeout_ms) } {
+            n if n > 0 => Ok(true),
+            0 => Ok(false),
+            _ => {
+                let error = std::io::Error::last_os_error();
+                if error.kind() == std::io::ErrorKind::Interrupted {
+                    Ok(false)
+                } else {
+                    Err(format!("Waiting for process events failed: {}", error))
+                }
+            }
+        }
+    }
+
+    /// The TGID of an `exec` event, `None` for other events.
+    fn parse_event(message: &[u8]) -> Result<Option<u32>, String> {
+        let u32_at = |offset: usize| {
+            message
+                .get(offset..offset + 4)
+                .map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+        };
+        let cn = NLMSG_HEADER_LEN;
+        let event = cn + CN_MSG_HEADER_LEN;
+        if u32_at(cn) != Some(CN_IDX_PROC) || u32_at(cn + 4) != Some(CN_VAL_PROC) {
+            return Ok(None);
+        }
+        let data = event + PROC_EVENT_HEADER_LEN;
+        match u32_at(event) {
+            // Answer to the subscription, `err` is 0 on success.
+            Some(PROC_EVENT_NONE) => match u32_at(data) {
+                Some(0) | None => Ok(None),
+                Some(error) => Err(format!(
+                    "Subscribing to process events failed: {}",
+                    std::io::Error::from_raw_os_error(error as i32)
+                )),
+            },
+            // `exec_proc_event { process_pid, process_tgid }`
+            Some(PROC_EVENT_EXEC) => Ok(u32_at(data + 4)),
+            _ => Ok(None),
+        }
+    }
+
+    fn describe(&mut self, pid: u32) -> Option<StartedProcess> {
+        let sys_pid = Pid::from_u32(pid);
+        self.sys.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[sys_pid]),
+            true,
+            ProcessRefreshKind::nothing(),
+        );
+        // Already gone again, e.g. a short-lived shell command.
+        let process = self.sys.process(sys_pid)?;
+        Some(StartedProcess {
+            pid,
+            name: process.name().to_string_lossy().into_owned(),
+            parent: process.parent().map(|p| p.as_u32()),
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl ProcessStartSource for ProcConnectorSource {
+    fn description(&self) -> &'static str {
+        "proc connector"
+    }
+
+    fn is_event_driven(&self) -> bool {
+        true
+    }
+
+    fn next_starts(&mut self, timeout: Duration) -> Result<Vec<StartedProcess>, String> {
+        if !self.wait_readable(timeout)? {
+            return Ok(Vec::new());
+        }
+
+        let mut pids = Vec::new();
+        let mut buffer = [0u8; 256];
+        loop {
+            let received = unsafe {
+                libc::recv(
+                    self.socket.as_raw_fd(),
+                    buffer.as_mut_ptr() as *mut libc::c_void,
+                    buffer.len(),
+                    libc::MSG_DONTWAIT,
+                )
+            };
+            if received < 0 {
+                let error = std::io::Error::last_os_error();
+                match error.raw_os_error() {
+                    Some(libc::EAGAIN) | Some(libc::EINTR) => break,
+                    // Events were dropped, a start may be missing.
+                    Some(libc::ENOBUFS) => {
+                        return Err("Process events were lost".to_string());
+                    }
+                    _ => return Err(format!("Reading process events failed: {}", error)),
+                }
+            }
+            if let Some(pid) = Self::parse_event(&buffer[..received as usize])? {
+                if !pids.contains(&pid) {
+                    pids.push(pid);
+                }
+            }
+        }
+        Ok(pids
+            .into_iter()
+            .filter_map(|pid| self.describe(pid))
+            .collect())
+    }
+}
+
+/// Handle that is signalled when the process exits.
+#[cfg(target_os = "windows")]
+struct ExitHandle(HANDLE);
+
+#[cfg(target_os = "windows")]
+impl ExitHandle {
+    fn open(pid: u32) -> Option<Self> {
+        let handle = unsafe { OpenProcess(SYNCHRONIZE, FALSE, pid) };
+        (!handle.is_null()).then_some(Self(handle))
+    }
+
+    fn has_exited(&self) -> bool {
+        unsafe { WaitForSingleObject(self.0, 0) == WAIT_OBJECT_0 }
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for ExitHandle {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
+
+/// The best start source available on this system, and notices about the ones that
+/// were not.
+pub fn best_start_source() -> (Box<dyn ProcessStartSource>, Vec<String>) {
+    let mut notices = Vec::new();
+
+    #[cfg(target_os = "windows")]
+    let source: Box<dyn ProcessStartSource> = match WmiStartSource::connect() {
+        Ok(source) => Box::new(source),
+        Err(e) => {
+            notices.push(format!(
+                "WMI process events unavailable ({}), scanning instead",
+                e
+            ));
+            Box::new(NameScanSource::new())
+        }
+    };
+    #[cfg(target_os = "linux")]
+    let source: Box<dyn ProcessStartSource> = match ProcConnectorSource::connect() {
+        Ok(source) => Box::new(source),
+        Err(e) => {
+            notices.push(format!(
+                "Process events unavailable ({}), scanning instead",
+                e
+            ));
+            Box::new(NameScanSource::new())
+        }
+    };
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    let source: Box<dyn ProcessStartSource> = Box::new(NameScanSource::new());
+
+    (source, notices)
+}
+
+struct TrackedProcess {
+    name: String,
+    target: String,
+    parent: Option<u32>,
+    #[cfg(target_os = "windows")]
+    exit_handle: Option<ExitHandle>,
+}
+
+/// Watches for processes the classifier accepts and for the processes they start.
+///
+/// While no target runs, new processes come from the start source. Once a target runs,
+/// only its PIDs are checked: on Windows by waiting on the process handles (the exit is
+/// noticed immediately), otherwise by refreshing just those PIDs. An event-driven source
+/// is still drained on every call to catch additional instances and child processes; a
+/// scanning source is not, so children started later are only found by event sources.
+pub struct ProcessWatcher {
+    classifier: Classifier,
+    source: Box<dyn ProcessStartSource>,
+    tracked: HashMap<u32, TrackedProcess>,
+    /// Used for the initial scan and for PIDs that cannot be waited on.
+    sys: System,
+    pending: Vec<WatchEvent>,
+    notices: Vec<String>,
+}
+
+impl ProcessWatcher {
+    /// Creates a watcher using the best start source available on this system.
+    pub fn new(classifier: Classifier) -> Self {
+        let (source, notices) = best_start_source();
+        Self::with_source(classifier, source, notices)
+    }
+
+    pub fn with_source(
+        classifier: Classifier,
+        source: Box<dyn ProcessStartSource>,
+        mut notices: Vec<String>,
+    ) -> Self {
+        notices.push(format!("Process watcher uses the {}", source.description()));
+        let mut watcher = Self {
+            classifier,
+            source,
+            tracked: HashMap::new(),
+            sys: System::new(),
+            pending: Vec::new(),
+            notices,
+        };
+        watcher.initial_scan();
+        watcher
+    }
+
+    /// Event sources only report processes started after the subscription,
+    /// so targets that are already running are found with one scan.
+    fn initial_scan(&mut self) {
+        self.sys.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing(),
+        );
+        let running: Vec<StartedProcess> = self
+            .sys
+            .processes()
+            .iter()
+            .map(|(pid, process)| StartedProcess {
+                pid: pid.as_u32(),
+                name: process.name().to_string_lossy().into_owned(),
+                parent: process.parent().map(|p| p.as_u32()),
+            })
+            .collect();
+
+        let mut unmatched = Vec::new();
+        for process in running {
+            if self.tracked.contains_key(&process.pid) {
+                continue;
+            }
+            match (self.classifier)(&process) {
+                Some(target) => self.insert(process, target),
+                None => unmatched.push(process),
+            }
+        }
+        // Children of the targets, one generation per pass. The classifier is not asked
+        // again, it may be expensive.
+        loop {
+            let before = unmatched.len();
+            unmatched.retain(|process| match self.parent_target(process) {
+                Some(target) => {
+                    self.insert(process.clone(), target);
+                    false
+                }
+                None => true,
+            });
+            if unmatched.len() == before {
+                break;
+            }
+        }
+    }
+
+    /// Target of the tracked parent: processes a target starts belong to it.
+    fn parent_target(&self, process: &StartedProcess) -> Option<String> {
+        let parent = self.tracked.get(&process.parent?)?;
+        Some(parent.target.clone())
+    }
+
+    /// Classifies all running processes again, e.g. after the targets changed.
+    ///
+    /// Processes that no longer match are reported as exited, newly matching ones as
+    /// started.
+    pub fn reclassify(&mut self) {
+        let tracked: Vec<(StartedProcess, String)> = self
+            .tracked
+            .iter()
+            .map(|(pid, p)| {
+                let process = StartedProcess {
+                    pid: *pid,
+                    name: p.name.clone(),
+                    parent: p.parent,
+                };
+                (process, p.target.clone())
+            })
+            .collect();
+        for (process, target) in tracked {
+            let current = (self.classifier)(&process).or_else(|| self.parent_target(&process));
+            if current.as_deref() != Some(target.as_str()) {
+                self.untrack(process.pid);
+            }
+        }
+        self.initial_scan();
+    }
+
+    fn track(&mut self, process: StartedProcess) {
+        if self.tracked.contains_key(&process.pid) {
+            return;
+        }
+        let target = (self.classifier)(&process).or_else(|| self.parent_target(&process));
+        if let Some(target) = target {
+            self.insert(process, target);
+        }
+    }
+
+    fn insert(&mut self, process: StartedProcess, target: String) {
+        self.tracked.insert(
+            process.pid,
+            TrackedProcess {
+                name: process.name.clone(),
+                target: target.clone(),
+                parent: process.parent,
+                #[cfg(target_os = "windows")]
+                exit_handle: ExitHandle::open(process.pid),
+            },
+        );
+        self.pending.push(WatchEvent::Started {
+            pid: process.pid,
+            name: process.name,
+            target,
+        });
+    }
+
+    /// The target a tracked process belongs to.
+    pub fn target_of(&self, pid: u32) -> Option<&str> {
+        self.tracked.get(&pid).map(|p| p.target.as_str())
+    }
+
+    pub fn source_description(&self) -> &'static str {
+        self.source.description()
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.tracked.is_empty()
+    }
+
+    /// Messages for the log (chosen source, fallbacks). Emptied on every call.
+    pub fn drain_notices(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.notices)
+    }
+
+    /// Waits up to `timeout` and returns what happened since the last call.
+    pub fn next_events(&mut self, timeout: Duration) -> Vec<WatchEvent> {
+        if !self.pending.is_empty() {
+            return std::mem::take(&mut self.pending);
+        }
+
+        let starts = if self.tracked.is_empty() {
+            self.source.next_starts(timeout)
+        } else {
+            self.wait_for_exits(timeout);
+            if self.source.is_event_driven() {
+                self.source.next_starts(Duration::ZERO)
+            } else {
+                Ok(Vec::new())
+            }
+        };
+
+        match starts {
+            Ok(starts) => {
+                for process in starts {
+                    self.track(process);
+                }
+            }
+            Err(e) => {
+                self.notices.push(format!(
+                    "{} failed ({}), falling back to the process list scan",
+                    self.source.description(),
+                    e
+                ));
+                self.source = Box::new(NameScanSource::new());
+            }
+        }
+
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Blocks up to `timeout` or until a tracked process exits.
+    fn wait_for_exits(&mut self, timeout: Duration) {
+        #[cfg(target_os = "windows")]
+        {
+            let handles: Vec<HANDLE> = self
+                .tracked
+                .values()
+                .filter_map(|p| p.exit_handle.as_ref().map(|h| h.0))
+                .collect();
+            let all_waitable = handles.len() == self.tracked.len();
+            // `WaitForMultipleObjects` accepts at most 64 handles.
+            if !handles.is_empty() && handles.len() <= 64 {
+                let result = unsafe {
+                    WaitForMultipleObjects(
+                        handles.len() as u32,
+                        handles.as_ptr(),
+                        FALSE,
+                        timeout.as_millis().min(u32::MAX as u128) as u32,
+                    )
+                };
+                if result == WAIT_FAILED {
+                    std::thread::sleep(timeout);
+                }
+            } else {
+                std::thread::sleep(timeout);
+            }
+
+            let exited: Vec<u32> = self
+                .tracked
+                .iter()
+                .filter(|(_, p)| p.exit_handle.as_ref().is_some_and(|h| h.has_exited()))
+                .map(|(pid, _)| *pid)
+                .collect();
+            for pid in exited {
+                self.untrack(pid);
+            }
+            if all_waitable {
+                return;
+            }
+        }
+        #[cfg(not(target_os = "windows"))]
+        std::thread::sleep(timeout);
+
+        self.poll_liveness();
+    }
+
+    /// Refreshes only the tracked PIDs. A PID that is gone or now belongs to a process
+    /// with another name counts as exited.
+    fn poll_liveness(&mut self) {
+        let pids: Vec<Pid> = self.tracked.keys().map(|pid| Pid::from_u32(*pid)).collect();
+        self.sys.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&pids),
+            true,
+            ProcessRefreshKind::nothing(),
+        );
+        let exited: Vec<u32> = self
+            .tracked
+            .iter()
+            .filter(|(pid, tracked)| {
+                self.sys
+                    .process(Pid::from_u32(**pid))
+                    .map_or(true, |p| !p.name().eq_ignore_ascii_case(&tracked.name))
+            })
+            .map(|(pid, _)| *pid)
+            .collect();
+        for pid in exited {
+            self.untrack(pid);
+        }
+    }
+
+    fn untrack(&mut self, pid: u32) {
+        if let Some(process) = self.tracked.remove(&pid) {
+            self.pending.push(WatchEvent::Exited {
+                pid,
+                name: process.name,
+                target: process.target,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    /// Far above any real PID, so these processes never exist.
+    const FAKE_PID: u32 = 4_000_000_000;
+
+    /// Replays scripted batches of starts.
+    struct FakeSource {
+        batches: VecDeque<Result<Vec<StartedProcess>, String>>,
+    }
+
+    impl ProcessStartSource for FakeSource {
+        fn description(&self) -> &'static str {
+            "fake source"
+        }
+
+        fn is_event_driven(&self) -> bool {
+            true
+        }
+
+        fn next_starts(&mut self, _timeout: Duration) -> Result<Vec<StartedProcess>, String> {
+            self.batches.pop_front().unwrap_or_else(|| Ok(Vec::new()))
+        }
+    }
+
+    fn started(pid: u32, name: &str, parent: Option<u32>) -> StartedProcess {
+        StartedProcess {
+            pid,
+            name: name.to_string(),
+            parent,
+        }
+    }
+
+    /// Accepts only fake processes named `game.exe` while `enabled` is set.
+    fn watcher(
+        batches: Vec<Result<Vec<StartedProcess>, String>>,
+        enabled: Rc<Cell<bool>>,
+    ) -> ProcessWatcher {
+        let classifier: Classifier = Box::new(move |process| {
+            (enabled.get() && process.pid >= FAKE_PID && process.name == "game.exe")
+                .then(|| "game".to_string())
+        });
+        let source = Box::new(FakeSource {
+            batches: batches.into(),
+        });
+        ProcessWatcher::with_source(classifier, source, Vec::new())
+    }
+
+    fn pids(events: &[WatchEvent], started: bool) -> Vec<u32> {
+        let mut pids: Vec<u32> = events
+            .iter()
+            .filter_map(|event| match event {
+                WatchEvent::Started { pid, .. } if started => Some(*pid),
+                WatchEvent::Exited { pid, .. } if !started => Some(*pid),
+                _ => None,
+            })
+            .collect();
+        pids.sort_unstable();
+        pids
+    }
+
+    #[test]
+    fn tracks_targets_and_their_children() {
+        let batch = vec![
+            started(FAKE_PID, "game.exe", None),
+            started(FAKE_PID + 1, "helper.exe", Some(FAKE_PID)),
+            started(FAKE_PID + 2, "helper.exe", Some(1)),
+        ];
+        let mut watcher = watcher(vec![Ok(batch)], Rc::new(Cell::new(true)));
+        assert!(!watcher.is_running());
+
+        let events = watcher.next_events(Duration::ZERO);
+        assert_eq!(pids(&events, true), vec![FAKE_PID, FAKE_PID + 1]);
+        assert_eq!(watcher.target_of(FAKE_PID + 1), Some("game"));
+        assert_eq!(watcher.target_of(FAKE_PID + 2), None);
+        assert!(watcher.is_running());
+    }
+
+    #[test]
+    fn reports_processes_that_are_gone_as_exited() {
+        let batch = vec![
+            started(FAKE_PID, "game.exe", None),
+            started(FAKE_PID + 1, "helper.exe", Some(FAKE_PID)),
+        ];
+        let mut watcher = watcher(vec![Ok(batch)], Rc::new(Cell::new(true)));
+        watcher.next_events(Duration::ZERO);
+
+        // The fake PIDs do not exist, so the liveness check drops them.
+        let events = watcher.next_events(Duration::ZERO);
+        assert_eq!(pids(&events, false), vec![FAKE_PID, FAKE_PID + 1]);
+        assert!(!watcher.is_running());
+    }
+
+    #[test]
+    fn reclassify_drops_processes_that_no_longer_match() {
+        let enabled = Rc::new(Cell::new(true));
+        let batch = vec![started(FAKE_PID, "game.exe", None)];
+        let mut watcher = watcher(vec![Ok(batch)], enabled.clone());
+        watcher.next_events(Duration::ZERO);
+
+        enabled.set(false);
+        watcher.reclassify();
+        let events = watcher.next_events(Duration::ZERO);
+        assert_eq!(pids(&events, false), vec![FAKE_PID]);
+        assert!(!watcher.is_running());
+    }
+
+    #[test]
+    fn falls_back_to_scanning_when_the_source_fails() {
+        let mut watcher = watcher(vec![Err("gone".to_string())], Rc::new(Cell::new(true)));
+        assert_eq!(watcher.source_description(), "fake source");
+        watcher.drain_notices();
+
+        assert!(watcher.next_events(Duration::ZERO).is_empty());
+        assert_eq!(watcher.source_description(), "process list scan");
+        let notices = watcher.drain_notices();
+        assert!(notices[0].contains("fake source failed (gone)"));
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod proc_connector_tests {
+    use super::*;
+
+    fn message(what: u32, data: &[u32]) -> Vec<u8> {
+        let mut message = ProcConnectorSource::listen_request();
+        message.truncate(NLMSG_HEADER_LEN + CN_MSG_HEADER_LEN);
+        message.extend_from_slice(&what.to_ne_bytes());
+        message.extend_from_slice(&0u32.to_ne_bytes()); // cpu
+        message.extend_from_slice(&0u64.to_ne_bytes()); // timestamp
+        for value in data {
+            message.extend_from_slice(&value.to_ne_bytes());
+        }
+        message
+    }
+
+    #[test]
+    fn parses_exec_events() {
+        // A thread of process 100 called `exec`: the TGID counts.
+        let exec = message(PROC_EVENT_EXEC, &[101, 100]);
+        assert_eq!(ProcConnectorSource::parse_event(&exec), Ok(Some(100)));
+
+        // fork: parent PID/TGID, child PID/TGID
+        let fork = message(1, &[1, 1, 200, 200]);
+        assert_eq!(ProcConnectorSource::parse_event(&fork), Ok(None));
+    }
+
+    #[test]
+    fn refused_subscription_is_an_error() {
+        let accepted = message(PROC_EVENT_NONE, &[0]);
+        assert_eq!(ProcConnectorSource::parse_event(&accepted), Ok(None));
+
+        let refused = message(PROC_EVENT_NONE, &[libc::EPERM as u32]);
+        assert!(ProcConnectorSource::parse_event(&refused).is_err());
+    }
+
+    #[test]
+    fn ignores_short_and_foreign_messages() {
+        let exec = message(PROC_EVENT_EXEC, &[101, 100]);
+        assert_eq!(ProcConnectorSource::parse_event(&exec[..30]), Ok(None));
+        assert_eq!(
+            ProcConnectorSource::parse_event(&exec[..exec.len() - 4]),
+            Ok(None)
+        );
+
+        let mut other = exec.clone();
+        other[NLMSG_HEADER_LEN] = 7;
+        assert_eq!(ProcConnectorSource::parse_event(&other), Ok(None));
+    }
+}
@@ -1,5 +1,9 @@
+use crate::pe_version::{ExecutableMetadata, ExecutableMetadataCache};
+use crate::verification::Verification;
 use serde::{Deserialize, Serialize};
-use sysinfo::{ProcessesToUpdate, System};
+use sysinfo::{
+    Pid, Process, ProcessRefreshKind, ProcessesToUpdate, System, MINIMUM_CPU_UPDATE_INTERVAL,
+};
 use tauri::{AppHandle, Emitter};
 
 // * HINWEIS: Conditional Compilation
@@ -8,31 +12,249 @@ use tauri::{AppHandle, Emitter};
 #[cfg(target_os = "windows")]
 use winapi::shared::minwindef::FALSE;
 #[cfg(target_os = "windows")]
+use winapi::shared::ntdef::{HANDLE, NTSTATUS};
+#[cfg(target_os = "windows")]
 use winapi::um::handleapi::CloseHandle;
 #[cfg(target_os = "windows")]
-use winapi::um::processthreadsapi::OpenProcess;
+use winapi::um::processthreadsapi::{
+    GetPriorityClass, OpenProcess, SetPriorityClass, SetProcessInformation,
+};
+#[cfg(target_os = "windows")]
+use winapi::um::winbase::{
+    GetProcessAffinityMask, SetProcessAffinityMask, ABOVE_NORMAL_PRIORITY_CLASS,
+    BELOW_NORMAL_PRIORITY_CLASS, HIGH_PRIORITY_CLASS, IDLE_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS,
+};
+#[cfg(target_os = "windows")]
+use winapi::um::winnt::{
+    PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SET_INFORMATION, PROCESS_SUSPEND_RESUME,
+    PROCESS_TERMINATE,
+};
+
+// * HINWEIS: Undokumentierte NT-Funktionen
+// `NtSuspendProcess`/`NtResumeProcess` sind nicht in `winapi` enthalten, werden aber von
+// Tools wie Process Explorer genutzt. Sie halten alle Threads eines Prozesses auf einmal an,
+// statt jeden Thread einzeln per `SuspendThread` anfassen zu müssen.
+#[cfg(target_os = "windows")]
+#[link(name = "ntdll")]
+extern "system" {
+    fn NtSuspendProcess(process_handle: HANDLE) -> NTSTATUS;
+    fn NtResumeProcess(process_handle: HANDLE) -> NTSTATUS;
+    fn NtSetInformationProcess(
+        process_handle: HANDLE,
+        process_information_class: u32,
+        process_information: *mut std::ffi::c_void,
+        process_information_length: u32,
+    ) -> NTSTATUS;
+}
+
+// * HINWEIS: Konstanten, die `winapi` 0.3 noch nicht kennt
+// `ProcessIoPriority` stammt aus `PROCESSINFOCLASS` (ntddk), `ProcessPowerThrottling` aus
+// `PROCESS_INFORMATION_CLASS` (Windows 10 1709+).
+#[cfg(target_os = "windows")]
+const PROCESS_IO_PRIORITY_CLASS: u32 = 33;
 #[cfg(target_os = "windows")]
-use winapi::um::winbase::SetProcessAffinityMask;
+const PROCESS_POWER_THROTTLING_CLASS: u32 = 4;
+#[cfg(target_os = "windows")]
+const PROCESS_POWER_THROTTLING_CURRENT_VERSION: u32 = 1;
+#[cfg(target_os = "windows")]
+const PROCESS_POWER_THROTTLING_EXECUTION_SPEED: u32 = 0x1;
+
+#[cfg(target_os = "windows")]
+#[repr(C)]
+struct ProcessPowerThrottlingState {
+    version: u32,
+    control_mask: u32,
+    state_mask: u32,
+}
+
+/// Prozesse, die nie beendet, angehalten, gedrosselt oder von Regeln verändert werden.
+///
+/// Einzige Quelle der Liste; das Frontend holt sie über `get_protected_processes`.
+pub const PROTECTED_PROCESS_NAMES: &[&str] = &[
+    // Add any other critical process names here, e.g., specific anti-cheat software.
+    "[System Process]",            // System Process
+    "amdfendrsr.exe",              // AMD External Events Utility
+    "AmdPpkgSvc.exe",              // AMD Power Profiling SDK Service
+    "ApplicationFrameHost.exe",    // Application Frame Host
+    "atieclxx.exe",                // AMD Driver
+    "atiesrxx.exe",                // AMD External Events Utility
+    "audiodg.exe",                 // Audio Engine
+    "cmd.exe",                     // Command Prompt
+    "conhost.exe",                 // Console Window Host
+    "csrss.exe",                   // Client/Server Runtime Subsystem
+    "cs2.exe",                     // The game itself
+    "CS2ToolsByJonny.exe",         // The CS2 Tools application itself
+    "ctfmon.exe",                  // Keyboard Input Loader
+    "dasHost.exe",                 // Device Association Framework Host
+    "DataExchangeHost.exe",        // Data Exchange Host
+    "dllhost.exe",                 // COM Surrogate
+    "dwm.exe",                     // Desktop Window Manager
+    "explorer.exe",                // Windows Explorer
+    "fontdrvhost.exe",             // Font Driver Host
+    "KillerAnalyticsService.exe",  // Network Driver Utility
+    "KillerNetworkService.exe",    // Network Driver
+    "lsass.exe",                   // Local Security Authority Process
+    "LsaIso.exe",                  // LSA Isolated User Mode Process
+    "Memory Compression",          // Memory Compression Process
+    "msedgewebview2.exe",          // Microsoft Edge WebView2 (if used)
+    "MpDefenderCoreService.exe",   // Windows Defender
+    "MsMpEng.exe",                 // Windows Defender
+    "NisSrv.exe",                  // Network Inspection Service (Windows Defender)
+    "NVDisplay.Container.exe",     // NVIDIA Display Container LS
+    "Registry",                    // Windows Registry
+    "RtkAudUService64.exe",        // Audio Driver
+    "RuntimeBroker.exe",           // Store App Permissions Manager
+    "SearchFilterHost.exe",        // Windows Search Filter Host
+    "SearchHost.exe",              // Windows Search Host
+    "SearchIndexer.exe",           // Windows Search
+    "SearchProtocolHost.exe",      // Windows Search Protocol Host
+    "Secure System",               // Secure System Process
+    "SecurityHealthService.exe",   // Windows Security Health Service
+    "SecurityHealthSystray.exe",   // Windows Security Health Systray
+    "services.exe",                // Services Control Manager
+    "ShellExperienceHost.exe",     // Shell Experience Host
+    "ShellHost.exe",               // Windows Shell Host
+    "sihost.exe",                  // Shell Infrastructure
+    "smss.exe",                    // Session Manager Subsystem
+    "spoolsv.exe",                 // Print Spooler Service
+    "StartMenuExperienceHost.exe", // Start Menu Experience Host
+    "steam.exe",                   // Steam Client (if the user has it installed and running)
+    "steamservice.exe",            // Steam Client Service
+    "steamwebhelper.exe",          // Steam Web Helper
+    "System",                      // Another name for the System Process
+    "SystemSettings.exe",          // System Settings
+    "System Idle Process",         // System Idle Process
+    "svchost.exe",                 // Service Host Process
+    "taskhostw.exe",               // Task Host for Windows
+    "taskmgr.exe",                 // Task Manager
+    "TextInputHost.exe",
+    "wininit.exe",    // Windows Start-Up Application
+    "winlogon.exe",   // Windows Logon Application
+    "wlanext.exe",    // WiFi Driver
+    "WmiPrvSE.exe",   // Windows Management Instrumentation
+    "wslservice.exe", // Subsystem for Linux (System Service)
+    "WUDFHost.exe",   // Windows Driver Foundation
+];
+
+/// Prüft (case-insensitive), ob ein Prozessname geschützt ist.
+pub fn is_process_protected(process_name: &str) -> bool {
+    PROTECTED_PROCESS_NAMES
+        .iter()
+        .any(|protected| protected.eq_ignore_ascii_case(process_name))
+}
+
+/// Prioritätsklasse eines Prozesses.
+///
+/// `Realtime` wird bewusst nicht angeboten: Ein Prozess mit Echtzeit-Priorität kann
+/// Maus, Tastatur und sogar den Audio-Stack des Systems aushungern.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ProcessPriority {
+    Idle,
+    BelowNormal,
+    Normal,
+    AboveNormal,
+    High,
+}
+
+#[cfg(target_os = "windows")]
+impl ProcessPriority {
+    fn as_priority_class(self) -> u32 {
+        match self {
+            ProcessPriority::Idle => IDLE_PRIORITY_CLASS,
+            ProcessPriority::BelowNormal => BELOW_NORMAL_PRIORITY_CLASS,
+            ProcessPriority::Normal => NORMAL_PRIORITY_CLASS,
+            ProcessPriority::AboveNormal => ABOVE_NORMAL_PRIORITY_CLASS,
+            ProcessPriority::High => HIGH_PRIORITY_CLASS,
+        }
+    }
+
+    fn from_priority_class(class: u32) -> Option<Self> {
+        match class {
+            IDLE_PRIORITY_CLASS => Some(ProcessPriority::Idle),
+            BELOW_NORMAL_PRIORITY_CLASS => Some(ProcessPriority::BelowNormal),
+            NORMAL_PRIORITY_CLASS => Some(ProcessPriority::Normal),
+            ABOVE_NORMAL_PRIORITY_CLASS => Some(ProcessPriority::AboveNormal),
+            HIGH_PRIORITY_CLASS => Some(ProcessPriority::High),
+            _ => None,
+        }
+    }
+}
+
+/// I/O-Priorität eines Prozesses (Festplatten- und Netzwerkzugriffe).
+///
+/// `High` und `Critical` werden nicht angeboten: Windows erlaubt sie nur mit
+/// `SeIncreaseBasePriorityPrivilege`, und sie würden Treiber-I/O verdrängen.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum IoPriority {
+    VeryLow,
+    Low,
+    Normal,
+}
+
 #[cfg(target_os = "windows")]
-use winapi::um::winnt::{PROCESS_SET_INFORMATION, PROCESS_TERMINATE};
+impl IoPriority {
+    fn as_io_priority_hint(self) -> u32 {
+        match self {
+            IoPriority::VeryLow => 0,
+            IoPriority::Low => 1,
+            IoPriority::Normal => 2,
+        }
+    }
+}
 
 /// Informationen über einen laufenden Prozess.
 ///
 /// Wird an das Frontend gesendet.
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ProcessInfo {
     pub pid: u32,
     pub name: String,
     pub memory: u64,
+    /// CPU-Auslastung in Prozent (summiert über alle Kerne).
+    /// Ist nur aussagekräftig, wenn dasselbe `System` mindestens zweimal aktualisiert wurde;
+    /// `get_processes` und der Prozess-Stream tun das.
+    pub cpu_usage: f32,
+    /// Versionsinformationen der Programmdatei (Beschreibung, Hersteller, ...).
+    /// Wird nur auf Anfrage gelesen, siehe `get_processes`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ExecutableMetadata>,
+}
+
+impl ProcessInfo {
+    /// Erstellt ein `ProcessInfo` aus einem `sysinfo`-Prozesseintrag.
+    pub fn from_process(pid: &Pid, process: &Process) -> Self {
+        Self {
+            pid: pid.as_u32(),
+            // `to_string_lossy()`: Konvertiert den OS-spezifischen String (OsStr) in einen UTF-8 String.
+            // Falls ungültige Zeichen enthalten sind, werden sie ersetzt.
+            // `into_owned()`: Erstellt einen `String` (Heap) aus dem `Cow` (Copy-on-Write) Rückgabewert.
+            name: process.name().to_string_lossy().into_owned(),
+            memory: process.memory(),
+            cpu_usage: process.cpu_usage(),
+            metadata: None,
+        }
+    }
 }
 
 /// Ruft eine Liste aller laufenden Prozesse ab.
 ///
+/// # Arguments
+/// * `include_metadata` - Wenn `true`, werden zusätzlich Beschreibung, Hersteller, Produktname
+///   und Version aus der Programmdatei gelesen (zwischengespeichert pro Pfad).
+///
 /// # Returns
-/// * `Vec<ProcessInfo>`: Eine Liste mit PID, Name und Speicherverbrauch aller Prozesse.
-#[tauri::command]
-pub fn get_processes() -> Vec<ProcessInfo> {
+/// * `Vec<ProcessInfo>`: Eine Liste mit PID, Name, Speicherverbrauch und CPU-Auslastung
+///   aller Prozesse.
+///
+/// Läuft nicht im Haupt-Thread (`async`), weil die CPU-Messung kurz warten muss.
+#[tauri::command(async)]
+pub fn get_processes(
+    cache: tauri::State<ExecutableMetadataCache>,
+    include_metadata: Option<bool>,
+) -> Vec<ProcessInfo> {
     // * HINWEIS: `sysinfo` Crate
     // Wir nutzen `sysinfo` als plattformübergreifende Bibliothek, um Systeminformationen zu sammeln.
     // `System::new()` initialisiert die Struktur, lädt aber noch keine Daten.
@@ -44,20 +266,27 @@ pub fn get_processes() -> Vec<ProcessInfo> {
     // `true` (zweites Argument) bedeutet, dass auch Speicherinformationen aktualisiert werden sollen.
     sys.refresh_processes(ProcessesToUpdate::All, true);
 
+    // * HINWEIS: CPU-Auslastung
+    // `sysinfo` berechnet die Auslastung aus der Differenz zweier Messungen. Nach dem
+    // ersten Refresh wäre sie für jeden Prozess 0, deshalb wird nach der Mindestwartezeit
+    // ein zweites Mal gemessen.
+    std::thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+    sys.refresh_processes(ProcessesToUpdate::All, true);
+
     // * HINWEIS: Iterator-Kette (Functional Programming)
     // 1. `sys.processes()` gibt eine HashMap zurück (PID -> Process).
     // 2. `.iter()` erstellt einen Iterator über die Einträge.
     // 3. `.map(...)` transformiert jeden Eintrag in unser `ProcessInfo` Struct.
     // 4. `.collect()` sammelt die Ergebnisse in einen `Vec`.
+    let include_metadata = include_metadata.unwrap_or(false);
     sys.processes()
         .iter()
-        .map(|(pid, process)| ProcessInfo {
-            pid: pid.as_u32(),
-            // `to_string_lossy()`: Konvertiert den OS-spezifischen String (OsStr) in einen UTF-8 String.
-            // Falls ungültige Zeichen enthalten sind, werden sie ersetzt.
-            // `into_owned()`: Erstellt einen `String` (Heap) aus dem `Cow` (Copy-on-Write) Rückgabewert.
-            name: process.name().to_string_lossy().into_owned(),
-            memory: process.memory(),
+        .map(|(pid, process)| {
+            let mut info = ProcessInfo::from_process(pid, process);
+            if include_metadata {
+                info.metadata = process.exe().and_then(|exe| cache.get(exe));
+            }
+            info
         })
         .collect()
 }
@@ -68,22 +297,32 @@ pub fn get_processes() -> Vec<ProcessInfo> {
 /// * `pid` - Die Prozess-ID des zu beendenden Prozesses.
 #[tauri::command]
 pub fn terminate_process(app: AppHandle, pid: u32) -> Result<(), String> {
+    kill_process(pid).inspect_err(|err_msg| {
+        let _ = app.emit("log-error", err_msg);
+    })
+}
+
+/// Beendet einen Prozess, ohne Fehler an das Frontend zu melden.
+///
+/// Enthält alle Sicherheitsprüfungen von `terminate_process` und wird auch von den
+/// Gruppen-Aktionen genutzt, die ihre Fehler gesammelt zurückgeben.
+pub fn kill_process(pid: u32) -> Result<(), String> {
     // * SECURITY: Block termination of Windows system processes (System Idle Process and System).
     // These PIDs are always reserved by the OS and must never be killed by user-space apps.
     if pid == 0 || pid == 4 {
-        let err_msg = format!(
+        return Err(format!(
             "Refusing to terminate protected system process with PID {}",
             pid
-        );
-        let _ = app.emit("log-error", &err_msg);
-        return Err(err_msg);
+        ));
     }
 
     // * SECURITY: Prevent the app from killing its own process.
     if pid == std::process::id() {
-        let err_msg = "Refusing to terminate own process".to_string();
-        let _ = app.emit("log-error", &err_msg);
-        return Err(err_msg);
+        return Err("Refusing to terminate own process".to_string());
+    }
+
+    if let Some(name) = protected_process_name(pid) {
+        return Err(format!("Refusing to terminate protected process {}", name));
     }
 
     // * SECURITY: On Windows, verify we hold PROCESS_TERMINATE rights before proceeding.
@@ -94,12 +333,10 @@ pub fn terminate_process(app: AppHandle, pid: u32) -> Result<(), String> {
         unsafe {
             let handle = OpenProcess(PROCESS_TERMINATE, FALSE, pid);
             if handle.is_null() {
-                let err_msg = format!(
+                return Err(format!(
                     "Permission denied: cannot open process with PID {} for termination",
                     pid
-                );
-                let _ = app.emit("log-error", &err_msg);
-                return Err(err_msg);
+                ));
             }
             // Close the probe handle immediately; sysinfo will open its own handle below.
             CloseHandle(handle);
@@ -107,12 +344,13 @@ pub fn terminate_process(app: AppHandle, pid: u32) -> Result<(), String> {
     }
 
     let mut sys = System::new();
-    // Wir müssen die Prozesse aktualisieren, um sicherzustellen, dass der Prozess existiert
-    // und wir ein aktuelles Handle darauf bekommen.
-    sys.refresh_processes(ProcessesToUpdate::All, true);
-
     let sys_pid = sysinfo::Pid::from_u32(pid);
 
+    // Wir müssen den Prozess aktualisieren, um sicherzustellen, dass er existiert
+    // und wir ein aktuelles Handle darauf bekommen. Nur diese eine PID zu laden ist deutlich
+    // günstiger als alle Prozesse – wichtig, wenn eine ganze Gruppe beendet wird.
+    sys.refresh_processes(ProcessesToUpdate::Some(&[sys_pid]), true);
+
     // * HINWEIS: `if let` Syntax
     // Das ist eine Kurzform für ein `match`, wenn uns nur der `Some`-Fall interessiert.
     // "Wenn `sys.process(sys_pid)` einen Wert (`Some(process)`) zurückgibt, dann führe den Block aus."
@@ -121,17 +359,32 @@ pub fn terminate_process(app: AppHandle, pid: u32) -> Result<(), String> {
         if process.kill() {
             Ok(())
         } else {
-            let err_msg = format!("Failed to kill process with PID {}", pid);
-            let _ = app.emit("log-error", &err_msg);
-            Err(err_msg)
+            Err(format!("Failed to kill process with PID {}", pid))
         }
     } else {
-        let err_msg = format!("Process with PID {} not found", pid);
-        let _ = app.emit("log-error", &err_msg);
-        Err(err_msg)
+        Err(format!("Process with PID {} not found", pid))
     }
 }
 
+/// Name des Prozesses mit dieser PID, falls er in `PROTECTED_PROCESS_NAMES` steht.
+fn protected_process_name(pid: u32) -> Option<String> {
+    let pid = Pid::from_u32(pid);
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing(),
+    );
+    let name = sys.process(pid)?.name().to_string_lossy().into_owned();
+    is_process_protected(&name).then_some(name)
+}
+
+/// Gibt die geschützten Prozessnamen für das Frontend zurück.
+#[tauri::command]
+pub fn get_protected_processes() -> Vec<&'static str> {
+    PROTECTED_PROCESS_NAMES.to_vec()
+}
+
 /// Gibt die Anzahl der logischen CPU-Kerne zurück.
 #[tauri::command]
 pub fn get_cpu_count() -> usize {
@@ -148,23 +401,18 @@ pub fn get_cpu_count() -> usize {
 /// # Arguments
 /// * `pid` - Die Prozess-ID.
 /// * `cores` - Eine Liste der CPU-Kerne, die verwendet werden sollen.
+///
+/// # Returns
+/// * `Verification::Mismatch` mit den tatsächlich erlaubten Kernen, falls Windows die Maske nicht übernommen hat.
 #[tauri::command]
-pub fn set_process_affinity(app: AppHandle, pid: u32, cores: Vec<u32>) -> Result<(), String> {
-    if cores.is_empty() {
-        let err_msg = format!("No cores specified for PID {}", pid);
-        let _ = app.emit("log-error", &err_msg);
-        return Err(err_msg);
-    }
-
-    let mut mask_val: u64 = 0;
-    for core in &cores {
-        if *core >= 64 {
-            let err_msg = format!("Core index {} is too high (max 63) for PID {}", core, pid);
-            let _ = app.emit("log-error", &err_msg);
-            return Err(err_msg);
-        }
-        mask_val |= 1 << core;
-    }
+pub fn set_process_affinity(
+    app: AppHandle,
+    pid: u32,
+    cores: Vec<u32>,
+) -> Result<Verification<Vec<u32>>, String> {
+    let mask_val = affinity_mask(pid, &cores).inspect_err(|err_msg| {
+        let _ = app.emit("log-error", err_msg);
+    })?;
 
     let _ = app.emit(
         "log-info",
@@ -174,6 +422,61 @@ pub fn set_process_affinity(app: AppHandle, pid: u32, cores: Vec<u32>) -> Result
         ),
     );
 
+    let verification = apply_verified_affinity(pid, mask_val).inspect_err(|err_msg| {
+        let _ = app.emit("log-error", err_msg);
+    })?;
+    if let Verification::Mismatch { actual, .. } = &verification {
+        let _ = app.emit(
+            "log-warn",
+            format!("PID {} is running on cores {:?} instead", pid, actual),
+        );
+    }
+    Ok(verification)
+}
+
+/// Setzt die CPU-Affinität, ohne an das Frontend zu loggen.
+///
+/// Wird vom Regel-Modul genutzt, das Treffer und Fehler selbst protokolliert.
+pub fn apply_affinity(pid: u32, cores: &[u32]) -> Result<Verification<Vec<u32>>, String> {
+    apply_verified_affinity(pid, affinity_mask(pid, cores)?)
+}
+
+/// Setzt die Maske und liest sie zurück.
+fn apply_verified_affinity(pid: u32, mask_val: u64) -> Result<Verification<Vec<u32>>, String> {
+    apply_affinity_mask(pid, mask_val)?;
+    Ok(Verification::compare(
+        mask_cores(mask_val),
+        mask_cores(read_affinity(pid)?),
+    ))
+}
+
+/// Gegenstück zu `affinity_mask`: die Kerne, deren Bit gesetzt ist.
+///
+/// Das Frontend bekommt Kernlisten statt Masken, da JavaScript-Zahlen keine 64 Bit fassen.
+pub fn mask_cores(mask: u64) -> Vec<u32> {
+    (0..64).filter(|core| mask & (1 << core) != 0).collect()
+}
+
+/// Baut aus einer Kernliste die Bitmaske für `SetProcessAffinityMask`.
+pub fn affinity_mask(pid: u32, cores: &[u32]) -> Result<u64, String> {
+    if cores.is_empty() {
+        return Err(format!("No cores specified for PID {}", pid));
+    }
+
+    let mut mask_val: u64 = 0;
+    for core in cores {
+        if *core >= 64 {
+            return Err(format!(
+                "Core index {} is too high (max 63) for PID {}",
+                core, pid
+            ));
+        }
+        mask_val |= 1 << core;
+    }
+    Ok(mask_val)
+}
+
+fn apply_affinity_mask(pid: u32, mask_val: u64) -> Result<(), String> {
     #[cfg(target_os = "windows")]
     {
         // * HINWEIS: Unsafe Block
@@ -182,9 +485,10 @@ pub fn set_process_affinity(app: AppHandle, pid: u32, cores: Vec<u32>) -> Result
             // 1. Prozess-Handle öffnen mit Berechtigung zum Setzen von Informationen.
             let handle = OpenProcess(PROCESS_SET_INFORMATION, FALSE, pid);
             if handle.is_null() {
-                let err_msg = format!("Failed to open process with PID {} to set affinity", pid);
-                let _ = app.emit("log-error", &err_msg);
-                return Err(err_msg);
+                return Err(format!(
+                    "Failed to open process with PID {} to set affinity",
+                    pid
+                ));
             }
 
             // * FIX: SetProcessAffinityMask expects a specific integer type (DWORD_PTR).
@@ -196,9 +500,7 @@ pub fn set_process_affinity(app: AppHandle, pid: u32, cores: Vec<u32>) -> Result
             CloseHandle(handle);
 
             if result == 0 {
-                let err_msg = format!("Failed to set affinity for PID {}", pid);
-                let _ = app.emit("log-error", &err_msg);
-                return Err(err_msg);
+                return Err(format!("Failed to set affinity for PID {}", pid));
             }
             Ok(())
         }
@@ -206,8 +508,298 @@ pub fn set_process_affinity(app: AppHandle, pid: u32, cores: Vec<u32>) -> Result
     // Fallback für Nicht-Windows-Systeme
     #[cfg(not(target_os = "windows"))]
     {
-        let err_msg = "Setting process affinity is not supported on this OS".to_string();
-        let _ = app.emit("log-error", &err_msg);
-        Err(err_msg)
+        let _ = (pid, mask_val);
+        Err("Setting process affinity is not supported on this OS".to_string())
+    }
+}
+
+/// Liest die aktuelle Affinitätsmaske eines Prozesses.
+///
+/// Gegenstück zu `affinity_mask`, um zu prüfen, ob eine gesetzte Affinität noch gilt.
+pub fn read_affinity(pid: u32) -> Result<u64, String> {
+    #[cfg(target_os = "windows")]
+    {
+        unsafe {
+            let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid);
+            if handle.is_null() {
+                return Err(format!(
+                    "Failed to open process with PID {} to read affinity",
+                    pid
+                ));
+            }
+
+            let mut process_mask = 0;
+            let mut system_mask = 0;
+            let result = GetProcessAffinityMask(handle, &mut process_mask, &mut system_mask);
+            CloseHandle(handle);
+
+            if result == 0 {
+                return Err(format!("Failed to read affinity for PID {}", pid));
+            }
+            Ok(process_mask as u64)
+        }
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = pid;
+        Err("Reading process affinity is not supported on this OS".to_string())
+    }
+}
+
+/// Setzt die Prioritätsklasse eines Prozesses.
+///
+/// # Arguments
+/// * `pid` - Die Prozess-ID.
+/// * `priority` - Die neue Prioritätsklasse.
+#[tauri::command]
+pub fn set_process_priority(
+    app: AppHandle,
+    pid: u32,
+    priority: ProcessPriority,
+) -> Result<(), String> {
+    apply_priority(pid, priority).inspect_err(|err_msg| {
+        let _ = app.emit("log-error", err_msg);
+    })
+}
+
+/// Setzt die Prioritätsklasse, ohne Fehler an das Frontend zu melden.
+pub fn apply_priority(pid: u32, priority: ProcessPriority) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        unsafe {
+            let handle = OpenProcess(PROCESS_SET_INFORMATION, FALSE, pid);
+            if handle.is_null() {
+                return Err(format!(
+                    "Failed to open process with PID {} to set priority",
+                    pid
+                ));
+            }
+
+            let result = SetPriorityClass(handle, priority.as_priority_class());
+            CloseHandle(handle);
+
+            if result == 0 {
+                return Err(format!(
+                    "Failed to set priority {:?} for PID {}",
+                    priority, pid
+                ));
+            }
+            Ok(())
+        }
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = (pid, priority);
+        Err("Setting process priority is not supported on this OS".to_string())
+    }
+}
+
+/// Setzt die I/O-Priorität eines Prozesses.
+///
+/// Nutzt die undokumentierte, aber seit Vista stabile Klasse `ProcessIoPriority`
+/// (so machen es auch Process Explorer und Process Lasso).
+pub fn apply_io_priority(pid: u32, priority: IoPriority) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        unsafe {
+            let handle = OpenProcess(PROCESS_SET_INFORMATION, FALSE, pid);
+            if handle.is_null() {
+                return Err(format!(
+                    "Failed to open process with PID {} to set I/O priority",
+                    pid
+                ));
+            }
+
+            let mut hint = priority.as_io_priority_hint();
+            let status = NtSetInformationProcess(
+                handle,
+                PROCESS_IO_PRIORITY_CLASS,
+                &mut hint as *mut u32 as *mut _,
+                std::mem::size_of::<u32>() as u32,
+            );
+            CloseHandle(handle);
+
+            if status < 0 {
+                return Err(format!(
+                    "Failed to set I/O priority {:?} for PID {} (NTSTATUS {:#x})",
+                    priority, pid, status
+                ));
+            }
+            Ok(())
+        }
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = (pid, priority);
+        Err("Setting I/O priority is not supported on this OS".to_string())
+    }
+}
+
+/// Schaltet den Effizienzmodus (EcoQoS) eines Prozesses ein oder aus.
+///
+/// Entspricht dem "Effizienzmodus" im Task-Manager: Windows plant den Prozess bevorzugt auf
+/// sparsamen Kernen und mit niedriger Taktfrequenz ein. Die Prioritätsklasse wird dabei
+/// nicht verändert, dafür gibt es `apply_priority`.
+pub fn apply_efficiency_mode(pid: u32, enabled: bool) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        unsafe {
+            let handle = OpenProcess(PROCESS_SET_INFORMATION, FALSE, pid);
+            if handle.is_null() {
+                return Err(format!(
+                    "Failed to open process with PID {} to set efficiency mode",
+                    pid
+                ));
+            }
+
+            // `control_mask` sagt Windows, dass wir die Drosselung selbst steuern,
+            // `state_mask` ob sie an (Bit gesetzt) oder aus (Bit gelöscht) ist.
+            let mut state = ProcessPowerThrottlingState {
+                version: PROCESS_POWER_THROTTLING_CURRENT_VERSION,
+                control_mask: PROCESS_POWER_THROTTLING_EXECUTION_SPEED,
+                state_mask: if enabled {
+                    PROCESS_POWER_THROTTLING_EXECUTION_SPEED
+                } else {
+                    0
+                },
+            };
+            let result = SetProcessInformation(
+                handle,
+                PROCESS_POWER_THROTTLING_CLASS,
+                &mut state as *mut ProcessPowerThrottlingState as *mut _,
+                std::mem::size_of::<ProcessPowerThrottlingState>() as u32,
+            );
+            CloseHandle(handle);
+
+            if result == 0 {
+                return Err(format!(
+                    "Failed to {} efficiency mode for PID {}",
+                    if enabled { "enable" } else { "disable" },
+                    pid
+                ));
+            }
+            Ok(())
+        }
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = (pid, enabled);
+        Err("Efficiency mode is not supported on this OS".to_string())
+    }
+}
+
+/// Liest die aktuelle Prioritätsklasse eines Prozesses.
+///
+/// Wird gebraucht, um eine automatisch gesenkte Priorität später wiederherzustellen.
+pub fn read_priority(pid: u32) -> Result<ProcessPriority, String> {
+    #[cfg(target_os = "windows")]
+    {
+        unsafe {
+            let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid);
+            if handle.is_null() {
+                return Err(format!(
+                    "Failed to open process with PID {} to read priority",
+                    pid
+                ));
+            }
+
+            let class = GetPriorityClass(handle);
+            CloseHandle(handle);
+
+            // 0 = Fehler; `REALTIME_PRIORITY_CLASS` wird bewusst nicht abgebildet.
+            ProcessPriority::from_priority_class(class)
+                .ok_or_else(|| format!("Unsupported priority class {:#x} for PID {}", class, pid))
+        }
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = pid;
+        Err("Reading process priority is not supported on this OS".to_string())
+    }
+}
+
+/// Hält alle Threads eines Prozesses an.
+///
+/// Der Prozess bleibt im Speicher, bekommt aber keine CPU-Zeit mehr,
+/// bis er mit `resume_process` fortgesetzt wird.
+#[tauri::command]
+pub fn suspend_process(app: AppHandle, pid: u32) -> Result<(), String> {
+    set_suspended(pid, true).inspect_err(|err_msg| {
+        let _ = app.emit("log-error", err_msg);
+    })
+}
+
+/// Setzt einen mit `suspend_process` angehaltenen Prozess fort.
+#[tauri::command]
+pub fn resume_process(app: AppHandle, pid: u32) -> Result<(), String> {
+    set_suspended(pid, false).inspect_err(|err_msg| {
+        let _ = app.emit("log-error", err_msg);
+    })
+}
+
+/// Hält einen Prozess an (`suspend = true`) oder setzt ihn fort (`suspend = false`).
+pub fn set_suspended(pid: u32, suspend: bool) -> Result<(), String> {
+    let action = if suspend { "suspend" } else { "resume" };
+
+    // * SECURITY: Gleiche Schutzregeln wie beim Beenden. Ein angehaltener System-Prozess
+    // oder die eigene App würden das System bzw. die UI einfrieren.
+    if pid == 0 || pid == 4 || pid == std::process::id() {
+        return Err(format!(
+            "Refusing to {} protected process with PID {}",
+            action, pid
+        ));
+    }
+    if let Some(name) = protected_process_name(pid) {
+        return Err(format!("Refusing to {} protected process {}", action, name));
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        unsafe {
+            let handle = OpenProcess(PROCESS_SUSPEND_RESUME, FALSE, pid);
+            if handle.is_null() {
+                return Err(format!(
+                    "Permission denied: cannot open process with PID {} to {}",
+                    pid, action
+                ));
+            }
+
+            let status = if suspend {
+                NtSuspendProcess(handle)
+            } else {
+                NtResumeProcess(handle)
+            };
+            CloseHandle(handle);
+
+            // NTSTATUS: Werte < 0 sind Fehler.
+            if status < 0 {
+                return Err(format!(
+                    "Failed to {} process with PID {} (NTSTATUS {:#x})",
+                    action, pid, status
+                ));
+            }
+            Ok(())
+        }
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let signal = if suspend {
+            sysinfo::Signal::Stop
+        } else {
+            sysinfo::Signal::Continue
+        };
+
+        let mut sys = System::new();
+        let sys_pid = Pid::from_u32(pid);
+        sys.refresh_processes(ProcessesToUpdate::Some(&[sys_pid]), true);
+
+        match sys.process(sys_pid).and_then(|p| p.kill_with(signal)) {
+            Some(true) => Ok(()),
+            Some(false) => Err(format!("Failed to {} process with PID {}", action, pid)),
+            None => Err(format!(
+                "Process with PID {} not found or signal not supported",
+                pid
+            )),
+        }
     }
 }
@@ -0,0 +1,354 @@
+use crate::games::{GameState, GameTarget, OptimizationProfile};
+use crate::session::{SessionEngine, SessionMessage};
+use crate::settings::{self, FrontendSettings};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+const PROFILES_KEY: &str = "optimizationProfiles";
+const ACTIVE_PROFILE_KEY: &str = "activeProfile";
+
+/// Command line switch, e.g. `--profile Streaming` or `--profile=streaming`.
+const PROFILE_ARG: &str = "--profile";
+
+/// Value of `--profile` that clears the default profile. No profile may use it as its
+/// ID or name, or it could not be selected from the command line.
+const NO_PROFILE_ARG: &str = "none";
+
+/// A profile the user can switch to ("Competitive", "Streaming", ...).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NamedProfile {
+    pub id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub profile: OptimizationProfile,
+}
+
+/// Result of `get_profiles`.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileList {
+    pub profiles: Vec<NamedProfile>,
+    /// Profile for games without their own, `None` to use the settings pages for them.
+    pub active_profile_id: Option<String>,
+}
+
+pub struct ProfileState {
+    profiles: Mutex<Vec<NamedProfile>>,
+    active: Mutex<Option<String>>,
+}
+
+impl ProfileState {
+    pub fn load(app: &AppHandle) -> Self {
+        let profiles: Vec<NamedProfile> = settings::load(app, PROFILES_KEY).unwrap_or_default();
+        // A stored active profile that no longer exists means none.
+        let active = settings::load::<_, String>(app, ACTIVE_PROFILE_KEY)
+            .filter(|id| profiles.iter().any(|p| &p.id == id));
+        Self {
+            profiles: Mutex::new(profiles),
+            active: Mutex::new(active),
+        }
+    }
+
+    pub fn list(&self) -> Vec<NamedProfile> {
+        self.profiles.lock().map(|p| p.clone()).unwrap_or_default()
+    }
+
+    pub fn get(&self, id: &str) -> Option<NamedProfile> {
+        self.profiles
+            .lock()
+            .ok()?
+            .iter()
+            .find(|profile| profile.id == id)
+            .cloned()
+    }
+
+    pub fn active_id(&self) -> Option<String> {
+        self.active.lock().ok()?.clone()
+    }
+
+    fn find(&self, key: &str) -> Option<NamedProfile> {
+        find_profile(&self.profiles.lock().ok()?, key).cloned()
+    }
+}
+
+/// Finds a profile by ID, or by name ignoring case.
+fn find_profile<'a>(profiles: &'a [NamedProfile], key: &str) -> Option<&'a NamedProfile> {
+    profiles
+        .iter()
+        .find(|profile| profile.id == key)
+        .or_else(|| {
+            profiles
+                .iter()
+                .find(|profile| profile.name.eq_ignore_ascii_case(key))
+        })
+}
+
+/// The profile to apply for `target` and the name of the named profile it came from.
+///
+/// The game's own profile wins: its named profile, then its inline profile. Only games
+/// without one use the active profile, and without that the settings pages.
+pub fn resolve_profile(
+    app: &AppHandle,
+    target: &GameTarget,
+) -> (OptimizationProfile, Option<String>) {
+    let state = app.state::<ProfileState>();
+    if let Some(named) = target.profile_id.as_deref().and_then(|id| state.get(id)) {
+        return (named.profile, Some(named.name));
+    }
+    if target.profile.is_none() {
+        if let Some(named) = state.active_id().and_then(|id| state.get(&id)) {
+            return (named.profile, Some(named.name));
+        }
+    }
+    (target.resolve_profile(&FrontendSettings::load(app)), None)
+}
+
+/// Checks a profile list before it is stored.
+fn validate_profiles(profiles: &[NamedProfile]) -> Result<(), String> {
+    let mut ids = HashSet::new();
+    let mut names = HashSet::new();
+    for named in profiles {
+        if named.name.trim().is_empty() {
+            return Err(format!("Profile '{}' has no name", named.id));
+        }
+        if named.name.trim().eq_ignore_ascii_case(NO_PROFILE_ARG) {
+            return Err(format!("'{}' is reserved for no profile", named.name));
+        }
+        if named.id.trim().is_empty() || named.id.eq_ignore_ascii_case(NO_PROFILE_ARG) {
+            return Err(format!("Profile '{}' has an invalid ID", named.name));
+        }
+        if !ids.insert(named.id.as_str()) {
+            return Err(format!("Duplicate profile ID '{}'", named.id));
+        }
+        if !names.insert(named.name.to_lowercase()) {
+            return Err(format!("Duplicate profile name '{}'", named.name));
+        }
+        named
+            .profile
+            .validate()
+            .map_err(|e| format!("Profile '{}': {}", named.name, e))?;
+    }
+    Ok(())
+}
+
+/// Makes `id` the profile for games without their own, or clears it with `None`.
+/// Running games switch right away.
+pub fn activate(app: &AppHandle, id: Option<String>) -> Result<(), String> {
+    let state = app.state::<ProfileState>();
+    let name = match &id {
+        Some(id) => Some(
+            state
+                .get(id)
+                .ok_or_else(|| format!("Unknown profile '{}'", id))?
+                .name,
+        ),
+        None => None,
+    };
+    settings::save(app, ACTIVE_PROFILE_KEY, &id)?;
+    *state.active.lock().map_err(|e| e.to_string())? = id;
+    let _ = app.emit(
+        "log-info",
+        match name {
+            Some(name) => format!("Switched to profile {}", name),
+            None => "Switched to no profile, games without their own use the settings".to_string(),
+        },
+    );
+    if let Some(session) = app.try_state::<SessionEngine>() {
+        session.send(SessionMessage::ProfilesChanged);
+    }
+    Ok(())
+}
+
+/// Switches to the next profile: none, then each named profile in order.
+pub fn cycle(app: &AppHandle) {
+    let state = app.state::<ProfileState>();
+    let ids: Vec<Option<String>> = std::iter::once(None)
+        .chain(state.list().into_iter().map(|p| Some(p.id)))
+        .collect();
+    if ids.len() < 2 {
+        let _ = app.emit("log-info", "There is no other profile to switch to");
+        return;
+    }
+    let active = state.active_id();
+    let current = ids.iter().position(|id| *id == active).unwrap_or(0);
+    let next = ids[(current + 1) % ids.len()].clone();
+    if let Err(e) = activate(app, next) {
+        let _ = app.emit("log-error", format!("Failed to switch profile: {}", e));
+    }
+}
+
+/// Values of every `--profile` switch in `args`, `None` where the value is missing.
+fn profile_args(args: &[String]) -> Vec<Option<String>> {
+    let mut values = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.strip_prefix(PROFILE_ARG) {
+            Some("") => values.push(args.next().cloned()),
+            Some(rest) => values.push(rest.strip_prefix('=').map(str::to_string)),
+            None => {}
+        }
+    }
+    values
+}
+
+/// Handles `--profile <name or ID>` on the command line, also from a second instance.
+pub fn handle_args(app: &AppHandle, args: &[String]) {
+    for value in profile_args(args) {
+        let Some(value) = value else {
+            let _ = app.emit("log-error", "--profile needs a profile name");
+            continue;
+        };
+        let result = if value.eq_ignore_ascii_case(NO_PROFILE_ARG) {
+            activate(app, None)
+        } else {
+            match app.state::<ProfileState>().find(&value) {
+                Some(named) => activate(app, Some(named.id)),
+                None => Err(format!("Unknown profile '{}'", value)),
+            }
+        };
+        if let Err(e) = result {
+            let _ = app.emit("log-error", format!("--profile: {}", e));
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_profiles(state: tauri::State<ProfileState>) -> ProfileList {
+    ProfileList {
+        profiles: state.list(),
+        active_profile_id: state.active_id(),
+    }
+}
+
+/// Stores the profiles. Profiles a game uses cannot be removed; removing the active one
+/// switches to none.
+#[tauri::command]
+pub fn set_profiles(
+    app: AppHandle,
+    state: tauri::State<ProfileState>,
+    games: tauri::State<GameState>,
+    profiles: Vec<NamedProfile>,
+) -> Result<(), String> {
+    validate_profiles(&profiles)
+        .and_then(|()| {
+            let ids: HashSet<&str> = profiles.iter().map(|p| p.id.as_str()).collect();
+            match games.targets().into_iter().find(|target| {
+                target
+                    .profile_id
+                    .as_deref()
+                    .is_some_and(|id| !ids.contains(id))
+            }) {
+                Some(target) => Err(format!(
+                    "{} still uses profile '{}'",
+                    target.name,
+                    target.profile_id.unwrap_or_default()
+                )),
+                None => Ok(()),
+            }
+        })
+        .inspect_err(|err_msg| {
+            let _ = app.emit("log-error", err_msg);
+        })?;
+    settings::save(&app, PROFILES_KEY, &profiles).inspect_err(|err_msg| {
+        let _ = app.emit("log-error", err_msg);
+    })?;
+
+    let count = profiles.len();
+    let active_removed = state
+        .active_id()
+        .is_some_and(|id| !profiles.iter().any(|p| p.id == id));
+    *state.profiles.lock().map_err(|e| e.to_string())? = profiles;
+    let _ = app.emit("log-info", format!("Saved {} profiles", count));
+    if active_removed {
+        activate(&app, None)?;
+    } else if let Some(session) = app.try_state::<SessionEngine>() {
+        // Running games pick up changes to their profile.
+        session.send(SessionMessage::ProfilesChanged);
+    }
+    Ok(())
+}
+
+/// Makes `profile_id` the profile for games without their own; `None` clears it.
+#[tauri::command]
+pub fn set_active_profile(app: AppHandle, profile_id: Option<String>) -> Result<(), String> {
+    activate(&app, profile_id).inspect_err(|err_msg| {
+        let _ = app.emit("log-error", err_msg);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(id: &str, name: &str) -> NamedProfile {
+        NamedProfile {
+            id: id.to_string(),
+            name: name.to_string(),
+            profile: OptimizationProfile::default(),
+        }
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn accepts_valid_profiles() {
+        let profiles = [named("a", "Competitive"), named("b", "Streaming")];
+        assert!(validate_profiles(&profiles).is_ok());
+        assert!(validate_profiles(&[]).is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_profiles() {
+        assert!(validate_profiles(&[named("a", " ")]).is_err());
+        assert!(validate_profiles(&[named("", "Competitive")]).is_err());
+        assert!(validate_profiles(&[named("a", "X"), named("a", "Y")]).is_err());
+        assert!(validate_profiles(&[named("a", "Streaming"), named("b", "streaming")]).is_err());
+
+        let mut bad_vibrance = named("a", "Competitive");
+        bad_vibrance.profile.vibrance = Some(101);
+        assert!(validate_profiles(&[bad_vibrance]).is_err());
+    }
+
+    #[test]
+    fn reserves_none_for_no_profile() {
+        assert!(validate_profiles(&[named("none", "Competitive")]).is_err());
+        assert!(validate_profiles(&[named("NONE", "Competitive")]).is_err());
+        assert!(validate_profiles(&[named("a", "None")]).is_err());
+        assert!(validate_profiles(&[named("a", "none")]).is_err());
+        assert!(validate_profiles(&[named("a", "None at all")]).is_ok());
+    }
+
+    #[test]
+    fn reads_profile_switches() {
+        assert_eq!(
+            profile_args(&args(&["app.exe", "--profile", "Streaming"])),
+            vec![Some("Streaming".to_string())]
+        );
+        assert_eq!(
+            profile_args(&args(&["app.exe", "--profile=streaming"])),
+            vec![Some("streaming".to_string())]
+        );
+        assert_eq!(profile_args(&args(&["app.exe", "--profile"])), vec![None]);
+        assert_eq!(profile_args(&args(&["app.exe", "--profiles"])), vec![None]);
+        assert!(profile_args(&args(&["app.exe", "--minimized"])).is_empty());
+    }
+
+    #[test]
+    fn finds_profiles_by_id_or_name() {
+        let profiles = [named("a", "Competitive"), named("b", "Streaming")];
+        assert_eq!(
+            find_profile(&profiles, "b").map(|p| p.id.as_str()),
+            Some("b")
+        );
+        assert_eq!(
+            find_profile(&profiles, "STREAMING").map(|p| p.id.as_str()),
+            Some("b")
+        );
+        assert!(find_profile(&profiles, "Casual").is_none());
+    }
+}
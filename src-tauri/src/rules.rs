@@ -0,0 +1,550 @@
+use crate::process_stream::sleep_unless_stopped;
+use crate::process_watcher::{self, NameScanSource, ProcessStartSource};
+use crate::processes::{self, is_process_protected, IoPriority, ProcessPriority};
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use sysinfo::{Pid, Process, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+use tauri::{AppHandle, Emitter, Manager};
+
+const RULES_KEY: &str = "processRules";
+const ENABLED_KEY: &str = "processRulesEnabled";
+
+/// How long the engine waits for new processes before it looks at the rules again.
+const START_WAIT: Duration = Duration::from_secs(2);
+
+/// How often a disabled engine checks whether it was switched on.
+const DISABLED_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Number of rule hits kept in memory for the log view.
+const MAX_HITS: usize = 200;
+
+/// Decides which processes a rule applies to.
+///
+/// Serialised as `{ "kind": "exeName", "value": "obs64.exe" }`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", content = "value", rename_all = "camelCase")]
+pub enum RuleMatcher {
+    /// Exact process name, case-insensitive.
+    ExeName(String),
+    /// Case-insensitive substring of the full executable path,
+    /// e.g. `\steamapps\common\` for every Steam game.
+    PathContains(String),
+}
+
+impl RuleMatcher {
+    fn matches(&self, process: &Process) -> bool {
+        self.matches_parts(&process.name().to_string_lossy(), process.exe())
+    }
+
+    /// `matches` on plain values, so it can be checked without a running process.
+    fn matches_parts(&self, name: &str, exe: Option<&Path>) -> bool {
+        match self {
+            RuleMatcher::ExeName(expected) => name.eq_ignore_ascii_case(expected),
+            RuleMatcher::PathContains(text) => exe.is_some_and(|exe| {
+                exe.to_string_lossy()
+                    .to_lowercase()
+                    .contains(&text.to_lowercase())
+            }),
+        }
+    }
+
+    fn value(&self) -> &str {
+        match self {
+            RuleMatcher::ExeName(value) | RuleMatcher::PathContains(value) => value,
+        }
+    }
+}
+
+/// What a rule does to a matching process. Every action is optional.
+///
+/// If `kill` is set, the process is terminated and the other actions are skipped.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleActions {
+    pub priority: Option<ProcessPriority>,
+    /// Logical cores the process may run on.
+    pub affinity: Option<Vec<u32>>,
+    pub io_priority: Option<IoPriority>,
+    pub efficiency_mode: Option<bool>,
+    #[serde(default)]
+    pub kill: bool,
+}
+
+impl RuleActions {
+    fn is_empty(&self) -> bool {
+        self.priority.is_none()
+            && self.affinity.is_none()
+            && self.io_priority.is_none()
+            && self.efficiency_mode.is_none()
+            && !self.kill
+    }
+}
+
+/// A persistent rule, e.g. "OBS always on cores 14-15" or "Discord always Above Normal".
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessRule {
+    /// Stable ID chosen by the frontend, used in the hit log.
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub matcher: RuleMatcher,
+    pub actions: RuleActions,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// One application of a rule to a process.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleHit {
+    /// Unix time in milliseconds.
+    pub timestamp: u64,
+    pub rule_id: String,
+    pub rule_name: String,
+    pub pid: u32,
+    pub process_name: String,
+    /// Human-readable list of actions that succeeded.
+    pub applied: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleEngineStatus {
+    pub enabled: bool,
+    pub rule_count: usize,
+    pub hit_count: usize,
+}
+
+/// Shared state between the rule engine thread and the commands.
+pub struct RuleEngineState {
+    enabled: AtomicBool,
+    rules: Mutex<Vec<ProcessRule>>,
+    hits: Mutex<VecDeque<RuleHit>>,
+    /// Set when the rules change, so they are applied to already running processes too.
+    rescan: AtomicBool,
+}
+
+impl RuleEngineState {
+    /// Loads the persisted rules.
+    pub fn load(app: &AppHandle) -> Self {
+        let rules: Vec<ProcessRule> = settings::load(app, RULES_KEY).unwrap_or_default();
+        let enabled: bool = settings::load(app, ENABLED_KEY).unwrap_or(false);
+        Self {
+            enabled: AtomicBool::new(enabled),
+            rules: Mutex::new(rules),
+            hits: Mutex::new(VecDeque::new()),
+            rescan: AtomicBool::new(false),
+        }
+    }
+}
+
+/// Starts the rule engine thread. `RuleEngineState` has to be managed already.
+///
+/// The engine runs independently of game sessions. It applies the rules to every process
+/// that is running when it starts (or when the rules change) and then to every new process.
+pub fn start_rule_engine(app: AppHandle, shutdown: Arc<AtomicBool>) {
+    thread::spawn(move || run_engine(&app, &shutdown));
+}
+
+fn run_engine(app: &AppHandle, shutdown: &AtomicBool) {
+    let state = app.state::<RuleEngineState>();
+    let refresh_kind = ProcessRefreshKind::nothing().with_exe(UpdateKind::OnlyIfNotSet);
+    // Only held while the engine is on, so no events pile up while it is off.
+    let mut source: Option<Box<dyn ProcessStartSource>> = None;
+    // PID plus start time of the processes the last full scan handled, so a start that
+    // raced with the scan is not handled twice.
+    let mut scanned: HashSet<(u32, u64)> = HashSet::new();
+
+    while !shutdown.load(Ordering::Relaxed) {
+        if !state.enabled.load(Ordering::Relaxed) {
+            source = None;
+            sleep_unless_stopped(DISABLED_POLL_INTERVAL, shutdown);
+            continue;
+        }
+
+        let rules: Vec<ProcessRule> = match state.rules.lock() {
+            Ok(rules) => rules.iter().filter(|r| r.enabled).cloned().collect(),
+            Err(_) => Vec::new(),
+        };
+
+        // Turning the engine on or changing the rules applies them to running processes.
+        let mut full_scan = state.rescan.swap(false, Ordering::Relaxed);
+        let source = match &mut source {
+            Some(source) => source,
+            None => {
+                let (new_source, notices) = process_watcher::best_start_source();
+                for notice in notices {
+                    let _ = app.emit("log-info", format!("Rule engine: {}", notice));
+                }
+                full_scan = true;
+                source.insert(primed(new_source))
+            }
+        };
+
+        if full_scan {
+            let mut sys = System::new();
+            sys.refresh_processes_specifics(ProcessesToUpdate::All, true, refresh_kind);
+            scanned = sys
+                .processes()
+                .iter()
+                .map(|(pid, process)| (pid.as_u32(), process.start_time()))
+                .collect();
+            for (pid, process) in sys.processes() {
+                apply_rules(app, &state, &rules, pid.as_u32(), process);
+            }
+        }
+
+        let starts = match source.next_starts(START_WAIT) {
+            Ok(starts) => starts,
+            Err(e) => {
+                let _ = app.emit(
+                    "log-warn",
+                    format!(
+                        "Rule engine: {} failed ({}), falling back to the process list scan",
+                        source.description(),
+                        e
+                    ),
+                );
+                *source = primed(Box::new(NameScanSource::new()));
+                continue;
+            }
+        };
+        if !starts.is_empty() {
+            let pids: Vec<Pid> = starts.iter().map(|p| Pid::from_u32(p.pid)).collect();
+            let mut sys = System::new();
+            sys.refresh_processes_specifics(ProcessesToUpdate::Some(&pids), true, refresh_kind);
+            for (pid, process) in sys.processes() {
+                if !scanned.contains(&(pid.as_u32(), process.start_time())) {
+                    apply_rules(app, &state, &rules, pid.as_u32(), process);
+                }
+            }
+        }
+        scanned.clear();
+    }
+}
+
+/// A scanning source reports every running process on its first call; the full scan
+/// already covers those.
+fn primed(mut source: Box<dyn ProcessStartSource>) -> Box<dyn ProcessStartSource> {
+    let _ = source.next_starts(Duration::ZERO);
+    source
+}
+
+fn apply_rules(
+    app: &AppHandle,
+    state: &RuleEngineState,
+    rules: &[ProcessRule],
+    pid: u32,
+    process: &Process,
+) {
+    for rule in rules.iter().filter(|rule| rule.matcher.matches(process)) {
+        if let Some(hit) = apply_rule(rule, pid, process) {
+            record_hit(app, state, hit);
+        }
+    }
+}
+
+/// Applies the actions of one rule to one process.
+///
+/// Returns `None` for processes the engine must never touch.
+fn apply_rule(rule: &ProcessRule, pid: u32, process: &Process) -> Option<RuleHit> {
+    let process_name = process.name().to_string_lossy().into_owned();
+
+    // * SECURITY: Rules are user-defined and may use broad path matchers, so protected
+    // processes and the app itself are skipped here instead of relying on validation.
+    if is_process_protected(&process_name) || pid == std::process::id() {
+        return None;
+    }
+
+    let actions = &rule.actions;
+    let mut applied = Vec::new();
+    let mut errors = Vec::new();
+    let mut record = |label: String, result: Result<(), String>| match result {
+        Ok(()) => applied.push(label),
+        Err(e) => errors.push(e),
+    };
+
+    if actions.kill {
+        record("kill".to_string(), processes::kill_process(pid));
+    } else {
+        if let Some(priority) = actions.priority {
+            record(
+                format!("priority {:?}", priority),
+                processes::apply_priority(pid, priority),
+            );
+        }
+        if let Some(cores) = &actions.affinity {
+            record(
+                format!("affinity {:?}", cores),
+                processes::apply_affinity(pid, cores)
+                    .and_then(|verification| verification.into_result("Affinity")),
+            );
+        }
+        if let Some(io_priority) = actions.io_priority {
+            record(
+                format!("I/O priority {:?}", io_priority),
+                processes::apply_io_priority(pid, io_priority),
+            );
+        }
+        if let Some(enabled) = actions.efficiency_mode {
+            record(
+                format!("efficiency mode {}", if enabled { "on" } else { "off" }),
+                processes::apply_efficiency_mode(pid, enabled),
+            );
+        }
+    }
+
+    Some(RuleHit {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+        rule_id: rule.id.clone(),
+        rule_name: rule.name.clone(),
+        pid,
+        process_name,
+        applied,
+        errors,
+    })
+}
+
+fn record_hit(app: &AppHandle, state: &RuleEngineState, hit: RuleHit) {
+    if hit.errors.is_empty() {
+        let _ = app.emit(
+            "log-info",
+            format!(
+                "Rule '{}' applied to {} (PID {}): {}",
+                hit.rule_name,
+                hit.process_name,
+                hit.pid,
+                hit.applied.join(", ")
+            ),
+        );
+    } else {
+        let _ = app.emit(
+            "log-warn",
+            format!(
+                "Rule '{}' partially failed for {} (PID {}): {}",
+                hit.rule_name,
+                hit.process_name,
+                hit.pid,
+                hit.errors.join("; ")
+            ),
+        );
+    }
+
+    let _ = app.emit("rule-hit", &hit);
+
+    if let Ok(mut hits) = state.hits.lock() {
+        if hits.len() >= MAX_HITS {
+            hits.pop_front();
+        }
+        hits.push_back(hit);
+    }
+}
+
+/// Checks a rule set before it is stored.
+fn validate_rules(rules: &[ProcessRule]) -> Result<(), String> {
+    let mut ids = HashSet::new();
+    for rule in rules {
+        if rule.id.trim().is_empty() {
+            return Err(format!("Rule '{}' has no ID", rule.name));
+        }
+        if !ids.insert(rule.id.as_str()) {
+            return Err(format!("Duplicate rule ID '{}'", rule.id));
+        }
+        if rule.matcher.value().trim().is_empty() {
+            return Err(format!("Rule '{}' has an empty matcher", rule.name));
+        }
+        if rule.actions.is_empty() {
+            return Err(format!("Rule '{}' has no actions", rule.name));
+        }
+        if let Some(cores) = &rule.actions.affinity {
+            processes::affinity_mask(0, cores)
+                .map_err(|e| format!("Rule '{}': {}", rule.name, e))?;
+        }
+        if let RuleMatcher::ExeName(name) = &rule.matcher {
+            if is_process_protected(name) {
+                return Err(format!(
+                    "Rule '{}' targets the protected process {}",
+                    rule.name, name
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns all stored rules in evaluation order.
+#[tauri::command]
+pub fn get_process_rules(state: tauri::State<RuleEngineState>) -> Result<Vec<ProcessRule>, String> {
+    Ok(state.rules.lock().map_err(|e| e.to_string())?.clone())
+}
+
+/// Replaces the rule set, persists it and applies it to running processes.
+///
+/// Rules are evaluated in list order, so a later rule wins if two rules set the same action.
+#[tauri::command]
+pub fn set_process_rules(
+    app: AppHandle,
+    state: tauri::State<RuleEngineState>,
+    rules: Vec<ProcessRule>,
+) -> Result<(), String> {
+    validate_rules(&rules).inspect_err(|err_msg| {
+        let _ = app.emit("log-error", err_msg);
+    })?;
+    settings::save(&app, RULES_KEY, &rules).inspect_err(|err_msg| {
+        let _ = app.emit("log-error", err_msg);
+    })?;
+
+    let count = rules.len();
+    *state.rules.lock().map_err(|e| e.to_string())? = rules;
+    state.rescan.store(true, Ordering::Relaxed);
+
+    let _ = app.emit("log-info", format!("Saved {} process rules", count));
+    Ok(())
+}
+
+/// Turns the rule engine on or off. The state is persisted.
+#[tauri::command]
+pub fn set_rule_engine_enabled(
+    app: AppHandle,
+    state: tauri::State<RuleEngineState>,
+    enabled: bool,
+) -> Result<(), String> {
+    settings::save(&app, ENABLED_KEY, &enabled).inspect_err(|err_msg| {
+        let _ = app.emit("log-error", err_msg);
+    })?;
+    state.enabled.store(enabled, Ordering::Relaxed);
+    let _ = app.emit(
+        "log-info",
+        format!(
+            "Rule engine {}",
+            if enabled { "enabled" } else { "disabled" }
+        ),
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_rule_engine_status(
+    state: tauri::State<RuleEngineState>,
+) -> Result<RuleEngineStatus, String> {
+    Ok(RuleEngineStatus {
+        enabled: state.enabled.load(Ordering::Relaxed),
+        rule_count: state.rules.lock().map_err(|e| e.to_string())?.len(),
+        hit_count: state.hits.lock().map_err(|e| e.to_string())?.len(),
+    })
+}
+
+/// Returns the most recent rule hits, newest first.
+#[tauri::command]
+pub fn get_rule_hits(state: tauri::State<RuleEngineState>) -> Result<Vec<RuleHit>, String> {
+    let hits = state.hits.lock().map_err(|e| e.to_string())?;
+    Ok(hits.iter().rev().cloned().collect())
+}
+
+#[tauri::command]
+pub fn clear_rule_hits(state: tauri::State<RuleEngineState>) -> Result<(), String> {
+    state.hits.lock().map_err(|e| e.to_string())?.clear();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, matcher: RuleMatcher) -> ProcessRule {
+        ProcessRule {
+            id: id.to_string(),
+            name: format!("Rule {}", id),
+            enabled: true,
+            matcher,
+            actions: RuleActions {
+                priority: Some(ProcessPriority::AboveNormal),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn exe_name(name: &str) -> RuleMatcher {
+        RuleMatcher::ExeName(name.to_string())
+    }
+
+    #[test]
+    fn accepts_valid_rules() {
+        let mut pinned = rule("b", RuleMatcher::PathContains("\\steamapps\\".to_string()));
+        pinned.actions.affinity = Some(vec![14, 15]);
+        assert!(validate_rules(&[rule("a", exe_name("obs64.exe")), pinned]).is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        let duplicate = [
+            rule("a", exe_name("obs64.exe")),
+            rule("a", exe_name("discord.exe")),
+        ];
+        assert!(validate_rules(&duplicate).is_err());
+        assert!(validate_rules(&[rule(" ", exe_name("obs64.exe"))]).is_err());
+        assert!(validate_rules(&[rule("a", exe_name("  "))]).is_err());
+        assert!(validate_rules(&[rule("a", RuleMatcher::PathContains(String::new()))]).is_err());
+        assert!(validate_rules(&[rule("a", exe_name("CSRSS.exe"))]).is_err());
+
+        let mut no_actions = rule("a", exe_name("obs64.exe"));
+        no_actions.actions = RuleActions::default();
+        assert!(validate_rules(&[no_actions]).is_err());
+
+        let mut bad_affinity = rule("a", exe_name("obs64.exe"));
+        bad_affinity.actions.affinity = Some(vec![64]);
+        assert!(validate_rules(&[bad_affinity.clone()]).is_err());
+        bad_affinity.actions.affinity = Some(Vec::new());
+        assert!(validate_rules(&[bad_affinity]).is_err());
+    }
+
+    #[test]
+    fn matchers_ignore_case() {
+        let name = exe_name("obs64.exe");
+        assert!(name.matches_parts("OBS64.EXE", None));
+        assert!(!name.matches_parts("obs32.exe", None));
+
+        let path = RuleMatcher::PathContains("\\SteamApps\\Common\\".to_string());
+        let exe = Path::new("C:\\Steam\\steamapps\\common\\Game\\game.exe");
+        assert!(path.matches_parts("game.exe", Some(exe)));
+        assert!(!path.matches_parts("game.exe", Some(Path::new("C:\\Games\\game.exe"))));
+        assert!(!path.matches_parts("game.exe", None));
+    }
+
+    #[test]
+    fn rules_use_the_kind_value_shape() {
+        let json = serde_json::json!({
+            "id": "obs",
+            "name": "OBS",
+            "matcher": { "kind": "pathContains", "value": "\\obs-studio\\" },
+            "actions": { "priority": "aboveNormal", "ioPriority": "low" }
+        });
+        let rule: ProcessRule = serde_json::from_value(json).unwrap();
+        assert!(rule.enabled);
+        assert!(!rule.actions.kill);
+        assert_eq!(
+            rule.matcher,
+            RuleMatcher::PathContains("\\obs-studio\\".to_string())
+        );
+        assert_eq!(rule.actions.io_priority, Some(IoPriority::Low));
+
+        assert_eq!(
+            serde_json::to_value(exe_name("obs64.exe")).unwrap(),
+            serde_json::json!({ "kind": "exeName", "value": "obs64.exe" })
+        );
+    }
+}
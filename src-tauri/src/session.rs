@@ -0,0 +1,2410 @@
+use crate::cpu_parking::CoreParkingState;
+use crate::game_window::WindowInfo;
+use crate::games::{GameState, GameTarget, OptimizationProfile, StepPolicy};
+use crate::processes::ProcessPriority;
+use crate::profiles::ProfileState;
+use crate::settings::FrontendSettings;
+use crate::system_events::SystemEvent;
+use crate::throttle::{ThrottleLimits, ThrottleState};
+use crate::{cpu_parking, hog_detection, power, processes, profiles, vibrance};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// The game ID that also drives the legacy `cs2process` / `cs2window` events.
+const CS2_GAME_ID: &str = "cs2";
+
+/// Parking value used when the frontend has not stored the original one.
+const DEFAULT_PARKING: u32 = 10;
+
+/// Wait after a resume before applying the settings again, so the driver and the power
+/// service are up. Further notifications in this time push it back.
+const RESUME_SETTLE: Duration = Duration::from_secs(3);
+
+/// The same after a display change; Windows sends several while monitors come up.
+const DISPLAY_SETTLE: Duration = Duration::from_secs(1);
+
+/// How often a running session checks that its settings are still in place.
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Longest snooze `snooze_automation` accepts.
+const MAX_SNOOZE_MINUTES: u32 = 24 * 60;
+
+/// What the monitor thread reports.
+#[derive(Debug, Clone)]
+pub enum SessionMessage {
+    ProcessStarted {
+        game_id: String,
+        pid: u32,
+    },
+    ProcessExited {
+        game_id: String,
+        pid: u32,
+    },
+    /// The game window that has the focus, `None` for any other window.
+    Foreground(Option<ForegroundWindow>),
+    /// The focused game window is now on another monitor.
+    DisplayChanged(ForegroundWindow),
+    /// The system resumed or the displays changed; apply the settings again.
+    Reapply(SystemEvent),
+    /// Switches a part of the profiles off or back on.
+    SetFeature {
+        feature: SessionFeature,
+        enabled: bool,
+    },
+    ToggleFeature(SessionFeature),
+    /// The active profile or a profile's settings changed.
+    ProfilesChanged,
+    /// Ends a snooze and applies the profiles of the running games from the start, also
+    /// to games that were reverted.
+    ForceApply,
+    /// Reverts the running games and leaves new ones alone, for the given time or
+    /// until the next game starts.
+    Snooze(Option<Duration>),
+    /// Ends a snooze early and optimizes the running games again.
+    Unsnooze,
+    /// Reverts the running games. Games started afterwards are optimized as usual.
+    RevertNow,
+    /// Revert everything and stop; the sender is notified once done.
+    Shutdown(Sender<()>),
+}
+
+#[derive(Debug, Clone)]
+pub struct ForegroundWindow {
+    pub game_id: String,
+    pub pid: u32,
+    pub window: Option<WindowInfo>,
+}
+
+static APP_START: OnceLock<Instant> = OnceLock::new();
+
+/// Milliseconds since the session engine started.
+///
+/// Unlike the wall clock it never jumps (NTP, DST, manual changes), so the frontend can
+/// use it to order events and to measure durations between them.
+pub fn monotonic_millis() -> u64 {
+    APP_START.get_or_init(Instant::now).elapsed().as_millis() as u64
+}
+
+/// Payload of `game-started` and `game-stopped`.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GameEvent {
+    #[serde(flatten)]
+    pub game: ActiveGame,
+    /// The first process on start, the last one on stop.
+    pub pid: u32,
+    /// `monotonic_millis()` when the event was sent.
+    pub timestamp: u64,
+}
+
+/// Payload of `game-process`, sent for every process of a game that starts or exits.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GameProcessEvent {
+    pub game_id: String,
+    pub pid: u32,
+    pub running: bool,
+    pub exe_path: Option<String>,
+    /// Unix time in seconds, as reported by the OS.
+    pub start_time: Option<u64>,
+    /// All running processes of the game after this change.
+    pub pids: Vec<u32>,
+    pub timestamp: u64,
+}
+
+/// Payload of `game-window`.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GameWindowEvent {
+    /// The game in the foreground, `None` if another window has the focus.
+    pub game_id: Option<String>,
+    /// Owner of the foreground window.
+    pub pid: Option<u32>,
+    /// Monitor, bounds and fullscreen state of the game window.
+    pub window: Option<WindowInfo>,
+    pub timestamp: u64,
+}
+
+/// Payload of `game-display` (and `cs2display` for CS2).
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GameDisplayEvent {
+    pub game_id: String,
+    pub pid: u32,
+    /// GDI device name of the new monitor.
+    pub monitor: String,
+    pub previous_monitor: Option<String>,
+    pub window: WindowInfo,
+    pub timestamp: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveGame {
+    pub game_id: String,
+    pub name: String,
+    pub pids: Vec<u32>,
+    /// Executable of the first process.
+    pub exe_path: Option<String>,
+    /// Unix time in milliseconds.
+    pub started_at: u64,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStatus {
+    /// Running games, oldest first. The last one decides the global settings.
+    pub active_games: Vec<ActiveGame>,
+    pub foreground_game: Option<String>,
+    /// Monitor of the focused game window.
+    pub foreground_display: Option<String>,
+    /// Outcome of the most recent profile application.
+    pub last_report: Option<SessionReport>,
+    /// Name of the profile that decides the global settings; the game's name if it
+    /// uses its own profile or the settings. Without a running game, the default
+    /// profile the next game will use.
+    pub active_profile: Option<String>,
+    pub snooze: Option<SnoozeStatus>,
+    pub disabled_features: Vec<SessionFeature>,
+    /// Name of the power plan the session activated.
+    pub power_plan: Option<String>,
+    /// Cores the game with the active profile is limited to.
+    pub affinity_cores: Vec<u32>,
+    /// Level of the boosted display.
+    pub vibrance: Option<u32>,
+    /// Most recent error, cleared when the next session starts.
+    pub last_error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SnoozeStatus {
+    /// Unix time in milliseconds, `None` if the snooze lasts until the next game starts.
+    pub ends_at: Option<u64>,
+}
+
+/// Parts of the profiles that can be switched off from the tray, until the app restarts.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionFeature {
+    PowerPlan,
+    KillList,
+    Affinity,
+    Vibrance,
+}
+
+impl SessionFeature {
+    pub const ALL: [SessionFeature; 4] = [
+        SessionFeature::PowerPlan,
+        SessionFeature::KillList,
+        SessionFeature::Affinity,
+        SessionFeature::Vibrance,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SessionFeature::PowerPlan => "Power plan",
+            SessionFeature::KillList => "Kill list",
+            SessionFeature::Affinity => "Affinity",
+            SessionFeature::Vibrance => "Vibrance",
+        }
+    }
+
+    /// The step that applies the feature; vibrance follows the focus instead.
+    fn step(self) -> Option<SessionStep> {
+        match self {
+            SessionFeature::PowerPlan => Some(SessionStep::PowerPlan),
+            SessionFeature::KillList => Some(SessionStep::KillProcesses),
+            SessionFeature::Affinity => Some(SessionStep::Affinity),
+            SessionFeature::Vibrance => None,
+        }
+    }
+
+    /// Copies the feature's settings from `from`, which may be the default profile.
+    fn copy(self, profile: &mut OptimizationProfile, from: &OptimizationProfile) {
+        match self {
+            SessionFeature::PowerPlan => profile.power_plan_guid = from.power_plan_guid.clone(),
+            SessionFeature::KillList => profile.kill_processes = from.kill_processes.clone(),
+            SessionFeature::Affinity => profile.affinity_cores = from.affinity_cores.clone(),
+            SessionFeature::Vibrance => profile.vibrance = from.vibrance,
+        }
+    }
+}
+
+/// The parts a profile is applied in, in this order.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionStep {
+    PowerPlan,
+    CoreParking,
+    Affinity,
+    Priority,
+    KillProcesses,
+    Throttle,
+}
+
+impl SessionStep {
+    pub const ALL: [SessionStep; 6] = [
+        SessionStep::PowerPlan,
+        SessionStep::CoreParking,
+        SessionStep::Affinity,
+        SessionStep::Priority,
+        SessionStep::KillProcesses,
+        SessionStep::Throttle,
+    ];
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum StepStatus {
+    Applied,
+    /// The profile does not use this step, or it is already in place.
+    Skipped,
+    Failed {
+        error: String,
+    },
+    /// Not attempted because a critical step failed before it.
+    NotRun,
+    RolledBack,
+    RollbackFailed {
+        error: String,
+    },
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StepResult {
+    pub step: SessionStep,
+    #[serde(flatten)]
+    pub status: StepStatus,
+}
+
+/// Payload of `game-session-progress`, sent after every attempt of a step.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StepProgress {
+    pub game_id: String,
+    pub step: SessionStep,
+    #[serde(flatten)]
+    pub status: StepStatus,
+    /// 1 for the first try.
+    pub attempt: u32,
+    /// Set if the step failed and will be tried again.
+    pub retry_in_ms: Option<u64>,
+    pub timestamp: u64,
+}
+
+/// Payload of `game-session-report`, sent once all steps of a game's profile ran.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionReport {
+    pub game_id: String,
+    pub pid: u32,
+    pub steps: Vec<StepResult>,
+    /// A critical step failed and the applied steps were undone.
+    pub rolled_back: bool,
+    pub timestamp: u64,
+}
+
+/// Applies the game profiles. Owns a worker thread, so slow calls (`powercfg`, NvAPI)
+/// never delay the monitor.
+pub struct SessionEngine {
+    sender: Sender<SessionMessage>,
+    status: Arc<Mutex<SessionStatus>>,
+}
+
+impl SessionEngine {
+    pub fn start(app: AppHandle) -> Self {
+        APP_START.get_or_init(Instant::now);
+        let (sender, receiver) = mpsc::channel();
+        let status = Arc::new(Mutex::new(SessionStatus::default()));
+        let worker = Session::new(app, status.clone());
+        thread::spawn(move || worker.run(receiver));
+        Self { sender, status }
+    }
+
+    pub fn status(&self) -> Result<SessionStatus, String> {
+        Ok(self.status.lock().map_err(|e| e.to_string())?.clone())
+    }
+
+    /// PIDs of all running games.
+    pub fn game_pids(&self) -> HashSet<u32> {
+        self.status
+            .lock()
+            .map(|status| {
+                status
+                    .active_games
+                    .iter()
+                    .flat_map(|game| game.pids.iter().copied())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn send(&self, message: SessionMessage) {
+        // Only fails if the worker is gone, i.e. while the app shuts down.
+        let _ = self.sender.send(message);
+    }
+
+    /// Restores power plan, parking, vibrance and throttled processes and stops the
+    /// worker. Waits at most `timeout`, since `powercfg` or the driver may hang while
+    /// Windows shuts down. Returns `false` if the worker did not finish in time.
+    pub fn revert(&self, timeout: Duration) -> bool {
+        let (done, finished) = mpsc::channel();
+        if self.sender.send(SessionMessage::Shutdown(done)).is_err() {
+            // Already stopped.
+            return true;
+        }
+        finished.recv_timeout(timeout).is_ok()
+    }
+}
+
+enum StepOutcome {
+    Skipped,
+    /// Empty if the step cannot be undone (killed processes) or the previous value
+    /// could not be read. Affinity and priority have one entry per process.
+    Applied(Vec<Undo>),
+}
+
+/// What restores the state from before a step.
+enum Undo {
+    /// `owned` is the plan the session had set before, if any.
+    PowerPlan {
+        guid: String,
+        owned: Option<String>,
+    },
+    CoreParking {
+        ac_value: u32,
+        dc_value: u32,
+    },
+    Affinity {
+        pid: u32,
+        mask: u64,
+    },
+    Priority {
+        pid: u32,
+        priority: ProcessPriority,
+    },
+    Throttle,
+}
+
+/// Steps of a game's profile that have not run yet.
+///
+/// Steps run one after another in the profile's order, each no earlier than its delay
+/// after the game started. A failing step blocks the ones after it until its retries
+/// are used up.
+struct PlanRun {
+    pid: u32,
+    started: Instant,
+    queue: VecDeque<StepPolicy>,
+    /// Attempts of the step at the front so far.
+    attempt: u32,
+    /// When the step at the front is tried next.
+    due: Instant,
+    steps: Vec<StepResult>,
+    /// Index into `steps` and how to undo it.
+    undo_stack: Vec<(usize, Undo)>,
+    rolled_back: bool,
+}
+
+impl PlanRun {
+    fn new(profile: &OptimizationProfile, pid: u32) -> Self {
+        let started = Instant::now();
+        let queue: VecDeque<StepPolicy> = profile.ordered_steps().into();
+        let due = started + queue.front().map_or(Duration::ZERO, step_delay);
+        Self {
+            pid,
+            started,
+            queue,
+            attempt: 0,
+            due,
+            steps: Vec::new(),
+            undo_stack: Vec::new(),
+            rolled_back: false,
+        }
+    }
+
+    /// Moves on to the next step.
+    fn advance(&mut self) {
+        self.queue.pop_front();
+        self.attempt = 0;
+        self.due = self.started + self.queue.front().map_or(Duration::ZERO, step_delay);
+    }
+
+    /// Marks the steps that did not run yet.
+    fn abandon(&mut self) {
+        for policy in self.queue.drain(..) {
+            self.steps.push(StepResult {
+                step: policy.step,
+                status: StepStatus::NotRun,
+            });
+        }
+    }
+
+    /// Runs the steps that are due at `now`. Returns `true` once no step is left.
+    fn run_due(
+        &mut self,
+        profile: &OptimizationProfile,
+        executor: &mut impl StepExecutor,
+        now: Instant,
+    ) -> bool {
+        while let Some(policy) = self.queue.front().copied() {
+            let step = policy.step;
+            // Unused steps are skipped right away instead of holding up the others.
+            if profile.uses(step) && self.due > now {
+                return false;
+            }
+            self.attempt += 1;
+            let status = match executor.apply(step, profile) {
+                Ok(StepOutcome::Skipped) => StepStatus::Skipped,
+                Ok(StepOutcome::Applied(undos)) => {
+                    for undo in undos {
+                        self.undo_stack.push((self.steps.len(), undo));
+                    }
+                    StepStatus::Applied
+                }
+                Err(error) => {
+                    if self.attempt <= policy.retries {
+                        let wait = policy.retry_delay(self.attempt);
+                        self.due = now + wait;
+                        executor.progress(
+                            step,
+                            StepStatus::Failed { error },
+                            self.attempt,
+                            Some(wait),
+                        );
+                        return false;
+                    }
+                    self.rolled_back = profile.critical_steps.contains(&step);
+                    StepStatus::Failed { error }
+                }
+            };
+            executor.progress(step, status.clone(), self.attempt, None);
+            self.steps.push(StepResult { step, status });
+            self.advance();
+            if self.rolled_back {
+                self.abandon();
+            }
+        }
+        true
+    }
+
+    /// Undoes the applied steps in reverse order.
+    fn roll_back(&mut self, executor: &mut impl StepExecutor) {
+        while let Some((step_index, undo)) = self.undo_stack.pop() {
+            let status = match executor.undo(undo) {
+                Ok(()) => StepStatus::RolledBack,
+                Err(error) => StepStatus::RollbackFailed { error },
+            };
+            // A step with several processes keeps the first failure.
+            if !matches!(
+                self.steps[step_index].status,
+                StepStatus::RollbackFailed { .. }
+            ) {
+                self.steps[step_index].status = status;
+            }
+        }
+    }
+}
+
+/// What a `PlanRun` changes on the system, and where it reports its attempts.
+trait StepExecutor {
+    fn apply(
+        &mut self,
+        step: SessionStep,
+        profile: &OptimizationProfile,
+    ) -> Result<StepOutcome, String>;
+    fn undo(&mut self, undo: Undo) -> Result<(), String>;
+    fn progress(
+        &mut self,
+        step: SessionStep,
+        status: StepStatus,
+        attempt: u32,
+        retry_in: Option<Duration>,
+    );
+}
+
+/// Runs the steps of one running game.
+struct GameSteps<'a> {
+    session: &'a mut Session,
+    index: usize,
+}
+
+impl StepExecutor for GameSteps<'_> {
+    fn apply(
+        &mut self,
+        step: SessionStep,
+        profile: &OptimizationProfile,
+    ) -> Result<StepOutcome, String> {
+        let pids = self.session.games[self.index].info.pids.clone();
+        let outcome = self.session.apply_step(step, profile, &pids);
+        match &outcome {
+            Ok(StepOutcome::Applied(_)) => self.session.games[self.index].applied.push(step),
+            Ok(StepOutcome::Skipped) => {}
+            Err(error) => self.session.log("log-error", error.clone()),
+        }
+        outcome
+    }
+
+    fn undo(&mut self, undo: Undo) -> Result<(), String> {
+        self.session.undo(undo).inspect_err(|error| {
+            self.session
+                .log("log-error", format!("Rollback failed: {}", error));
+        })
+    }
+
+    fn progress(
+        &mut self,
+        step: SessionStep,
+        status: StepStatus,
+        attempt: u32,
+        retry_in: Option<Duration>,
+    ) {
+        let game_id = &self.session.games[self.index].info.game_id;
+        self.session
+            .emit_progress(game_id, step, status, attempt, retry_in);
+    }
+}
+
+fn step_delay(policy: &StepPolicy) -> Duration {
+    Duration::from_millis(policy.delay_ms)
+}
+
+struct RunningGame {
+    info: ActiveGame,
+    profile: OptimizationProfile,
+    /// Name of the named profile, `None` for the game's own profile or the settings.
+    profile_name: Option<String>,
+    /// Steps that took effect; only these are applied to new processes and enforced.
+    applied: Vec<SessionStep>,
+    run: Option<PlanRun>,
+    /// `false` after a rollback, a snooze or "revert now"; the game then runs with the
+    /// default profile until it is restarted.
+    optimized: bool,
+}
+
+struct Snooze {
+    /// `None` until the next game starts.
+    deadline: Option<Instant>,
+    status: SnoozeStatus,
+}
+
+/// State of the worker thread.
+struct Session {
+    app: AppHandle,
+    status: Arc<Mutex<SessionStatus>>,
+    games: Vec<RunningGame>,
+    foreground: Option<String>,
+    /// Monitor of the focused game window.
+    display: Option<String>,
+    /// Used to look up path and start time of new game processes.
+    sys: System,
+    /// Power plan set by the session, reverted when the last game exits.
+    power_plan: Option<String>,
+    /// GUID and name of the plan the session activated last, for the status.
+    power_plan_name: Option<(String, String)>,
+    parking_disabled: bool,
+    /// Display whose vibrance was raised, and for which game.
+    vibrance: Option<(String, String)>,
+    /// When to apply the settings again after a resume or display change.
+    reapply_due: Option<Instant>,
+    /// Display names may have changed, so the vibrance has to follow the window again.
+    displays_changed: bool,
+    snooze: Option<Snooze>,
+    /// Switched off from the tray; removed from every profile that is applied.
+    disabled: Vec<SessionFeature>,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl Session {
+    fn new(app: AppHandle, status: Arc<Mutex<SessionStatus>>) -> Self {
+        Self {
+            app,
+            status,
+            games: Vec::new(),
+            foreground: None,
+            display: None,
+            sys: System::new(),
+            power_plan: None,
+            power_plan_name: None,
+            parking_disabled: false,
+            vibrance: None,
+            reapply_due: None,
+            displays_changed: false,
+            snooze: None,
+            disabled: Vec::new(),
+        }
+    }
+
+    fn run(mut self, receiver: Receiver<SessionMessage>) {
+        let mut next_check = Instant::now() + WATCHDOG_INTERVAL;
+        loop {
+            let deadline = if self.games.is_empty() {
+                self.next_due()
+            } else {
+                Some(
+                    self.next_due()
+                        .map_or(next_check, |due| due.min(next_check)),
+                )
+            };
+            let message = match deadline {
+                // Without a game or snooze there is nothing to enforce, so just wait for
+                // the monitor.
+                None => match receiver.recv() {
+                    Ok(message) => Some(message),
+                    Err(_) => return,
+                },
+                Some(deadline) => {
+                    match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                    {
+                        Ok(message) => Some(message),
+                        Err(RecvTimeoutError::Timeout) => None,
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+            };
+            match message {
+                Some(SessionMessage::Shutdown(done)) => {
+                    self.revert_all();
+                    let _ = done.send(());
+                    return;
+                }
+                Some(message) => self.handle(message),
+                // Counts the minutes of a timed snooze down.
+                None if self.snooze.as_ref().is_some_and(|s| s.deadline.is_some()) => {
+                    self.publish_status()
+                }
+                None => {}
+            }
+            let snooze_over = self
+                .snooze
+                .as_ref()
+                .and_then(|snooze| snooze.deadline)
+                .is_some_and(|deadline| deadline <= Instant::now());
+            if snooze_over {
+                self.unsnooze();
+                self.publish_status();
+            }
+            self.run_due_steps();
+            if self.reapply_due.is_some_and(|due| due <= Instant::now()) {
+                self.reapply_due = None;
+                self.reapply();
+            }
+            if self.games.is_empty() {
+                next_check = Instant::now() + WATCHDOG_INTERVAL;
+            } else if Instant::now() >= next_check {
+                self.enforce();
+                next_check = Instant::now() + WATCHDOG_INTERVAL;
+            }
+        }
+    }
+
+    fn handle(&mut self, message: SessionMessage) {
+        match message {
+            SessionMessage::ProcessStarted { game_id, pid } => self.process_started(&game_id, pid),
+            SessionMessage::ProcessExited { game_id, pid } => self.process_exited(&game_id, pid),
+            SessionMessage::Foreground(window) => self.foreground_changed(window),
+            SessionMessage::DisplayChanged(window) => self.display_changed(window),
+            SessionMessage::Reapply(event) => self.schedule_reapply(event),
+            SessionMessage::SetFeature { feature, enabled } => self.set_feature(feature, enabled),
+            SessionMessage::ToggleFeature(feature) => {
+                let enabled = self.disabled.contains(&feature);
+                self.set_feature(feature, enabled);
+            }
+            SessionMessage::ProfilesChanged => self.profiles_changed(),
+            SessionMessage::ForceApply => {
+                if self.snooze.take().is_some() {
+                    self.log("log-info", "Automation resumed".to_string());
+                }
+                self.restart_games();
+            }
+            SessionMessage::Snooze(duration) => self.snooze(duration),
+            SessionMessage::Unsnooze => self.unsnooze(),
+            SessionMessage::RevertNow => {
+                if !self.games.is_empty() {
+                    self.log("log-info", "Reverting the game settings".to_string());
+                    self.revert_games();
+                }
+            }
+            SessionMessage::Shutdown(_) => {}
+        }
+        self.publish_status();
+    }
+
+    /// The profile of `target` without the features switched off, and the name of the
+    /// named profile it came from.
+    fn resolve_profile(&self, target: &GameTarget) -> (OptimizationProfile, Option<String>) {
+        let (mut profile, name) = profiles::resolve_profile(&self.app, target);
+        for feature in &self.disabled {
+            feature.copy(&mut profile, &OptimizationProfile::default());
+        }
+        (profile, name)
+    }
+
+    /// Applies the new profiles from the start if a running game's profile changed.
+    /// While snoozed the games stay as they are; the snooze ending picks them up.
+    fn profiles_changed(&mut self) {
+        if self.snooze.is_some() {
+            return;
+        }
+        // Reverted and rolled back games keep running without optimizations.
+        let changed = self.games.iter().filter(|g| g.optimized).any(|game| {
+            self.app
+                .state::<GameState>()
+                .target(&game.info.game_id)
+                .is_some_and(|target| {
+                    let (profile, name) = self.resolve_profile(&target);
+                    profile != game.profile || name != game.profile_name
+                })
+        });
+        if !changed {
+            return;
+        }
+        self.log(
+            "log-info",
+            "Profile changed, applying it to the running games".to_string(),
+        );
+        self.revert_games();
+        self.restart_games();
+    }
+
+    fn set_feature(&mut self, feature: SessionFeature, enabled: bool) {
+        if enabled != self.disabled.contains(&feature) {
+            return;
+        }
+        let state = if enabled { "on" } else { "off" };
+        self.log(
+            "log-info",
+            format!("{} switched {}", feature.label(), state),
+        );
+        if !enabled {
+            self.disabled.push(feature);
+            for game in &mut self.games {
+                feature.copy(&mut game.profile, &OptimizationProfile::default());
+                if let Some(step) = feature.step() {
+                    game.applied.retain(|applied| *applied != step);
+                }
+            }
+            match feature {
+                SessionFeature::PowerPlan => {
+                    self.restore_power_plan(&FrontendSettings::load(&self.app))
+                }
+                SessionFeature::Vibrance => self.restore_vibrance(),
+                // Killed processes stay gone and running ones keep their affinity; only
+                // processes started from now on are left alone.
+                SessionFeature::KillList | SessionFeature::Affinity => {}
+            }
+            return;
+        }
+
+        self.disabled.retain(|disabled| *disabled != feature);
+        let last = self.games.len().saturating_sub(1);
+        for index in 0..self.games.len() {
+            let game = &self.games[index];
+            if !game.optimized {
+                continue;
+            }
+            let Some(target) = self.app.state::<GameState>().target(&game.info.game_id) else {
+                continue;
+            };
+            let (full, _) = self.resolve_profile(&target);
+            let game = &mut self.games[index];
+            feature.copy(&mut game.profile, &full);
+            // A pending run applies the step once it gets there. The power plan belongs
+            // to the most recently started game.
+            let step = feature.step().filter(|&step| {
+                game.run.is_none() && (step != SessionStep::PowerPlan || index == last)
+            });
+            if let Some(step) = step {
+                let profile = game.profile.clone();
+                let pids = game.info.pids.clone();
+                match self.apply_step(step, &profile, &pids) {
+                    Ok(StepOutcome::Applied(_)) => self.games[index].applied.push(step),
+                    Ok(StepOutcome::Skipped) => {}
+                    Err(e) => self.log("log-error", e),
+                }
+            }
+        }
+        if feature == SessionFeature::Vibrance && self.vibrance.is_none() {
+            if let Some(game_id) = self.foreground.clone() {
+                self.boost_vibrance(&game_id);
+            }
+        }
+    }
+
+    fn snooze(&mut self, duration: Option<Duration>) {
+        self.snooze = Some(Snooze {
+            deadline: duration.map(|duration| Instant::now() + duration),
+            status: SnoozeStatus {
+                ends_at: duration.map(|duration| now_millis() + duration.as_millis() as u64),
+            },
+        });
+        let message = match duration {
+            Some(duration) => format!("Automation snoozed for {} min", duration.as_secs() / 60),
+            None => "Automation snoozed until the next game starts".to_string(),
+        };
+        self.log("log-info", message);
+        self.revert_games();
+    }
+
+    /// Ends the snooze and applies the profiles of the running games from the start.
+    fn unsnooze(&mut self) {
+        if self.snooze.take().is_none() {
+            return;
+        }
+        self.log("log-info", "Automation resumed".to_string());
+        self.restart_games();
+    }
+
+    fn restart_games(&mut self) {
+        for index in 0..self.games.len() {
+            let Some(target) = self
+                .app
+                .state::<GameState>()
+                .target(&self.games[index].info.game_id)
+            else {
+                continue;
+            };
+            let (profile, profile_name) = self.resolve_profile(&target);
+            let game = &mut self.games[index];
+            let Some(&pid) = game.info.pids.first() else {
+                continue;
+            };
+            game.run = Some(PlanRun::new(&profile, pid));
+            game.profile = profile;
+            game.profile_name = profile_name;
+            game.applied.clear();
+            game.optimized = true;
+        }
+        if let Some(game_id) = self.foreground.clone() {
+            self.boost_vibrance(&game_id);
+        }
+        self.run_due_steps();
+    }
+
+    /// Undoes the global settings and the vibrance of the running games. They keep the
+    /// default profile until they are restarted; pending steps are reported as not run.
+    fn revert_games(&mut self) {
+        self.restore_vibrance();
+        let mut runs = Vec::new();
+        for game in &mut self.games {
+            if let Some(mut run) = game.run.take() {
+                run.abandon();
+                runs.push((game.info.game_id.clone(), run));
+            }
+            game.profile = OptimizationProfile::default();
+            game.applied.clear();
+            game.optimized = false;
+        }
+        for (game_id, run) in runs {
+            self.emit_report(&game_id, run);
+        }
+        self.restore_globals();
+    }
+
+    fn schedule_reapply(&mut self, event: SystemEvent) {
+        if self.games.is_empty() {
+            return;
+        }
+        let settle = match event {
+            SystemEvent::DisplaysChanged => {
+                self.displays_changed = true;
+                DISPLAY_SETTLE
+            }
+            _ => RESUME_SETTLE,
+        };
+        let due = Instant::now() + settle;
+        // A resume that is still settling is not cut short by a display change.
+        self.reapply_due = Some(self.reapply_due.map_or(due, |pending| pending.max(due)));
+    }
+
+    /// Runs the steps that took effect once more, unconditionally: after a resume the
+    /// read-back may even succeed while the driver is about to reset the value.
+    fn reapply(&mut self) {
+        if self.games.is_empty() {
+            return;
+        }
+        self.log(
+            "log-info",
+            "Applying the game settings again after a system change".to_string(),
+        );
+
+        if let Some(guid) = self.power_plan.clone() {
+            if let Err(e) =
+                power::set_active_power_plan(guid).and_then(|v| v.into_result("Power plan"))
+            {
+                self.log("log-error", format!("Failed to set power plan: {}", e));
+            }
+        }
+        if self.parking_disabled {
+            if let Err(e) = cpu_parking::set_core_parking_status(100, 100)
+                .and_then(|v| v.into_result("Core parking"))
+            {
+                self.log(
+                    "log-error",
+                    format!("Failed to deactivate core parking: {}", e),
+                );
+            }
+        }
+
+        let games: Vec<(OptimizationProfile, Vec<SessionStep>, Vec<u32>)> = self
+            .games
+            .iter()
+            .map(|g| (g.profile.clone(), g.applied.clone(), g.info.pids.clone()))
+            .collect();
+        for (profile, applied, pids) in games {
+            for pid in pids {
+                self.apply_process_settings(&profile, &applied, pid);
+            }
+            if applied.contains(&SessionStep::KillProcesses) {
+                self.kill_processes(&profile);
+            }
+            if applied.contains(&SessionStep::Throttle) {
+                if let Err(e) = self.throttle_processes(&profile) {
+                    self.log("log-error", e);
+                }
+            }
+        }
+
+        if std::mem::take(&mut self.displays_changed) {
+            // The monitor thread reports the window's new display on its next check.
+            self.display = None;
+        }
+        if let Some((_, game_id)) = self.vibrance.take() {
+            self.boost_vibrance(&game_id);
+        }
+    }
+
+    /// Watchdog: reads back what the session set and applies it again if something else
+    /// changed it (vendor tools, Windows after sleep, a second optimizer).
+    fn enforce(&mut self) {
+        if let Some(guid) = self.power_plan.clone() {
+            match power::get_power_plans() {
+                Ok(plans) => {
+                    let active = plans.iter().find(|plan| plan.is_active);
+                    if !active.is_some_and(|plan| plan.guid.eq_ignore_ascii_case(&guid)) {
+                        let found = active.map_or("none".to_string(), |plan| {
+                            format!("{} ({})", plan.name, plan.guid)
+                        });
+                        self.log(
+                            "log-warn",
+                            format!(
+                                "Power plan was changed to {}, activating {} again",
+                                found, guid
+                            ),
+                        );
+                        if let Err(e) = power::set_active_power_plan(guid)
+                            .and_then(|v| v.into_result("Power plan"))
+                        {
+                            self.log("log-error", format!("Failed to set power plan: {}", e));
+                        }
+                    }
+                }
+                Err(e) => self.log("log-warn", format!("Failed to read power plans: {}", e)),
+            }
+        }
+
+        // After the plan, since parking is stored per plan.
+        if self.parking_disabled {
+            match cpu_parking::get_core_parking_status() {
+                Ok(state) if state.ac_value != 100 || state.dc_value != 100 => {
+                    self.log(
+                        "log-warn",
+                        format!(
+                            "Core parking was changed to AC: {}%, DC: {}%, deactivating it again",
+                            state.ac_value, state.dc_value
+                        ),
+                    );
+                    if let Err(e) = cpu_parking::set_core_parking_status(100, 100)
+                        .and_then(|v| v.into_result("Core parking"))
+                    {
+                        self.log(
+                            "log-error",
+                            format!("Failed to deactivate core parking: {}", e),
+                        );
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => self.log(
+                    "log-warn",
+                    format!("Failed to read core parking status: {}", e),
+                ),
+            }
+        }
+
+        for game in &self.games {
+            for &pid in &game.info.pids {
+                self.enforce_process_settings(game, pid);
+            }
+        }
+
+        if let Some((display, game_id)) = self.vibrance.clone() {
+            let level = self
+                .games
+                .iter()
+                .find(|g| g.info.game_id == game_id)
+                .and_then(|g| g.profile.vibrance);
+            if let Some(level) = level {
+                match vibrance::check_vibrance(&display, level) {
+                    Ok(Some(found)) => {
+                        self.log(
+                            "log-warn",
+                            format!(
+                                "Vibrance on {} was changed to {}, setting {} again",
+                                display, found, level
+                            ),
+                        );
+                        if let Err(e) = vibrance::apply_vibrance(self.app.clone(), display, level) {
+                            self.log("log-error", format!("Failed to set vibrance: {}", e));
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => self.log("log-warn", format!("Failed to read vibrance: {}", e)),
+                }
+            }
+        }
+    }
+
+    /// Affinity and priority of one game process. Read errors are ignored: the process
+    /// may have exited since the last event, or never allowed us to query it.
+    fn enforce_process_settings(&self, game: &RunningGame, pid: u32) {
+        let profile = &game.profile;
+        if game.applied.contains(&SessionStep::Affinity) && !profile.affinity_cores.is_empty() {
+            if let (Ok(expected), Ok(found)) = (
+                processes::affinity_mask(pid, &profile.affinity_cores),
+                processes::read_affinity(pid),
+            ) {
+                if expected != found {
+                    self.log(
+                        "log-warn",
+                        format!(
+                            "Affinity of PID {} was changed to {:#x}, setting cores {:?} again",
+                            pid, found, profile.affinity_cores
+                        ),
+                    );
+                    if let Err(e) = processes::apply_affinity(pid, &profile.affinity_cores)
+                        .and_then(|v| v.into_result("Affinity"))
+                    {
+                        self.log(
+                            "log-error",
+                            format!("Failed to set affinity of PID {}: {}", pid, e),
+                        );
+                    }
+                }
+            }
+        }
+        let priority = profile
+            .priority
+            .filter(|_| game.applied.contains(&SessionStep::Priority));
+        if let Some(priority) = priority {
+            match processes::read_priority(pid) {
+                Ok(found) if found != priority => {
+                    self.log(
+                        "log-warn",
+                        format!(
+                            "Priority of PID {} was changed to {:?}, setting {:?} again",
+                            pid, found, priority
+                        ),
+                    );
+                    if let Err(e) = processes::apply_priority(pid, priority) {
+                        self.log(
+                            "log-error",
+                            format!("Failed to set priority of PID {}: {}", pid, e),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Errors are also kept in the status, so the tray can show them.
+    fn log(&self, level: &str, message: String) {
+        if level == "log-error" {
+            if let Ok(mut status) = self.status.lock() {
+                status.last_error = Some(message.clone());
+            }
+            let _ = self.app.emit(level, message);
+            self.publish_status();
+            return;
+        }
+        let _ = self.app.emit(level, message);
+    }
+
+    /// Updates the status and sends it as `game-session-status`, e.g. for the tray.
+    fn publish_status(&self) {
+        let Ok(mut status) = self.status.lock() else {
+            return;
+        };
+        status.active_games = self.games.iter().map(|g| g.info.clone()).collect();
+        status.foreground_game = self.foreground.clone();
+        status.foreground_display = self.display.clone();
+        status.active_profile = if self.games.is_empty() {
+            let profiles = self.app.state::<ProfileState>();
+            profiles
+                .active_id()
+                .and_then(|id| profiles.get(&id))
+                .map(|profile| profile.name)
+        } else {
+            self.games.iter().rev().find(|g| g.optimized).map(|g| {
+                g.profile_name
+                    .clone()
+                    .unwrap_or_else(|| g.info.name.clone())
+            })
+        };
+        status.snooze = self.snooze.as_ref().map(|snooze| snooze.status.clone());
+        status.disabled_features = self.disabled.clone();
+        status.power_plan = self
+            .power_plan
+            .as_ref()
+            .map(|guid| match &self.power_plan_name {
+                Some((named, name)) if named == guid => name.clone(),
+                _ => guid.clone(),
+            });
+        status.affinity_cores = self
+            .games
+            .iter()
+            .rev()
+            .find(|g| g.optimized)
+            .filter(|g| g.applied.contains(&SessionStep::Affinity))
+            .map(|g| g.profile.affinity_cores.clone())
+            .unwrap_or_default();
+        status.vibrance = self.vibrance.as_ref().and_then(|(_, owner)| {
+            self.games
+                .iter()
+                .find(|g| &g.info.game_id == owner)
+                .and_then(|g| g.profile.vibrance)
+        });
+        let status = status.clone();
+        let _ = self.app.emit("game-session-status", status);
+    }
+
+    /// Path and start time of a process (`None` if it is already gone or inaccessible).
+    fn process_details(&mut self, pid: u32) -> (Option<String>, Option<u64>) {
+        let pid = Pid::from_u32(pid);
+        self.sys.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[pid]),
+            true,
+            ProcessRefreshKind::nothing().with_exe(UpdateKind::OnlyIfNotSet),
+        );
+        match self.sys.process(pid) {
+            Some(process) => (
+                process.exe().map(|p| p.to_string_lossy().into_owned()),
+                Some(process.start_time()),
+            ),
+            None => (None, None),
+        }
+    }
+
+    fn emit_process_event(
+        &self,
+        game_id: &str,
+        pid: u32,
+        running: bool,
+        details: (Option<String>, Option<u64>),
+    ) {
+        let pids = self
+            .games
+            .iter()
+            .find(|g| g.info.game_id == game_id)
+            .map(|g| g.info.pids.clone())
+            .unwrap_or_default();
+        let _ = self.app.emit(
+            "game-process",
+            GameProcessEvent {
+                game_id: game_id.to_string(),
+                pid,
+                running,
+                exe_path: details.0,
+                start_time: details.1,
+                pids,
+                timestamp: monotonic_millis(),
+            },
+        );
+    }
+
+    fn process_started(&mut self, game_id: &str, pid: u32) {
+        let details = self.process_details(pid);
+
+        if let Some(game) = self.games.iter_mut().find(|g| g.info.game_id == game_id) {
+            // Another process of a running game: a helper it spawned, a second instance
+            // or the client restarted by its launcher.
+            if !game.info.pids.contains(&pid) {
+                game.info.pids.push(pid);
+            }
+            let profile = game.profile.clone();
+            let applied = game.applied.clone();
+            self.emit_process_event(game_id, pid, true, details);
+            self.apply_process_settings(&profile, &applied, pid);
+            // Keeps `game_pids` current for the CPU-hog detection.
+            self.publish_status();
+            return;
+        }
+
+        let Some(target) = self.app.state::<GameState>().target(game_id) else {
+            return;
+        };
+        // A snooze until the next launch ends here; a timed one keeps new games as they are.
+        let snoozed = match &self.snooze {
+            Some(snooze) if snooze.deadline.is_none() => {
+                self.snooze = None;
+                self.log("log-info", "Automation resumed".to_string());
+                false
+            }
+            Some(_) => true,
+            None => false,
+        };
+        let (profile, profile_name) = if snoozed {
+            (OptimizationProfile::default(), None)
+        } else {
+            self.resolve_profile(&target)
+        };
+        let session_starts = self.games.is_empty();
+        let info = ActiveGame {
+            game_id: game_id.to_string(),
+            name: target.name.clone(),
+            pids: vec![pid],
+            exe_path: details.0.clone(),
+            started_at: now_millis(),
+        };
+        self.games.push(RunningGame {
+            info: info.clone(),
+            run: (!snoozed).then(|| PlanRun::new(&profile, pid)),
+            profile,
+            profile_name,
+            applied: Vec::new(),
+            optimized: !snoozed,
+        });
+
+        self.log("log-info", format!("{} started (PID {})", target.name, pid));
+        if snoozed {
+            self.log(
+                "log-info",
+                format!("Automation is snoozed, {} is not optimized", target.name),
+            );
+        }
+        self.emit_process_event(game_id, pid, true, details);
+        let _ = self.app.emit(
+            "game-started",
+            GameEvent {
+                game: info,
+                pid,
+                timestamp: monotonic_millis(),
+            },
+        );
+        if game_id == CS2_GAME_ID {
+            let _ = self.app.emit("cs2process", "started");
+        }
+
+        if session_starts {
+            if let Ok(mut status) = self.status.lock() {
+                status.last_error = None;
+            }
+            hog_detection::set_session_active(&self.app, true);
+        }
+        // Steps without a delay run right away.
+        self.run_due_steps();
+    }
+
+    fn process_exited(&mut self, game_id: &str, pid: u32) {
+        let Some(index) = self.games.iter().position(|g| g.info.game_id == game_id) else {
+            return;
+        };
+        self.games[index].info.pids.retain(|p| *p != pid);
+        // The process is gone, so there is nothing left to look up.
+        self.emit_process_event(game_id, pid, false, (None, None));
+        if !self.games[index].info.pids.is_empty() {
+            self.publish_status();
+            return;
+        }
+
+        let mut game = self.games.remove(index);
+        if let Some(mut run) = game.run.take() {
+            run.abandon();
+            self.emit_report(game_id, run);
+        }
+        self.log("log-info", format!("{} stopped", game.info.name));
+        let _ = self.app.emit(
+            "game-stopped",
+            GameEvent {
+                game: game.info,
+                pid,
+                timestamp: monotonic_millis(),
+            },
+        );
+        if game_id == CS2_GAME_ID {
+            let _ = self.app.emit("cs2process", "stopped");
+        }
+
+        if self
+            .vibrance
+            .as_ref()
+            .is_some_and(|(_, owner)| owner == game_id)
+        {
+            self.restore_vibrance();
+        }
+
+        // Overlapping games: the most recently started game that is still running
+        // takes over the global settings; the last one to exit restores the defaults.
+        match self
+            .games
+            .last()
+            .map(|g| (g.profile.clone(), g.applied.clone()))
+        {
+            Some((profile, applied)) => self.apply_globals(&profile, &applied),
+            None => self.end_session(),
+        }
+    }
+
+    fn foreground_changed(&mut self, window: Option<ForegroundWindow>) {
+        let game_id = window.as_ref().map(|w| w.game_id.clone());
+        if game_id == self.foreground {
+            return;
+        }
+        let was_cs2 = self.foreground.as_deref() == Some(CS2_GAME_ID);
+        let is_cs2 = game_id.as_deref() == Some(CS2_GAME_ID);
+        self.foreground = game_id.clone();
+        self.display = window
+            .as_ref()
+            .and_then(|w| w.window.as_ref())
+            .map(|w| w.monitor.clone());
+
+        let _ = self.app.emit(
+            "game-window",
+            GameWindowEvent {
+                game_id: game_id.clone(),
+                pid: window.as_ref().map(|w| w.pid),
+                window: window.and_then(|w| w.window),
+                timestamp: monotonic_millis(),
+            },
+        );
+        if was_cs2 != is_cs2 {
+            let status = if is_cs2 { "foreground" } else { "background" };
+            let _ = self.app.emit("cs2window", status);
+        }
+
+        if self.vibrance.is_some() {
+            self.restore_vibrance();
+        }
+        if let Some(id) = game_id {
+            self.boost_vibrance(&id);
+        }
+    }
+
+    fn display_changed(&mut self, window: ForegroundWindow) {
+        let Some(info) = window.window else {
+            return;
+        };
+        if self.foreground.as_deref() != Some(window.game_id.as_str())
+            || self.display.as_deref() == Some(info.monitor.as_str())
+        {
+            return;
+        }
+        let previous_monitor = self.display.replace(info.monitor.clone());
+        self.log(
+            "log-info",
+            format!(
+                "{} moved from {} to {}",
+                window.game_id,
+                previous_monitor.as_deref().unwrap_or("an unknown display"),
+                info.monitor
+            ),
+        );
+
+        let event = GameDisplayEvent {
+            game_id: window.game_id.clone(),
+            pid: window.pid,
+            monitor: info.monitor.clone(),
+            previous_monitor,
+            window: info,
+            timestamp: monotonic_millis(),
+        };
+        let _ = self.app.emit("game-display", &event);
+        if window.game_id == CS2_GAME_ID {
+            let _ = self.app.emit("cs2display", &event);
+        }
+
+        // Move the boost: restore the old display, then raise the new one.
+        if self.vibrance.is_some() {
+            self.restore_vibrance();
+        }
+        self.boost_vibrance(&window.game_id);
+    }
+
+    /// Raises the vibrance of the game's display if its profile asks for it.
+    ///
+    /// Uses the monitor the monitor thread reported; falls back to the display of the
+    /// focused window if it could not be determined.
+    fn boost_vibrance(&mut self, game_id: &str) {
+        let Some(level) = self
+            .games
+            .iter()
+            .find(|g| g.info.game_id == game_id)
+            .and_then(|g| g.profile.vibrance)
+        else {
+            return;
+        };
+        let result = match self.display.clone() {
+            Some(display) => {
+                vibrance::apply_vibrance(self.app.clone(), display.clone(), level).map(|_| display)
+            }
+            None => vibrance::apply_vibrance_to_focused_display(self.app.clone(), level),
+        };
+        match result {
+            Ok(display) => {
+                self.log(
+                    "log-info",
+                    format!("Vibrance set to {} on {} for {}", level, display, game_id),
+                );
+                self.vibrance = Some((display, game_id.to_string()));
+            }
+            Err(e) => self.log("log-error", format!("Failed to set vibrance: {}", e)),
+        }
+    }
+
+    fn next_due(&self) -> Option<Instant> {
+        self.games
+            .iter()
+            .filter_map(|g| g.run.as_ref().map(|run| run.due))
+            .chain(self.reapply_due)
+            .chain(self.snooze.as_ref().and_then(|snooze| snooze.deadline))
+            .chain(self.next_snooze_minute())
+            .min()
+    }
+
+    /// When the minutes left of a timed snooze drop next.
+    fn next_snooze_minute(&self) -> Option<Instant> {
+        let deadline = self.snooze.as_ref()?.deadline?;
+        let now = Instant::now();
+        let left = deadline.saturating_duration_since(now).as_millis() as u64 % 60_000;
+        Some(now + Duration::from_millis(if left == 0 { 60_000 } else { left }))
+    }
+
+    fn run_due_steps(&mut self) {
+        let mut ran = false;
+        for index in 0..self.games.len() {
+            let Some(mut run) = self.games[index].run.take() else {
+                continue;
+            };
+            ran = true;
+            if self.advance_run(index, &mut run) {
+                self.finish_run(index, run);
+            } else {
+                self.games[index].run = Some(run);
+            }
+        }
+        if ran {
+            self.publish_status();
+        }
+    }
+
+    /// Runs the steps that are due. Returns `true` once no step is left.
+    fn advance_run(&mut self, index: usize, run: &mut PlanRun) -> bool {
+        let profile = self.games[index].profile.clone();
+        run.run_due(
+            &profile,
+            &mut GameSteps {
+                session: self,
+                index,
+            },
+            Instant::now(),
+        )
+    }
+
+    /// Reports a finished run. If a critical step failed, the steps applied before it
+    /// are undone in reverse order.
+    fn finish_run(&mut self, index: usize, mut run: PlanRun) {
+        let game_id = self.games[index].info.game_id.clone();
+        if run.rolled_back {
+            self.log(
+                "log-warn",
+                format!("A critical step failed for {}, rolling back", game_id),
+            );
+            run.roll_back(&mut GameSteps {
+                session: self,
+                index,
+            });
+            // Nothing of the profile is left in place, so neither re-apply it to new
+            // processes nor let the watchdog enforce it.
+            let game = &mut self.games[index];
+            game.profile = OptimizationProfile::default();
+            game.applied.clear();
+            game.optimized = false;
+            let name = game.info.name.clone();
+            self.log("log-warn", format!("{} runs without optimizations", name));
+        }
+        self.emit_report(&game_id, run);
+    }
+
+    fn emit_progress(
+        &self,
+        game_id: &str,
+        step: SessionStep,
+        status: StepStatus,
+        attempt: u32,
+        retry_in: Option<Duration>,
+    ) {
+        let _ = self.app.emit(
+            "game-session-progress",
+            StepProgress {
+                game_id: game_id.to_string(),
+                step,
+                status,
+                attempt,
+                retry_in_ms: retry_in.map(|wait| wait.as_millis() as u64),
+                timestamp: monotonic_millis(),
+            },
+        );
+    }
+
+    fn emit_report(&self, game_id: &str, run: PlanRun) {
+        let report = SessionReport {
+            game_id: game_id.to_string(),
+            pid: run.pid,
+            steps: run.steps,
+            rolled_back: run.rolled_back,
+            timestamp: monotonic_millis(),
+        };
+        let _ = self.app.emit("game-session-report", &report);
+        if let Ok(mut status) = self.status.lock() {
+            status.last_report = Some(report);
+        }
+    }
+
+    fn apply_step(
+        &mut self,
+        step: SessionStep,
+        profile: &OptimizationProfile,
+        pids: &[u32],
+    ) -> Result<StepOutcome, String> {
+        match step {
+            SessionStep::PowerPlan => self.set_power_plan(profile),
+            SessionStep::CoreParking => self.disable_parking(profile),
+            SessionStep::Affinity => {
+                self.for_each_process(pids, |s, pid| s.set_affinity(profile, pid))
+            }
+            SessionStep::Priority => {
+                self.for_each_process(pids, |s, pid| s.set_priority(profile, pid))
+            }
+            SessionStep::KillProcesses => Ok(self.kill_processes(profile)),
+            SessionStep::Throttle => self.throttle_processes(profile),
+        }
+    }
+
+    /// Applies a per-process step to all processes of the game. Fails only if it failed
+    /// for all of them; helpers often exit before they can be changed.
+    fn for_each_process(
+        &self,
+        pids: &[u32],
+        apply: impl Fn(&Self, u32) -> Result<StepOutcome, String>,
+    ) -> Result<StepOutcome, String> {
+        let mut undos = Vec::new();
+        let mut errors = Vec::new();
+        let mut applied = false;
+        for &pid in pids {
+            match apply(self, pid) {
+                Ok(StepOutcome::Applied(undo)) => {
+                    applied = true;
+                    undos.extend(undo);
+                }
+                Ok(StepOutcome::Skipped) => {}
+                Err(e) => errors.push(e),
+            }
+        }
+        if !applied && !errors.is_empty() {
+            return Err(errors.join("; "));
+        }
+        for error in errors {
+            self.log("log-warn", error);
+        }
+        Ok(if applied {
+            StepOutcome::Applied(undos)
+        } else {
+            StepOutcome::Skipped
+        })
+    }
+
+    fn undo(&mut self, undo: Undo) -> Result<(), String> {
+        match undo {
+            Undo::PowerPlan { guid, owned } => {
+                power::set_active_power_plan(guid.clone())
+                    .and_then(|v| v.into_result("Power plan"))?;
+                self.log("log-info", format!("Rolled back power plan to {}", guid));
+                self.power_plan = owned;
+            }
+            Undo::CoreParking { ac_value, dc_value } => {
+                cpu_parking::set_core_parking_status(ac_value, dc_value)
+                    .and_then(|v| v.into_result("Core parking"))?;
+                self.log(
+                    "log-info",
+                    format!(
+                        "Rolled back core parking (AC: {}%, DC: {}%)",
+                        ac_value, dc_value
+                    ),
+                );
+                self.parking_disabled = false;
+            }
+            Undo::Affinity { pid, mask } => {
+                processes::apply_affinity(pid, &processes::mask_cores(mask))
+                    .and_then(|v| v.into_result("Affinity"))?;
+                self.log("log-info", format!("Rolled back affinity of PID {}", pid));
+            }
+            Undo::Priority { pid, priority } => {
+                processes::apply_priority(pid, priority)?;
+                self.log("log-info", format!("Rolled back priority of PID {}", pid));
+            }
+            Undo::Throttle => {
+                if let Some(throttle) = self.app.try_state::<ThrottleState>() {
+                    throttle.release_all(&self.app)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Power plan and core parking follow the most recently started game, as far as
+    /// its run has applied them.
+    fn apply_globals(&mut self, profile: &OptimizationProfile, applied: &[SessionStep]) {
+        if applied.contains(&SessionStep::PowerPlan) {
+            if let Err(e) = self.set_power_plan(profile) {
+                self.log("log-error", e);
+            }
+        }
+        if applied.contains(&SessionStep::CoreParking) {
+            if let Err(e) = self.disable_parking(profile) {
+                self.log("log-error", e);
+            }
+        }
+    }
+
+    fn set_power_plan(&mut self, profile: &OptimizationProfile) -> Result<StepOutcome, String> {
+        let Some(guid) = &profile.power_plan_guid else {
+            return Ok(StepOutcome::Skipped);
+        };
+        if self.power_plan.as_ref() == Some(guid) {
+            return Ok(StepOutcome::Skipped);
+        }
+        // Without the previous plan the step still runs, it just cannot be undone.
+        let plans = power::get_power_plans().unwrap_or_default();
+        let previous = plans.iter().find(|plan| plan.is_active).cloned();
+        self.power_plan_name = plans
+            .into_iter()
+            .find(|plan| &plan.guid == guid)
+            .map(|plan| (plan.guid, plan.name));
+        power::set_active_power_plan(guid.clone())
+            .and_then(|v| v.into_result("Power plan"))
+            .map_err(|e| format!("Failed to set power plan: {}", e))?;
+        self.log("log-info", format!("Activated power plan {}", guid));
+        let owned = self.power_plan.replace(guid.clone());
+        let undo = previous.map(|plan| Undo::PowerPlan {
+            guid: plan.guid,
+            owned,
+        });
+        Ok(StepOutcome::Applied(undo.into_iter().collect()))
+    }
+
+    fn disable_parking(&mut self, profile: &OptimizationProfile) -> Result<StepOutcome, String> {
+        if !profile.prevent_parking || self.parking_disabled {
+            return Ok(StepOutcome::Skipped);
+        }
+        let previous = cpu_parking::get_core_parking_status().ok();
+        cpu_parking::set_core_parking_status(100, 100)
+            .and_then(|v| v.into_result("Core parking"))
+            .map_err(|e| format!("Failed to deactivate core parking: {}", e))?;
+        self.log("log-info", "Core parking deactivated".to_string());
+        self.parking_disabled = true;
+        let undo = previous.map(|state| Undo::CoreParking {
+            ac_value: state.ac_value,
+            dc_value: state.dc_value,
+        });
+        Ok(StepOutcome::Applied(undo.into_iter().collect()))
+    }
+
+    /// Affinity and priority for a process that appeared after the steps ran.
+    fn apply_process_settings(
+        &self,
+        profile: &OptimizationProfile,
+        applied: &[SessionStep],
+        pid: u32,
+    ) {
+        if applied.contains(&SessionStep::Affinity) {
+            if let Err(e) = self.set_affinity(profile, pid) {
+                self.log("log-error", e);
+            }
+        }
+        if applied.contains(&SessionStep::Priority) {
+            if let Err(e) = self.set_priority(profile, pid) {
+                self.log("log-error", e);
+            }
+        }
+    }
+
+    fn set_affinity(&self, profile: &OptimizationProfile, pid: u32) -> Result<StepOutcome, String> {
+        if profile.affinity_cores.is_empty() {
+            return Ok(StepOutcome::Skipped);
+        }
+        let previous = processes::read_affinity(pid).ok();
+        processes::apply_affinity(pid, &profile.affinity_cores)
+            .and_then(|v| v.into_result("Affinity"))
+            .map_err(|e| format!("Failed to set affinity of PID {}: {}", pid, e))?;
+        self.log(
+            "log-info",
+            format!(
+                "Affinity of PID {} set to cores {:?}",
+                pid, profile.affinity_cores
+            ),
+        );
+        let undo = previous.map(|mask| Undo::Affinity { pid, mask });
+        Ok(StepOutcome::Applied(undo.into_iter().collect()))
+    }
+
+    fn set_priority(&self, profile: &OptimizationProfile, pid: u32) -> Result<StepOutcome, String> {
+        let Some(priority) = profile.priority else {
+            return Ok(StepOutcome::Skipped);
+        };
+        let previous = processes::read_priority(pid).ok();
+        processes::apply_priority(pid, priority)
+            .map_err(|e| format!("Failed to set priority of PID {}: {}", pid, e))?;
+        self.log(
+            "log-info",
+            format!("Priority of PID {} set to {:?}", pid, priority),
+        );
+        let undo = previous.map(|priority| Undo::Priority { pid, priority });
+        Ok(StepOutcome::Applied(undo.into_iter().collect()))
+    }
+
+    /// Killing cannot be undone; failures are only logged, since a process may have exited.
+    fn kill_processes(&self, profile: &OptimizationProfile) -> StepOutcome {
+        if profile.kill_processes.is_empty() {
+            return StepOutcome::Skipped;
+        }
+        for PlannedProcess { pid, name } in processes_named(&profile.kill_processes) {
+            match processes::kill_process(pid) {
+                Ok(()) => self.log("log-info", format!("Killed {} (PID {})", name, pid)),
+                Err(e) => self.log(
+                    "log-warn",
+                    format!("Failed to kill {} (PID {}): {}", name, pid, e),
+                ),
+            }
+        }
+        StepOutcome::Applied(Vec::new())
+    }
+
+    fn throttle_processes(&self, profile: &OptimizationProfile) -> Result<StepOutcome, String> {
+        if profile.throttle_processes.is_empty() {
+            return Ok(StepOutcome::Skipped);
+        }
+        self.app
+            .state::<ThrottleState>()
+            .throttle_by_name(
+                &self.app,
+                &profile.throttle_processes,
+                &profile.throttle_limits,
+            )
+            .map_err(|e| format!("Failed to throttle processes: {}", e))?;
+        Ok(StepOutcome::Applied(vec![Undo::Throttle]))
+    }
+
+    fn restore_vibrance(&mut self) {
+        let Some((display, _)) = self.vibrance.take() else {
+            return;
+        };
+        let level = FrontendSettings::load(&self.app).vibrance.default_vibrance;
+        match vibrance::apply_vibrance(self.app.clone(), display.clone(), level) {
+            Ok(_) => self.log(
+                "log-info",
+                format!("Vibrance restored to {} on {}", level, display),
+            ),
+            Err(e) => self.log("log-error", format!("Failed to restore vibrance: {}", e)),
+        }
+    }
+
+    /// Reverts a session that is still running, because the app quits.
+    fn revert_all(&mut self) {
+        self.restore_vibrance();
+        if self.games.is_empty() {
+            return;
+        }
+        self.log(
+            "log-info",
+            "Quitting during a game session, restoring the defaults".to_string(),
+        );
+        self.games.clear();
+        self.end_session();
+        self.publish_status();
+    }
+
+    /// Reverts everything the session changed once no game runs anymore.
+    fn end_session(&mut self) {
+        self.restore_globals();
+        hog_detection::set_session_active(&self.app, false);
+    }
+
+    /// Restores power plan and core parking and lifts the throttling.
+    fn restore_globals(&mut self) {
+        let settings = FrontendSettings::load(&self.app);
+        self.restore_power_plan(&settings);
+
+        if self.parking_disabled {
+            self.parking_disabled = false;
+            let ac = settings
+                .cpu_management
+                .default_ac_parking
+                .unwrap_or(DEFAULT_PARKING);
+            let dc = settings
+                .cpu_management
+                .default_dc_parking
+                .unwrap_or(DEFAULT_PARKING);
+            match cpu_parking::set_core_parking_status(ac, dc)
+                .and_then(|v| v.into_result("Core parking"))
+            {
+                Ok(()) => self.log(
+                    "log-info",
+                    format!("Restored core parking (AC: {}%, DC: {}%)", ac, dc),
+                ),
+                Err(e) => self.log(
+                    "log-error",
+                    format!("Failed to restore core parking: {}", e),
+                ),
+            }
+        }
+
+        if let Some(throttle) = self.app.try_state::<ThrottleState>() {
+            let _ = throttle.release_all(&self.app);
+        }
+    }
+
+    fn restore_power_plan(&mut self, settings: &FrontendSettings) {
+        if self.power_plan.take().is_none() {
+            return;
+        }
+        let guid = settings.power_plan_default.guid.clone();
+        if guid.is_empty() {
+            self.log(
+                "log-warn",
+                "No default power plan configured, keeping the game's plan".to_string(),
+            );
+        } else if let Err(e) =
+            power::set_active_power_plan(guid.clone()).and_then(|v| v.into_result("Power plan"))
+        {
+            self.log("log-error", format!("Failed to restore power plan: {}", e));
+        } else {
+            self.log("log-info", format!("Restored power plan {}", guid));
+        }
+    }
+}
+
+/// Running processes with one of the names, except protected ones and this app.
+fn processes_named(names: &[String]) -> Vec<PlannedProcess> {
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(ProcessesToUpdate::All, true, ProcessRefreshKind::nothing());
+    let own_pid = std::process::id();
+    sys.processes()
+        .iter()
+        .filter_map(|(pid, process)| {
+            let name = process.name().to_string_lossy();
+            (pid.as_u32() != own_pid
+                && !processes::is_process_protected(&name)
+                && names.iter().any(|n| n.eq_ignore_ascii_case(&name)))
+            .then(|| PlannedProcess {
+                pid: pid.as_u32(),
+                name: name.into_owned(),
+            })
+        })
+        .collect()
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedProcess {
+    pub pid: u32,
+    pub name: String,
+}
+
+/// What a session would do, resolved against the running system.
+#[derive(Serialize, Debug, Clone)]
+#[serde(
+    tag = "action",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum PlannedAction {
+    PowerPlan {
+        guid: String,
+        name: Option<String>,
+        /// The plan active right now.
+        current: Option<String>,
+    },
+    CoreParking {
+        ac_value: u32,
+        dc_value: u32,
+        current: Option<CoreParkingState>,
+    },
+    Affinity {
+        cores: Vec<u32>,
+        /// Hexadecimal, since the mask does not fit into a JavaScript number.
+        mask: String,
+        /// Game processes running right now; later ones get the same.
+        pids: Vec<u32>,
+    },
+    Priority {
+        priority: ProcessPriority,
+        pids: Vec<u32>,
+    },
+    KillProcesses {
+        processes: Vec<PlannedProcess>,
+    },
+    Throttle {
+        processes: Vec<PlannedProcess>,
+        limits: ThrottleLimits,
+    },
+    /// Applied while the game is focused, restored to `restore_level` afterwards.
+    Vibrance {
+        level: u32,
+        /// The monitor of the game window if it is focused right now; otherwise the
+        /// boost goes to whichever monitor the window is on once it gets the focus.
+        display: Option<String>,
+        restore_level: u32,
+    },
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedStep {
+    #[serde(flatten)]
+    pub action: PlannedAction,
+    /// Its failure would roll back the steps before it.
+    pub critical: bool,
+    /// After the game started.
+    pub delay_ms: u64,
+    pub retries: u32,
+}
+
+/// Result of `preview_session_plan`.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionPlan {
+    pub game_id: String,
+    pub name: String,
+    /// `true` if the game has no own profile and the settings pages apply.
+    pub uses_settings: bool,
+    /// Named profile that applies, from the game or the active profile.
+    pub profile_name: Option<String>,
+    pub running_pids: Vec<u32>,
+    /// In the order the session applies them; vibrance last, as it follows the focus.
+    pub steps: Vec<PlannedStep>,
+}
+
+/// Shows what starting `game_id` (CS2 by default) would change, without changing
+/// anything.
+#[tauri::command]
+pub fn preview_session_plan(
+    app: AppHandle,
+    game_id: Option<String>,
+) -> Result<SessionPlan, String> {
+    let game_id = game_id.unwrap_or_else(|| CS2_GAME_ID.to_string());
+    let target = app
+        .state::<GameState>()
+        .target(&game_id)
+        .ok_or_else(|| format!("Unknown game '{}'", game_id))
+        .inspect_err(|err_msg| {
+            let _ = app.emit("log-error", err_msg);
+        })?;
+    let settings = FrontendSettings::load(&app);
+    let (profile, profile_name) = profiles::resolve_profile(&app, &target);
+    let status = app.state::<SessionEngine>().status()?;
+    let running_pids = status
+        .active_games
+        .iter()
+        .find(|g| g.game_id == game_id)
+        .map(|g| g.pids.clone())
+        .unwrap_or_default();
+
+    let mut actions = Vec::new();
+    if let Some(guid) = &profile.power_plan_guid {
+        let plans = power::get_power_plans().unwrap_or_default();
+        actions.push((
+            SessionStep::PowerPlan,
+            PlannedAction::PowerPlan {
+                guid: guid.clone(),
+                name: plans
+                    .iter()
+                    .find(|plan| plan.guid.eq_ignore_ascii_case(guid))
+                    .map(|plan| plan.name.clone()),
+                current: plans
+                    .iter()
+                    .find(|plan| plan.is_active)
+                    .map(|plan| plan.guid.clone()),
+            },
+        ));
+    }
+    if profile.prevent_parking {
+        actions.push((
+            SessionStep::CoreParking,
+            PlannedAction::CoreParking {
+                ac_value: 100,
+                dc_value: 100,
+                current: cpu_parking::get_core_parking_status().ok(),
+            },
+        ));
+    }
+    if !profile.affinity_cores.is_empty() {
+        let mask = processes::affinity_mask(0, &profile.affinity_cores)?;
+        actions.push((
+            SessionStep::Affinity,
+            PlannedAction::Affinity {
+                cores: profile.affinity_cores.clone(),
+                mask: format!("{:#x}", mask),
+                pids: running_pids.clone(),
+            },
+        ));
+    }
+    if let Some(priority) = profile.priority {
+        actions.push((
+            SessionStep::Priority,
+            PlannedAction::Priority {
+                priority,
+                pids: running_pids.clone(),
+            },
+        ));
+    }
+    if !profile.kill_processes.is_empty() {
+        actions.push((
+            SessionStep::KillProcesses,
+            PlannedAction::KillProcesses {
+                processes: processes_named(&profile.kill_processes),
+            },
+        ));
+    }
+    if !profile.throttle_processes.is_empty() {
+        actions.push((
+            SessionStep::Throttle,
+            PlannedAction::Throttle {
+                processes: processes_named(&profile.throttle_processes),
+                limits: profile.throttle_limits,
+            },
+        ));
+    }
+
+    let mut steps = Vec::new();
+    for policy in profile.ordered_steps() {
+        if let Some(index) = actions.iter().position(|(step, _)| *step == policy.step) {
+            steps.push(PlannedStep {
+                action: actions.swap_remove(index).1,
+                critical: profile.critical_steps.contains(&policy.step),
+                delay_ms: policy.delay_ms,
+                retries: policy.retries,
+            });
+        }
+    }
+    if let Some(level) = profile.vibrance {
+        let focused = status.foreground_game.as_deref() == Some(game_id.as_str());
+        steps.push(PlannedStep {
+            action: PlannedAction::Vibrance {
+                level,
+                display: status.foreground_display.filter(|_| focused),
+                restore_level: settings.vibrance.default_vibrance,
+            },
+            critical: false,
+            delay_ms: 0,
+            retries: 0,
+        });
+    }
+
+    Ok(SessionPlan {
+        game_id,
+        name: target.name,
+        uses_settings: profile_name.is_none() && target.profile.is_none(),
+        profile_name,
+        running_pids,
+        steps,
+    })
+}
+
+/// Switches a part of the game profiles off or back on until the app restarts.
+#[tauri::command]
+pub fn set_session_feature(
+    session: tauri::State<SessionEngine>,
+    feature: SessionFeature,
+    enabled: bool,
+) {
+    session.send(SessionMessage::SetFeature { feature, enabled });
+}
+
+/// Reverts the running games and stops optimizing for `minutes`, or until the next
+/// game starts if `None`.
+#[tauri::command]
+pub fn snooze_automation(
+    app: AppHandle,
+    session: tauri::State<SessionEngine>,
+    minutes: Option<u32>,
+) -> Result<(), String> {
+    if let Some(minutes) = minutes {
+        if minutes == 0 || minutes > MAX_SNOOZE_MINUTES {
+            let err_msg = format!(
+                "Snooze must be between 1 and {} minutes",
+                MAX_SNOOZE_MINUTES
+            );
+            let _ = app.emit("log-error", &err_msg);
+            return Err(err_msg);
+        }
+    }
+    let duration = minutes.map(|minutes| Duration::from_secs(u64::from(minutes) * 60));
+    session.send(SessionMessage::Snooze(duration));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn resume_automation(session: tauri::State<SessionEngine>) {
+    session.send(SessionMessage::Unsnooze);
+}
+
+/// Restores the defaults while games keep running; they stay unoptimized until restarted.
+#[tauri::command]
+pub fn revert_session(session: tauri::State<SessionEngine>) {
+    session.send(SessionMessage::RevertNow);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Applies every step, except that each step in `failures` fails that many times.
+    #[derive(Default)]
+    struct FakeExecutor {
+        failures: HashMap<SessionStep, u32>,
+        applied: Vec<SessionStep>,
+        undone: Vec<SessionStep>,
+        retries: Vec<(SessionStep, Duration)>,
+    }
+
+    impl StepExecutor for FakeExecutor {
+        fn apply(
+            &mut self,
+            step: SessionStep,
+            _profile: &OptimizationProfile,
+        ) -> Result<StepOutcome, String> {
+            if let Some(left) = self.failures.get_mut(&step).filter(|left| **left > 0) {
+                *left -= 1;
+                return Err(format!("{:?} failed", step));
+            }
+            self.applied.push(step);
+            let undo = match step {
+                SessionStep::PowerPlan => Undo::PowerPlan {
+                    guid: "plan".to_string(),
+                    owned: None,
+                },
+                SessionStep::CoreParking => Undo::CoreParking {
+                    ac_value: 0,
+                    dc_value: 0,
+                },
+                SessionStep::Affinity => Undo::Affinity { pid: 1, mask: 1 },
+                SessionStep::Priority => Undo::Priority {
+                    pid: 1,
+                    priority: ProcessPriority::Normal,
+                },
+                SessionStep::KillProcesses => return Ok(StepOutcome::Applied(Vec::new())),
+                SessionStep::Throttle => Undo::Throttle,
+            };
+            Ok(StepOutcome::Applied(vec![undo]))
+        }
+
+        fn undo(&mut self, undo: Undo) -> Result<(), String> {
+            self.undone.push(match undo {
+                Undo::PowerPlan { .. } => SessionStep::PowerPlan,
+                Undo::CoreParking { .. } => SessionStep::CoreParking,
+                Undo::Affinity { .. } => SessionStep::Affinity,
+                Undo::Priority { .. } => SessionStep::Priority,
+                Undo::Throttle => SessionStep::Throttle,
+            });
+            Ok(())
+        }
+
+        fn progress(
+            &mut self,
+            step: SessionStep,
+            _status: StepStatus,
+            _attempt: u32,
+            retry_in: Option<Duration>,
+        ) {
+            if let Some(wait) = retry_in {
+                self.retries.push((step, wait));
+            }
+        }
+    }
+
+    /// Uses every step.
+    fn full_profile() -> OptimizationProfile {
+        OptimizationProfile {
+            power_plan_guid: Some("plan".to_string()),
+            affinity_cores: vec![0],
+            priority: Some(ProcessPriority::High),
+            prevent_parking: true,
+            vibrance: None,
+            kill_processes: vec!["kill.exe".to_string()],
+            throttle_processes: vec!["throttle.exe".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn statuses(run: &PlanRun) -> Vec<(SessionStep, &'static str)> {
+        run.steps
+            .iter()
+            .map(|result| {
+                let status = match result.status {
+                    StepStatus::Applied => "applied",
+                    StepStatus::Skipped => "skipped",
+                    StepStatus::Failed { .. } => "failed",
+                    StepStatus::NotRun => "not run",
+                    StepStatus::RolledBack => "rolled back",
+                    StepStatus::RollbackFailed { .. } => "rollback failed",
+                };
+                (result.step, status)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn runs_all_steps_in_order() {
+        let profile = full_profile();
+        let mut executor = FakeExecutor::default();
+        let mut run = PlanRun::new(&profile, 1);
+        assert!(run.run_due(&profile, &mut executor, run.started));
+        assert_eq!(executor.applied, SessionStep::ALL);
+        assert!(!run.rolled_back);
+    }
+
+    #[test]
+    fn critical_failure_undoes_in_reverse_order() {
+        let profile = OptimizationProfile {
+            critical_steps: vec![SessionStep::KillProcesses],
+            ..full_profile()
+        };
+        let mut executor = FakeExecutor {
+            failures: HashMap::from([(SessionStep::KillProcesses, 1)]),
+            ..Default::default()
+        };
+        let mut run = PlanRun::new(&profile, 1);
+        assert!(run.run_due(&profile, &mut executor, run.started));
+        assert!(run.rolled_back);
+        run.roll_back(&mut executor);
+
+        assert_eq!(
+            executor.undone,
+            [
+                SessionStep::Priority,
+                SessionStep::Affinity,
+                SessionStep::CoreParking,
+                SessionStep::PowerPlan,
+            ]
+        );
+        assert_eq!(
+            statuses(&run),
+            [
+                (SessionStep::PowerPlan, "rolled back"),
+                (SessionStep::CoreParking, "rolled back"),
+                (SessionStep::Affinity, "rolled back"),
+                (SessionStep::Priority, "rolled back"),
+                (SessionStep::KillProcesses, "failed"),
+                (SessionStep::Throttle, "not run"),
+            ]
+        );
+    }
+
+    #[test]
+    fn non_critical_failure_continues() {
+        let profile = full_profile();
+        let mut executor = FakeExecutor {
+            failures: HashMap::from([(SessionStep::Affinity, 1)]),
+            ..Default::default()
+        };
+        let mut run = PlanRun::new(&profile, 1);
+        assert!(run.run_due(&profile, &mut executor, run.started));
+        assert!(!run.rolled_back);
+        assert_eq!(statuses(&run)[2], (SessionStep::Affinity, "failed"));
+        assert_eq!(statuses(&run)[5], (SessionStep::Throttle, "applied"));
+    }
+
+    #[test]
+    fn failed_step_retries_after_its_backoff() {
+        let profile = OptimizationProfile {
+            step_policies: vec![StepPolicy {
+                step: SessionStep::PowerPlan,
+                delay_ms: 0,
+                retries: 2,
+                backoff_ms: 100,
+                order: 0,
+            }],
+            ..full_profile()
+        };
+        let mut executor = FakeExecutor {
+            failures: HashMap::from([(SessionStep::PowerPlan, 2)]),
+            ..Default::default()
+        };
+        let mut run = PlanRun::new(&profile, 1);
+        let start = run.started;
+
+        // The failing step holds up the ones after it.
+        assert!(!run.run_due(&profile, &mut executor, start));
+        assert_eq!(run.due, start + Duration::from_millis(100));
+        assert!(executor.applied.is_empty());
+
+        assert!(!run.run_due(&profile, &mut executor, start + Duration::from_millis(99)));
+        assert_eq!(run.attempt, 1);
+
+        let second = start + Duration::from_millis(100);
+        assert!(!run.run_due(&profile, &mut executor, second));
+        assert_eq!(run.due, second + Duration::from_millis(200));
+
+        assert!(run.run_due(&profile, &mut executor, second + Duration::from_millis(200)));
+        assert_eq!(
+            executor.retries,
+            [
+                (SessionStep::PowerPlan, Duration::from_millis(100)),
+                (SessionStep::PowerPlan, Duration::from_millis(200)),
+            ]
+        );
+        assert_eq!(executor.applied, SessionStep::ALL);
+        assert_eq!(statuses(&run)[0], (SessionStep::PowerPlan, "applied"));
+    }
+
+    #[test]
+    fn steps_wait_for_their_delay() {
+        let profile = OptimizationProfile {
+            step_policies: vec![StepPolicy {
+                step: SessionStep::Affinity,
+                delay_ms: 5000,
+                retries: 0,
+                backoff_ms: 0,
+                order: 0,
+            }],
+            ..full_profile()
+        };
+        let mut executor = FakeExecutor::default();
+        let mut run = PlanRun::new(&profile, 1);
+        assert!(!run.run_due(&profile, &mut executor, run.started));
+        assert_eq!(
+            executor.applied,
+            [SessionStep::PowerPlan, SessionStep::CoreParking]
+        );
+        assert!(run.run_due(
+            &profile,
+            &mut executor,
+            run.started + Duration::from_secs(5)
+        ));
+        assert_eq!(executor.applied, SessionStep::ALL);
+    }
+}
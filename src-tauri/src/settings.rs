@@ -0,0 +1,113 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_store::StoreExt;
+
+/// The store file shared with the frontend (`src/lib/storage.ts`).
+///
+/// `app.store()` returns the instance the frontend already opened, so both sides see the
+/// same in-memory data. Backend features only touch their own keys.
+pub const STORE_PATH: &str = "settings.json";
+
+/// Reads a key from the settings store.
+///
+/// Returns `None` if the key is missing or does not match `T` (e.g. after a schema change),
+/// so callers can fall back to their defaults.
+pub fn load<R: Runtime, T: DeserializeOwned>(app: &AppHandle<R>, key: &str) -> Option<T> {
+    let store = app.store(STORE_PATH).ok()?;
+    let value = store.get(key)?;
+    serde_json::from_value(value).ok()
+}
+
+/// Writes a key to the settings store and saves the file immediately.
+pub fn save<R: Runtime, T: Serialize>(
+    app: &AppHandle<R>,
+    key: &str,
+    value: &T,
+) -> Result<(), String> {
+    let store = app
+        .store(STORE_PATH)
+        .map_err(|e| format!("Failed to open {}: {}", STORE_PATH, e))?;
+    let value = serde_json::to_value(value).map_err(|e| e.to_string())?;
+    store.set(key, value);
+    store
+        .save()
+        .map_err(|e| format!("Failed to save {}: {}", STORE_PATH, e))
+}
+
+/// A power plan as stored by the frontend.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct PowerPlanSetting {
+    pub name: String,
+    pub guid: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VibranceSettings {
+    pub enabled: bool,
+    pub default_vibrance: u32,
+    pub cs2_vibrance: u32,
+}
+
+impl Default for VibranceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            default_vibrance: 50,
+            cs2_vibrance: 100,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CpuManagementSettings {
+    pub enabled: bool,
+    pub selected_cores: Vec<u32>,
+    pub prevent_parking: bool,
+    pub default_ac_parking: Option<u32>,
+    pub default_dc_parking: Option<u32>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ThrottleSettings {
+    pub enabled: bool,
+    pub processes: Vec<String>,
+    pub cpu_percent: Option<u32>,
+    pub memory_mb: Option<u64>,
+}
+
+/// The settings the frontend pages edit (`defaultAppSettings` in `src/lib/settingsStore.ts`).
+///
+/// Every page stores its own top-level key, so they are read one by one. Read it again
+/// whenever it is needed: the frontend may change any of these at any time.
+#[derive(Debug, Clone, Default)]
+pub struct FrontendSettings {
+    pub processes_to_kill: Vec<String>,
+    pub process_management_active: bool,
+    pub power_plan_cs2: PowerPlanSetting,
+    pub power_plan_default: PowerPlanSetting,
+    pub power_plan_management_active: bool,
+    pub vibrance: VibranceSettings,
+    pub cpu_management: CpuManagementSettings,
+    pub throttle: ThrottleSettings,
+}
+
+impl FrontendSettings {
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        Self {
+            processes_to_kill: load(app, "processesToKill").unwrap_or_default(),
+            process_management_active: load(app, "processManagementActive").unwrap_or_default(),
+            power_plan_cs2: load(app, "powerPlanCS2").unwrap_or_default(),
+            power_plan_default: load(app, "powerPlanDefault").unwrap_or_default(),
+            power_plan_management_active: load(app, "powerPlanManagementActive")
+                .unwrap_or_default(),
+            vibrance: load(app, "vibranceSettings").unwrap_or_default(),
+            cpu_management: load(app, "cpuManagement").unwrap_or_default(),
+            throttle: load(app, "throttleSettings").unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use crate::session::SessionEngine;
+use crate::{cs2monitoring, hog_detection, process_stream, throttle, ShutdownState};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How long quitting waits for the session to restore power plan, parking and vibrance.
+/// Windows itself only waits about five seconds for an app to finish `WM_ENDSESSION`.
+const SESSION_REVERT_TIMEOUT: Duration = Duration::from_secs(4);
+/// A monitor pass can hang in a slow process scan; it must not eat into the time above.
+const MONITOR_STOP_TIMEOUT: Duration = Duration::from_millis(500);
+
+static REVERTED: AtomicBool = AtomicBool::new(false);
+
+/// Stops the background threads and undoes everything that outlives the app.
+///
+/// Runs once, on whichever comes first: the app exiting (tray "Quit", closing) or
+/// Windows ending the user session (shutdown, restart, logoff; see `system_events`).
+pub fn revert_all(app: &AppHandle) {
+    if REVERTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    if let Some(state) = app.try_state::<ShutdownState>() {
+        state.flag.store(true, Ordering::Relaxed);
+    }
+    // The monitor goes first, so no game event reaches the session while it reverts.
+    // Events it still sends after the session stopped are dropped.
+    if let Some(monitor) = app.try_state::<cs2monitoring::MonitorService>() {
+        if !monitor.stop(MONITOR_STOP_TIMEOUT) {
+            let _ = app.emit("log-warn", "Game monitor did not stop in time");
+        }
+    }
+    if let Some(session) = app.try_state::<SessionEngine>() {
+        if !session.revert(SESSION_REVERT_TIMEOUT) {
+            let _ = app.emit(
+                "log-warn",
+                "Game session did not finish restoring the defaults in time",
+            );
+        }
+    }
+    if let Some(streams) = app.try_state::<process_stream::ProcessStreamState>() {
+        streams.stop_all();
+    }
+    if let Some(hogs) = app.try_state::<hog_detection::HogDetectionState>() {
+        hog_detection::revert_mitigations(app, &hogs);
+    }
+    // Job object and cgroup limits outlive the app, so they are lifted here.
+    if let Some(throttle) = app.try_state::<throttle::ThrottleState>() {
+        let _ = throttle.release_all(app);
+    }
+}
@@ -0,0 +1,294 @@
+use crate::session::{SessionEngine, SessionMessage};
+use crate::shutdown;
+use serde::{Deserialize, Serialize};
+#[cfg(any(test, debug_assertions))]
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+#[cfg(target_os = "windows")]
+use std::sync::OnceLock;
+use std::thread;
+use tauri::{AppHandle, Emitter, Manager};
+#[cfg(target_os = "windows")]
+use winapi::shared::minwindef::{FALSE, LPARAM, LRESULT, TRUE, UINT, WPARAM};
+#[cfg(target_os = "windows")]
+use winapi::shared::windef::HWND;
+#[cfg(target_os = "windows")]
+use winapi::um::libloaderapi::GetModuleHandleW;
+#[cfg(target_os = "windows")]
+use winapi::um::winuser::{
+    CreateWindowExW, DefWindowProcW, DispatchMessageW, GetMessageW, RegisterClassW,
+    TranslateMessage, MSG, PBT_APMRESUMEAUTOMATIC, PBT_APMSUSPEND, WM_DISPLAYCHANGE, WM_ENDSESSION,
+    WM_POWERBROADCAST, WM_QUERYENDSESSION, WNDCLASSW, WS_OVERLAPPED,
+};
+
+/// A change of the system that can undo what a game session applied.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SystemEvent {
+    Suspending,
+    /// Back from sleep or hibernation. The driver resets the vibrance, and Windows may
+    /// restore the previous power plan.
+    Resumed,
+    /// A monitor was added, removed or changed resolution.
+    DisplaysChanged,
+    /// Shutdown, restart or logoff. Handled before the call returns, since Windows ends
+    /// the process right after.
+    SessionEnding,
+}
+
+pub type EventSink = Arc<dyn Fn(SystemEvent) + Send + Sync>;
+
+/// Delivers system notifications.
+pub trait SystemEventSource: Send {
+    fn description(&self) -> &'static str;
+
+    /// Delivers events to `sink` until the app exits. Runs on a thread of its own.
+    fn run(self: Box<Self>, sink: EventSink) -> Result<(), String>;
+}
+
+/// Events sent through a channel by `simulate_system_event`, so resume handling can be
+/// tried without sleeping the machine, on Linux as well. Debug builds and tests only.
+#[cfg(any(test, debug_assertions))]
+pub struct ChannelSource {
+    receiver: Receiver<SystemEvent>,
+}
+
+#[cfg(any(test, debug_assertions))]
+impl ChannelSource {
+    pub fn new() -> (Sender<SystemEvent>, Self) {
+        let (sender, receiver) = mpsc::channel();
+        (sender, Self { receiver })
+    }
+}
+
+#[cfg(any(test, debug_assertions))]
+impl SystemEventSource for ChannelSource {
+    fn description(&self) -> &'static str {
+        "simulated events"
+    }
+
+    fn run(self: Box<Self>, sink: EventSink) -> Result<(), String> {
+        for event in self.receiver {
+            sink(event);
+        }
+        Ok(())
+    }
+}
+
+/// Messages Windows broadcasts to top-level windows.
+///
+/// The webview window's messages are handled by the event loop, so a hidden window on
+/// its own thread receives them.
+#[cfg(target_os = "windows")]
+pub struct WindowMessageSource;
+
+#[cfg(target_os = "windows")]
+static WINDOW_SINK: OnceLock<EventSink> = OnceLock::new();
+
+#[cfg(target_os = "windows")]
+impl SystemEventSource for WindowMessageSource {
+    fn description(&self) -> &'static str {
+        "window messages"
+    }
+
+    fn run(self: Box<Self>, sink: EventSink) -> Result<(), String> {
+        if WINDOW_SINK.set(sink).is_err() {
+            return Err("already running".to_string());
+        }
+        unsafe {
+            let class_name: Vec<u16> = "CS2ToolsSystemEvents\0".encode_utf16().collect();
+            let instance = GetModuleHandleW(std::ptr::null());
+            let class = WNDCLASSW {
+                lpfnWndProc: Some(window_proc),
+                hInstance: instance,
+                lpszClassName: class_name.as_ptr(),
+                ..std::mem::zeroed()
+            };
+            if RegisterClassW(&class) == 0 {
+                return Err("failed to register the window class".to_string());
+            }
+            let hwnd = CreateWindowExW(
+                0,
+                class_name.as_ptr(),
+                class_name.as_ptr(),
+                WS_OVERLAPPED,
+                0,
+                0,
+                0,
+                0,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                instance,
+                std::ptr::null_mut(),
+            );
+            if hwnd.is_null() {
+                return Err("failed to create the window".to_string());
+            }
+            let mut msg: MSG = std::mem::zeroed();
+            while GetMessageW(&mut msg, std::ptr::null_mut(), 0, 0) > 0 {
+                TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+unsafe extern "system" fn window_proc(
+    hwnd: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    let event = match msg {
+        // Never block the shutdown; the revert happens once it is certain.
+        WM_QUERYENDSESSION => return TRUE as LRESULT,
+        // `wparam` is `FALSE` if another app cancelled the shutdown.
+        WM_ENDSESSION if wparam != FALSE as WPARAM => Some(SystemEvent::SessionEnding),
+        WM_ENDSESSION => None,
+        // `PBT_APMRESUMEAUTOMATIC` is sent on every resume, `PBT_APMRESUMESUSPEND` only
+        // if a user is present, so only the first counts.
+        WM_POWERBROADCAST => match wparam {
+            PBT_APMSUSPEND => Some(SystemEvent::Suspending),
+            PBT_APMRESUMEAUTOMATIC => Some(SystemEvent::Resumed),
+            _ => None,
+        },
+        WM_DISPLAYCHANGE => Some(SystemEvent::DisplaysChanged),
+        _ => return DefWindowProcW(hwnd, msg, wparam, lparam),
+    };
+    if let (Some(event), Some(sink)) = (event, WINDOW_SINK.get()) {
+        sink(event);
+    }
+    match msg {
+        WM_POWERBROADCAST => TRUE as LRESULT,
+        _ => 0,
+    }
+}
+
+/// Feeds simulated events in.
+#[cfg(debug_assertions)]
+pub struct SystemEventState {
+    injector: Sender<SystemEvent>,
+}
+
+/// Starts the OS notification source, and in debug builds the channel for simulated
+/// events.
+pub fn start(app: AppHandle) {
+    let dispatch_app = app.clone();
+    let sink: EventSink = Arc::new(move |event| dispatch(&dispatch_app, event));
+
+    #[cfg(debug_assertions)]
+    {
+        let (injector, channel) = ChannelSource::new();
+        spawn_source(app.clone(), Box::new(channel), sink.clone());
+        app.manage(SystemEventState { injector });
+    }
+    #[cfg(target_os = "windows")]
+    spawn_source(app, Box::new(WindowMessageSource), sink);
+    #[cfg(not(target_os = "windows"))]
+    let _ = (app, sink);
+}
+
+fn spawn_source(app: AppHandle, source: Box<dyn SystemEventSource>, sink: EventSink) {
+    thread::spawn(move || {
+        let description = source.description();
+        if let Err(e) = source.run(sink) {
+            let _ = app.emit(
+                "log-warn",
+                format!(
+                    "System notifications via {} unavailable: {}",
+                    description, e
+                ),
+            );
+        }
+    });
+}
+
+fn dispatch(app: &AppHandle, event: SystemEvent) {
+    match event {
+        SystemEvent::SessionEnding => shutdown::revert_all(app),
+        SystemEvent::Suspending => {
+            let _ = app.emit("log-info", "System is going to sleep");
+        }
+        SystemEvent::Resumed | SystemEvent::DisplaysChanged => {}
+    }
+    if let (Some(message), Some(session)) =
+        (session_message(event), app.try_state::<SessionEngine>())
+    {
+        session.send(message);
+    }
+    let _ = app.emit("system-event", event);
+}
+
+/// What the running session has to do after `event`.
+fn session_message(event: SystemEvent) -> Option<SessionMessage> {
+    match event {
+        SystemEvent::Resumed | SystemEvent::DisplaysChanged => Some(SessionMessage::Reapply(event)),
+        // Handled by `shutdown::revert_all` directly, the session may be stuck.
+        SystemEvent::SessionEnding | SystemEvent::Suspending => None,
+    }
+}
+
+/// Injects a system event, e.g. to try resume handling without sleeping the machine.
+/// Debug builds only.
+#[cfg(debug_assertions)]
+#[tauri::command]
+pub fn simulate_system_event(
+    app: AppHandle,
+    state: tauri::State<SystemEventState>,
+    event: SystemEvent,
+) -> Result<(), String> {
+    state
+        .injector
+        .send(event)
+        .map_err(|e| e.to_string())
+        .inspect_err(|err_msg| {
+            let _ = app.emit("log-error", err_msg);
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Runs `events` through a `ChannelSource` and returns what reaches the session.
+    fn drive(events: &[SystemEvent]) -> Vec<SessionMessage> {
+        let (sender, source) = ChannelSource::new();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink_received = received.clone();
+        let sink: EventSink = Arc::new(move |event| {
+            if let Some(message) = session_message(event) {
+                sink_received.lock().unwrap().push(message);
+            }
+        });
+        for &event in events {
+            sender.send(event).unwrap();
+        }
+        // The source returns once every sender is gone.
+        drop(sender);
+        Box::new(source).run(sink).unwrap();
+        Arc::try_unwrap(received).unwrap().into_inner().unwrap()
+    }
+
+    #[test]
+    fn resume_and_display_change_reapply() {
+        let messages = drive(&[SystemEvent::Resumed, SystemEvent::DisplaysChanged]);
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(
+            messages[0],
+            SessionMessage::Reapply(SystemEvent::Resumed)
+        ));
+        assert!(matches!(
+            messages[1],
+            SessionMessage::Reapply(SystemEvent::DisplaysChanged)
+        ));
+    }
+
+    #[test]
+    fn suspend_and_session_end_do_not_reach_the_session() {
+        let messages = drive(&[SystemEvent::Suspending, SystemEvent::SessionEnding]);
+        assert!(messages.is_empty());
+    }
+}
@@ -0,0 +1,778 @@
+use crate::process_groups::{GroupActionFailure, GroupActionResult};
+use crate::processes::is_process_protected;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{AppHandle, Emitter};
+
+#[cfg(target_os = "windows")]
+use std::collections::HashMap;
+#[cfg(target_os = "windows")]
+use winapi::shared::minwindef::FALSE;
+#[cfg(target_os = "windows")]
+use winapi::um::handleapi::CloseHandle;
+#[cfg(target_os = "windows")]
+use winapi::um::jobapi2::{AssignProcessToJobObject, CreateJobObjectW, SetInformationJobObject};
+#[cfg(target_os = "windows")]
+use winapi::um::processthreadsapi::OpenProcess;
+#[cfg(target_os = "windows")]
+use winapi::um::winnt::{
+    JobObjectCpuRateControlInformation, JobObjectExtendedLimitInformation, HANDLE,
+    JOBOBJECT_CPU_RATE_CONTROL_INFORMATION, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+    JOB_OBJECT_CPU_RATE_CONTROL_ENABLE, JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP,
+    JOB_OBJECT_LIMIT_PROCESS_MEMORY, PROCESS_SET_QUOTA, PROCESS_TERMINATE,
+};
+
+#[cfg(target_os = "linux")]
+use std::collections::HashMap;
+#[cfg(target_os = "linux")]
+use std::fs;
+#[cfg(target_os = "linux")]
+use std::path::PathBuf;
+
+/// Smallest memory limit accepted. Lower values make most applications fail immediately.
+const MIN_MEMORY_MB: u64 = 64;
+
+/// Limits for a throttled process.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ThrottleLimits {
+    /// Share of the *whole* machine (all cores together) in percent, 1-100.
+    pub cpu_percent: Option<u32>,
+    /// Memory limit in MiB.
+    ///
+    /// On Windows this is a hard commit limit (allocations above it fail), on Linux it is
+    /// `memory.high` (the kernel reclaims memory and slows the process down instead).
+    pub memory_mb: Option<u64>,
+}
+
+impl ThrottleLimits {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.cpu_percent.is_none() && self.memory_mb.is_none() {
+            return Err("No throttle limits specified".to_string());
+        }
+        if let Some(cpu) = self.cpu_percent {
+            if !(1..=100).contains(&cpu) {
+                return Err(format!(
+                    "CPU limit must be between 1 and 100 %, got {}",
+                    cpu
+                ));
+            }
+        }
+        if let Some(memory) = self.memory_mb {
+            if memory < MIN_MEMORY_MB {
+                return Err(format!(
+                    "Memory limit must be at least {} MiB, got {}",
+                    MIN_MEMORY_MB, memory
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// `CpuRate` of a job object, in 1/100 of a percent of all processors together.
+    #[cfg(any(test, target_os = "windows"))]
+    fn job_cpu_rate(&self) -> Option<u32> {
+        self.cpu_percent.map(|percent| percent * 100)
+    }
+
+    fn memory_bytes(&self) -> Option<u64> {
+        self.memory_mb.map(|memory_mb| memory_mb * 1024 * 1024)
+    }
+
+    /// Content of `cpu.max`. The quota is per period over all cores, so 100 % of an
+    /// 8-core machine is a quota of 8 periods.
+    #[cfg(any(test, target_os = "linux"))]
+    fn cgroup_cpu_max(&self, cores: u64) -> String {
+        match self.cpu_percent {
+            Some(percent) => {
+                let quota = CGROUP_CPU_PERIOD_US * cores * percent as u64 / 100;
+                format!("{} {}", quota.max(1000), CGROUP_CPU_PERIOD_US)
+            }
+            None => format!("max {}", CGROUP_CPU_PERIOD_US),
+        }
+    }
+
+    /// Content of `memory.high`.
+    #[cfg(any(test, target_os = "linux"))]
+    fn cgroup_memory_high(&self) -> String {
+        match self.memory_bytes() {
+            Some(bytes) => bytes.to_string(),
+            None => "max".to_string(),
+        }
+    }
+}
+
+/// Puts processes into a resource-limited container and takes them out again.
+pub trait Throttler: Send {
+    /// Limits a process. Calling it again for the same PID updates the limits.
+    fn throttle(&mut self, pid: u32, limits: &ThrottleLimits) -> Result<(), String>;
+
+    /// Removes all limits from a process that was throttled before.
+    fn release(&mut self, pid: u32) -> Result<(), String>;
+
+    fn throttled_pids(&self) -> Vec<u32>;
+
+    fn release_all(&mut self) -> GroupActionResult {
+        let mut result = GroupActionResult::default();
+        for pid in self.throttled_pids() {
+            match self.release(pid) {
+                Ok(()) => result.succeeded.push(pid),
+                Err(error) => result.failed.push(GroupActionFailure { pid, error }),
+            }
+        }
+        result
+    }
+}
+
+// * NOTE: Job Objects
+// Every throttled process gets its own job, so each one gets the full CPU cap instead of
+// sharing it. A process cannot leave a job, so "release" lifts all limits and closes the
+// handle. Without `JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE` the process keeps running.
+// Nested jobs (Windows 8+) allow this even for processes that are already in a job,
+// e.g. games started by Steam or sandboxed browser processes.
+#[cfg(target_os = "windows")]
+struct JobHandle(HANDLE);
+
+// The handle is only used behind the `ThrottleState` mutex.
+#[cfg(target_os = "windows")]
+unsafe impl Send for JobHandle {}
+
+#[cfg(target_os = "windows")]
+impl Drop for JobHandle {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+#[derive(Default)]
+pub struct JobObjectThrottler {
+    jobs: HashMap<u32, JobHandle>,
+}
+
+#[cfg(target_os = "windows")]
+impl JobObjectThrottler {
+    fn set_limits(job: &JobHandle, limits: &ThrottleLimits) -> Result<(), String> {
+        unsafe {
+            let mut cpu: JOBOBJECT_CPU_RATE_CONTROL_INFORMATION = std::mem::zeroed();
+            if let Some(rate) = limits.job_cpu_rate() {
+                cpu.ControlFlags =
+                    JOB_OBJECT_CPU_RATE_CONTROL_ENABLE | JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP;
+                *cpu.u.CpuRate_mut() = rate;
+            }
+            let result = SetInformationJobObject(
+                job.0,
+                JobObjectCpuRateControlInformation,
+                &mut cpu as *mut _ as *mut _,
+                std::mem::size_of::<JOBOBJECT_CPU_RATE_CONTROL_INFORMATION>() as u32,
+            );
+            if result == 0 {
+                return Err("Failed to set the CPU rate limit of the job object".to_string());
+            }
+
+            let mut extended: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+            if let Some(bytes) = limits.memory_bytes() {
+                extended.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+                extended.ProcessMemoryLimit = bytes as _;
+            }
+            let result = SetInformationJobObject(
+                job.0,
+                JobObjectExtendedLimitInformation,
+                &mut extended as *mut _ as *mut _,
+                std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            );
+            if result == 0 {
+                return Err("Failed to set the memory limit of the job object".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Throttler for JobObjectThrottler {
+    fn throttle(&mut self, pid: u32, limits: &ThrottleLimits) -> Result<(), String> {
+        if let Some(job) = self.jobs.get(&pid) {
+            return Self::set_limits(job, limits);
+        }
+
+        unsafe {
+            let job = CreateJobObjectW(std::ptr::null_mut(), std::ptr::null());
+            if job.is_null() {
+                return Err(format!("Failed to create a job object for PID {}", pid));
+            }
+            // Closes the job again on every early return below.
+            let job = JobHandle(job);
+            Self::set_limits(&job, limits)?;
+
+            let process = OpenProcess(PROCESS_SET_QUOTA | PROCESS_TERMINATE, FALSE, pid);
+            if process.is_null() {
+                return Err(format!(
+                    "Permission denied: cannot open process with PID {} to throttle it",
+                    pid
+                ));
+            }
+            let result = AssignProcessToJobObject(job.0, process);
+            CloseHandle(process);
+            if result == 0 {
+                return Err(format!(
+                    "Failed to assign process with PID {} to a job object",
+                    pid
+                ));
+            }
+
+            self.jobs.insert(pid, job);
+        }
+        Ok(())
+    }
+
+    fn release(&mut self, pid: u32) -> Result<(), String> {
+        let job = self
+            .jobs
+            .remove(&pid)
+            .ok_or_else(|| format!("Process with PID {} is not throttled", pid))?;
+        // The handle is closed when `job` is dropped, even if lifting the limits failed.
+        Self::set_limits(&job, &ThrottleLimits::default())
+    }
+
+    fn throttled_pids(&self) -> Vec<u32> {
+        self.jobs.keys().copied().collect()
+    }
+}
+
+// * NOTE: cgroup v2
+// Throttled processes are moved into `<root>/cs2-tools/pid-<pid>`, a leaf per process with
+// its own `cpu.max` and `memory.high`. On release they are moved back into the cgroup they
+// came from (read from `/proc/<pid>/cgroup`) and the leaf is removed.
+// Writing below `/sys/fs/cgroup` needs root or a delegated subtree (e.g. the user's
+// `user@<uid>.service`), which is why the root is configurable.
+#[cfg(target_os = "linux")]
+const CGROUP_GROUP_NAME: &str = "cs2-tools";
+/// Overrides `CgroupThrottler::DEFAULT_ROOT`, e.g. with a delegated subtree.
+#[cfg(target_os = "linux")]
+const CGROUP_ROOT_ENV: &str = "CS2_TOOLS_CGROUP_ROOT";
+#[cfg(any(test, target_os = "linux"))]
+const CGROUP_CPU_PERIOD_US: u64 = 100_000;
+
+#[cfg(target_os = "linux")]
+pub struct CgroupThrottler {
+    root: PathBuf,
+    /// `/proc`, replaced in tests.
+    proc_root: PathBuf,
+    /// Original cgroup directory per throttled PID.
+    throttled: HashMap<u32, PathBuf>,
+}
+
+#[cfg(target_os = "linux")]
+impl CgroupThrottler {
+    pub const DEFAULT_ROOT: &'static str = "/sys/fs/cgroup";
+
+    /// Creates a throttler working below `root`, which must be the cgroup v2 mount point
+    /// (or a directory laid out like one, for tests).
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            proc_root: PathBuf::from("/proc"),
+            throttled: HashMap::new(),
+        }
+    }
+
+    /// The root from `CS2_TOOLS_CGROUP_ROOT`, or `DEFAULT_ROOT` if it is not set.
+    pub fn configured_root() -> PathBuf {
+        std::env::var_os(CGROUP_ROOT_ENV)
+            .filter(|root| !root.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(Self::DEFAULT_ROOT))
+    }
+
+    fn leaf(&self, pid: u32) -> PathBuf {
+        self.root
+            .join(CGROUP_GROUP_NAME)
+            .join(format!("pid-{}", pid))
+    }
+
+    fn original_cgroup(&self, pid: u32) -> Result<PathBuf, String> {
+        let path = self.proc_root.join(pid.to_string()).join("cgroup");
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read the cgroup of PID {}: {}", pid, e))?;
+        // cgroup v2 has a single line of the form `0::/user.slice/...`.
+        let relative = content
+            .lines()
+            .find_map(|line| line.strip_prefix("0::"))
+            .ok_or_else(|| format!("PID {} is not in a cgroup v2 hierarchy", pid))?;
+        Ok(self.root.join(relative.trim().trim_start_matches('/')))
+    }
+
+    fn write(path: PathBuf, value: &str) -> Result<(), String> {
+        fs::write(&path, value).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    fn write_limits(leaf: &std::path::Path, limits: &ThrottleLimits) -> Result<(), String> {
+        let cores = std::thread::available_parallelism()
+            .map(|n| n.get() as u64)
+            .unwrap_or(1);
+        Self::write(leaf.join("cpu.max"), &limits.cgroup_cpu_max(cores))?;
+        Self::write(leaf.join("memory.high"), &limits.cgroup_memory_high())
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Throttler for CgroupThrottler {
+    fn throttle(&mut self, pid: u32, limits: &ThrottleLimits) -> Result<(), String> {
+        let leaf = self.leaf(pid);
+        if self.throttled.contains_key(&pid) {
+            return Self::write_limits(&leaf, limits);
+        }
+
+        let original = self.original_cgroup(pid)?;
+
+        // Controllers have to be enabled on every level above the leaf. The root may already
+        // have them (or not allow changes), so only the result of the leaf writes counts.
+        let group = self.root.join(CGROUP_GROUP_NAME);
+        fs::create_dir_all(&group)
+            .map_err(|e| format!("Failed to create {}: {}", group.display(), e))?;
+        let _ = Self::write(self.root.join("cgroup.subtree_control"), "+cpu +memory");
+        Self::write(group.join("cgroup.subtree_control"), "+cpu +memory")?;
+
+        fs::create_dir_all(&leaf)
+            .map_err(|e| format!("Failed to create {}: {}", leaf.display(), e))?;
+        let result = Self::write_limits(&leaf, limits)
+            .and_then(|_| Self::write(leaf.join("cgroup.procs"), &pid.to_string()));
+        if let Err(err) = result {
+            let _ = fs::remove_dir(&leaf);
+            return Err(err);
+        }
+
+        self.throttled.insert(pid, original);
+        Ok(())
+    }
+
+    fn release(&mut self, pid: u32) -> Result<(), String> {
+        let original = self
+            .throttled
+            .remove(&pid)
+            .ok_or_else(|| format!("Process with PID {} is not throttled", pid))?;
+
+        // An exited process has already left the leaf, there is nothing to move back.
+        let result = if self.proc_root.join(pid.to_string()).exists() {
+            Self::write(original.join("cgroup.procs"), &pid.to_string())
+        } else {
+            Ok(())
+        };
+        // Fails while the leaf is not empty; the next release attempt cleans it up.
+        let _ = fs::remove_dir(self.leaf(pid));
+        result
+    }
+
+    fn throttled_pids(&self) -> Vec<u32> {
+        self.throttled.keys().copied().collect()
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub struct UnsupportedThrottler;
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+impl Throttler for UnsupportedThrottler {
+    fn throttle(&mut self, _pid: u32, _limits: &ThrottleLimits) -> Result<(), String> {
+        Err("Throttling processes is not supported on this OS".to_string())
+    }
+
+    fn release(&mut self, pid: u32) -> Result<(), String> {
+        Err(format!("Process with PID {} is not throttled", pid))
+    }
+
+    fn throttled_pids(&self) -> Vec<u32> {
+        Vec::new()
+    }
+}
+
+/// The throttler of the current session, shared by all commands.
+pub struct ThrottleState {
+    throttler: Mutex<Box<dyn Throttler>>,
+}
+
+impl Default for ThrottleState {
+    fn default() -> Self {
+        #[cfg(target_os = "windows")]
+        let throttler: Box<dyn Throttler> = Box::<JobObjectThrottler>::default();
+        #[cfg(target_os = "linux")]
+        let throttler: Box<dyn Throttler> =
+            Box::new(CgroupThrottler::new(CgroupThrottler::configured_root()));
+        #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+        let throttler: Box<dyn Throttler> = Box::new(UnsupportedThrottler);
+
+        Self::with_throttler(throttler)
+    }
+}
+
+impl ThrottleState {
+    pub fn with_throttler(throttler: Box<dyn Throttler>) -> Self {
+        Self {
+            throttler: Mutex::new(throttler),
+        }
+    }
+
+    /// Throttles with cgroups below `root` instead of the configured root.
+    #[cfg(target_os = "linux")]
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self::with_throttler(Box::new(CgroupThrottler::new(root)))
+    }
+
+    /// Throttles a single process, e.g. one flagged by the CPU-hog detection.
+    ///
+    /// It is released together with all other throttled processes when the last game exits.
+    pub fn throttle(&self, pid: u32, limits: &ThrottleLimits) -> Result<(), String> {
+        limits.validate()?;
+        self.throttler
+            .lock()
+            .map_err(|e| e.to_string())?
+            .throttle(pid, limits)
+    }
+
+    /// Throttles every running process with one of the given names.
+    ///
+    /// Protected processes and the app itself are skipped.
+    pub fn throttle_by_name(
+        &self,
+        app: &AppHandle,
+        process_names: &[String],
+        limits: &ThrottleLimits,
+    ) -> Result<GroupActionResult, String> {
+        limits.validate().inspect_err(|err_msg| {
+            let _ = app.emit("log-error", err_msg);
+        })?;
+
+        let mut sys = System::new();
+        sys.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing(),
+        );
+        let own_pid = std::process::id();
+        let pids: Vec<u32> = sys
+            .processes()
+            .iter()
+            .filter(|(_, process)| {
+                let name = process.name().to_string_lossy();
+                !is_process_protected(&name)
+                    && process_names
+                        .iter()
+                        .any(|target| target.eq_ignore_ascii_case(&name))
+            })
+            .map(|(pid, _)| pid.as_u32())
+            .filter(|pid| *pid != own_pid)
+            .collect();
+
+        let mut throttler = self.throttler.lock().map_err(|e| e.to_string())?;
+        let mut result = GroupActionResult::default();
+        for pid in pids {
+            match throttler.throttle(pid, limits) {
+                Ok(()) => result.succeeded.push(pid),
+                Err(error) => {
+                    let _ = app.emit("log-warn", &error);
+                    result.failed.push(GroupActionFailure { pid, error });
+                }
+            }
+        }
+
+        let _ = app.emit(
+            "log-info",
+            format!(
+                "Throttled {:?} to {:?}: {} succeeded, {} failed",
+                process_names,
+                limits,
+                result.succeeded.len(),
+                result.failed.len()
+            ),
+        );
+        Ok(result)
+    }
+
+    /// Releases every throttled process and logs the outcome.
+    pub fn release_all(&self, app: &AppHandle) -> Result<GroupActionResult, String> {
+        let result = self
+            .throttler
+            .lock()
+            .map_err(|e| e.to_string())?
+            .release_all();
+
+        if !result.succeeded.is_empty() || !result.failed.is_empty() {
+            let _ = app.emit(
+                "log-info",
+                format!(
+                    "Released throttled processes: {} succeeded, {} failed",
+                    result.succeeded.len(),
+                    result.failed.len()
+                ),
+            );
+        }
+        for failure in &result.failed {
+            let _ = app.emit(
+                "log-warn",
+                format!("Failed to release PID {}: {}", failure.pid, failure.error),
+            );
+        }
+        Ok(result)
+    }
+}
+
+/// Throttles every running process with one of the given names.
+///
+/// Protected processes and the app itself are skipped.
+#[tauri::command]
+pub fn throttle_processes(
+    app: AppHandle,
+    state: tauri::State<ThrottleState>,
+    process_names: Vec<String>,
+    limits: ThrottleLimits,
+) -> Result<GroupActionResult, String> {
+    state.throttle_by_name(&app, &process_names, &limits)
+}
+
+/// Releases every throttled process. This also happens automatically when the last game exits.
+#[tauri::command]
+pub fn release_throttled_processes(
+    app: AppHandle,
+    state: tauri::State<ThrottleState>,
+) -> Result<GroupActionResult, String> {
+    state.release_all(&app)
+}
+
+/// Returns the PIDs that are currently throttled.
+#[tauri::command]
+pub fn get_throttled_processes(state: tauri::State<ThrottleState>) -> Result<Vec<u32>, String> {
+    let mut pids = state
+        .throttler
+        .lock()
+        .map_err(|e| e.to_string())?
+        .throttled_pids();
+    pids.sort_unstable();
+    Ok(pids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(cpu_percent: Option<u32>, memory_mb: Option<u64>) -> ThrottleLimits {
+        ThrottleLimits {
+            cpu_percent,
+            memory_mb,
+        }
+    }
+
+    #[test]
+    fn validates_limits() {
+        assert!(limits(Some(25), None).validate().is_ok());
+        assert!(limits(None, Some(MIN_MEMORY_MB)).validate().is_ok());
+        assert!(limits(None, None).validate().is_err());
+        assert!(limits(Some(0), None).validate().is_err());
+        assert!(limits(Some(101), None).validate().is_err());
+        assert!(limits(None, Some(MIN_MEMORY_MB - 1)).validate().is_err());
+    }
+
+    #[test]
+    fn job_object_limits() {
+        assert_eq!(limits(Some(25), None).job_cpu_rate(), Some(2500));
+        assert_eq!(limits(Some(100), None).job_cpu_rate(), Some(10_000));
+        assert_eq!(limits(None, Some(512)).job_cpu_rate(), None);
+        assert_eq!(
+            limits(None, Some(512)).memory_bytes(),
+            Some(512 * 1024 * 1024)
+        );
+        // Released processes get all limits lifted.
+        let lifted = ThrottleLimits::default();
+        assert_eq!((lifted.job_cpu_rate(), lifted.memory_bytes()), (None, None));
+    }
+
+    #[test]
+    fn cgroup_limits() {
+        // 25 % of 8 cores is two full periods.
+        assert_eq!(limits(Some(25), None).cgroup_cpu_max(8), "200000 100000");
+        assert_eq!(limits(Some(100), None).cgroup_cpu_max(1), "100000 100000");
+        // Never below 1 ms per period, which the kernel rejects.
+        assert_eq!(limits(Some(1), None).cgroup_cpu_max(1), "1000 100000");
+        assert_eq!(limits(None, Some(64)).cgroup_cpu_max(8), "max 100000");
+        assert_eq!(limits(None, Some(64)).cgroup_memory_high(), "67108864");
+        assert_eq!(limits(Some(50), None).cgroup_memory_high(), "max");
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod cgroup_tests {
+    use super::*;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    const PID: u32 = 4242;
+
+    /// A fake cgroup root plus a fake `/proc` in which `PID` runs in `user.slice/app`.
+    struct Fixture {
+        _dir: TempDir,
+        root: PathBuf,
+        throttler: CgroupThrottler,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            let root = dir.path().join("cgroup");
+            let proc_root = dir.path().join("proc");
+            fs::create_dir_all(root.join("user.slice/app")).unwrap();
+            fs::create_dir_all(proc_root.join(PID.to_string())).unwrap();
+            fs::write(
+                proc_root.join(PID.to_string()).join("cgroup"),
+                "0::/user.slice/app\n",
+            )
+            .unwrap();
+
+            let mut throttler = CgroupThrottler::new(&root);
+            throttler.proc_root = proc_root;
+            Self {
+                _dir: dir,
+                root,
+                throttler,
+            }
+        }
+
+        fn leaf(&self) -> PathBuf {
+            self.root
+                .join(CGROUP_GROUP_NAME)
+                .join(format!("pid-{}", PID))
+        }
+
+        fn exit(&self) {
+            fs::remove_dir_all(self.throttler.proc_root.join(PID.to_string())).unwrap();
+        }
+    }
+
+    fn read(path: impl AsRef<Path>) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    fn cores() -> u64 {
+        std::thread::available_parallelism()
+            .map(|n| n.get() as u64)
+            .unwrap()
+    }
+
+    #[test]
+    fn throttle_writes_limits_and_moves_the_process() {
+        let mut fixture = Fixture::new();
+        let limits = ThrottleLimits {
+            cpu_percent: Some(50),
+            memory_mb: Some(512),
+        };
+        fixture.throttler.throttle(PID, &limits).unwrap();
+
+        let leaf = fixture.leaf();
+        assert_eq!(
+            read(leaf.join("cpu.max")),
+            format!("{} 100000", 50_000 * cores())
+        );
+        assert_eq!(read(leaf.join("memory.high")), (512u64 << 20).to_string());
+        assert_eq!(read(leaf.join("cgroup.procs")), PID.to_string());
+        assert_eq!(
+            read(
+                fixture
+                    .root
+                    .join(CGROUP_GROUP_NAME)
+                    .join("cgroup.subtree_control")
+            ),
+            "+cpu +memory"
+        );
+        assert_eq!(fixture.throttler.throttled_pids(), vec![PID]);
+    }
+
+    #[test]
+    fn throttle_again_updates_the_limits() {
+        let mut fixture = Fixture::new();
+        let limits = ThrottleLimits {
+            cpu_percent: Some(1),
+            memory_mb: None,
+        };
+        fixture.throttler.throttle(PID, &limits).unwrap();
+        // 1 % of a small machine is below the kernel minimum.
+        assert_eq!(
+            read(fixture.leaf().join("cpu.max")),
+            format!("{} 100000", (1000 * cores()).max(1000))
+        );
+        assert_eq!(read(fixture.leaf().join("memory.high")), "max");
+
+        let limits = ThrottleLimits {
+            cpu_percent: None,
+            memory_mb: Some(64),
+        };
+        fixture.throttler.throttle(PID, &limits).unwrap();
+        assert_eq!(read(fixture.leaf().join("cpu.max")), "max 100000");
+        assert_eq!(
+            read(fixture.leaf().join("memory.high")),
+            (64u64 << 20).to_string()
+        );
+    }
+
+    #[test]
+    fn release_moves_the_process_back() {
+        let mut fixture = Fixture::new();
+        let limits = ThrottleLimits {
+            cpu_percent: Some(10),
+            memory_mb: None,
+        };
+        fixture.throttler.throttle(PID, &limits).unwrap();
+        fixture.throttler.release(PID).unwrap();
+
+        assert_eq!(
+            read(fixture.root.join("user.slice/app/cgroup.procs")),
+            PID.to_string()
+        );
+        assert!(fixture.throttler.throttled_pids().is_empty());
+        assert!(fixture.throttler.release(PID).is_err());
+    }
+
+    #[test]
+    fn release_of_an_exited_process() {
+        let mut fixture = Fixture::new();
+        let limits = ThrottleLimits {
+            cpu_percent: Some(10),
+            memory_mb: None,
+        };
+        fixture.throttler.throttle(PID, &limits).unwrap();
+        fixture.exit();
+
+        let result = fixture.throttler.release_all();
+        assert_eq!(result.succeeded, vec![PID]);
+        assert!(result.failed.is_empty());
+        assert!(!fixture.root.join("user.slice/app/cgroup.procs").exists());
+    }
+
+    #[test]
+    fn throttle_unknown_process() {
+        let mut fixture = Fixture::new();
+        fixture.exit();
+        let limits = ThrottleLimits {
+            cpu_percent: Some(10),
+            memory_mb: None,
+        };
+        assert!(fixture.throttler.throttle(PID, &limits).is_err());
+        assert!(!fixture.leaf().exists());
+    }
+
+    #[test]
+    fn state_uses_the_given_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = ThrottleState::with_root(dir.path());
+        // Invalid limits are rejected before anything is written.
+        assert!(state.throttle(PID, &ThrottleLimits::default()).is_err());
+        // Not running in the real `/proc`, and nothing is created below the root.
+        let limits = ThrottleLimits {
+            cpu_percent: Some(10),
+            memory_mb: None,
+        };
+        assert!(state.throttle(u32::MAX, &limits).is_err());
+        assert!(!dir.path().join(CGROUP_GROUP_NAME).exists());
+    }
+}
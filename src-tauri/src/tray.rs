@@ -0,0 +1,499 @@
+use crate::profiles::{self, ProfileState};
+use crate::session::{SessionEngine, SessionFeature, SessionMessage, SessionStatus};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::image::Image;
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{App, AppHandle, Emitter, Listener, Manager, Wry};
+
+const APP_NAME: &str = "CS2ToolsByJonny";
+
+/// Menu ID prefix of the entries that switch to a named profile.
+const USE_PROFILE_ID: &str = "use-profile:";
+const NO_PROFILE_ID: &str = "use-no-profile";
+
+/// Windows cuts tooltips off after 127 characters.
+const MAX_TOOLTIP_CHARS: usize = 127;
+
+/// What the tray icon shows, most important last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IconState {
+    Idle,
+    Active,
+    Snoozed,
+    Error,
+}
+
+/// The app icon, and the same icon with a colored dot for the other states.
+struct TrayIcons {
+    idle: Image<'static>,
+    active: Image<'static>,
+    snoozed: Image<'static>,
+    error: Image<'static>,
+}
+
+impl TrayIcons {
+    fn new(icon: &Image<'_>) -> Self {
+        Self {
+            idle: Image::new_owned(icon.rgba().to_vec(), icon.width(), icon.height()),
+            active: with_badge(icon, [0x2e, 0xcc, 0x71]),
+            snoozed: with_badge(icon, [0xf3, 0x9c, 0x12]),
+            error: with_badge(icon, [0xe7, 0x4c, 0x3c]),
+        }
+    }
+
+    fn get(&self, state: IconState) -> &Image<'static> {
+        match state {
+            IconState::Idle => &self.idle,
+            IconState::Active => &self.active,
+            IconState::Snoozed => &self.snoozed,
+            IconState::Error => &self.error,
+        }
+    }
+}
+
+/// Draws a dot with a dark outline into the bottom right corner.
+fn with_badge(icon: &Image<'_>, color: [u8; 3]) -> Image<'static> {
+    let (width, height) = (icon.width(), icon.height());
+    let mut rgba = icon.rgba().to_vec();
+    let radius = width.min(height) as f32 * 0.3;
+    let outline = (width.min(height) as f32 / 16.0).max(1.0);
+    let (cx, cy) = (width as f32 - radius, height as f32 - radius);
+    for y in 0..height {
+        for x in 0..width {
+            let distance = ((x as f32 + 0.5 - cx).powi(2) + (y as f32 + 0.5 - cy).powi(2)).sqrt();
+            if distance > radius {
+                continue;
+            }
+            let pixel = if distance > radius - outline {
+                [0x20, 0x20, 0x20, 0xff]
+            } else {
+                [color[0], color[1], color[2], 0xff]
+            };
+            let offset = ((y * width + x) * 4) as usize;
+            rgba[offset..offset + 4].copy_from_slice(&pixel);
+        }
+    }
+    Image::new_owned(rgba, width, height)
+}
+
+/// Menu entries that follow the session state.
+pub struct TrayMenu {
+    status: MenuItem<Wry>,
+    profile: MenuItem<Wry>,
+    features: Vec<(SessionFeature, CheckMenuItem<Wry>)>,
+    resume: MenuItem<Wry>,
+    revert: MenuItem<Wry>,
+    profiles: Submenu<Wry>,
+    profile_items: Mutex<Vec<ProfileItem>>,
+    icons: TrayIcons,
+    /// Set once the icon was changed, so it is only replaced when the state changes.
+    shown: Mutex<Option<IconState>>,
+}
+
+/// Entry of the "Default profile" submenu and the profile it was built for.
+struct ProfileItem {
+    /// `None` for "None".
+    id: Option<String>,
+    name: String,
+    item: CheckMenuItem<Wry>,
+}
+
+fn feature_id(feature: SessionFeature) -> String {
+    format!("feature-{:?}", feature)
+}
+
+/// Builds the tray icon and keeps its menu in sync with `game-session-status`.
+pub fn create(app: &App) -> tauri::Result<()> {
+    let status = MenuItem::with_id(app, "status", "No game running", false, None::<&str>)?;
+    let profile = MenuItem::with_id(app, "profile", "Profile: none", false, None::<&str>)?;
+    let features = SessionFeature::ALL
+        .iter()
+        .map(|&feature| {
+            CheckMenuItem::with_id(
+                app,
+                feature_id(feature),
+                feature.label(),
+                true,
+                true,
+                None::<&str>,
+            )
+            .map(|item| (feature, item))
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let snooze = Submenu::with_items(
+        app,
+        "Snooze automation",
+        true,
+        &[
+            &MenuItem::with_id(app, "snooze-30", "30 minutes", true, None::<&str>)?,
+            &MenuItem::with_id(app, "snooze-60", "60 minutes", true, None::<&str>)?,
+            &MenuItem::with_id(
+                app,
+                "snooze-launch",
+                "Until the next game starts",
+                true,
+                None::<&str>,
+            )?,
+        ],
+    )?;
+    let profiles = Submenu::with_items(app, "Default profile", true, &[])?;
+    let resume = MenuItem::with_id(app, "resume", "Resume automation", false, None::<&str>)?;
+    let revert = MenuItem::with_id(app, "revert", "Revert now", false, None::<&str>)?;
+    let show = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+
+    let menu = Menu::with_items(
+        app,
+        &[&status, &profile, &PredefinedMenuItem::separator(app)?],
+    )?;
+    for (_, item) in &features {
+        menu.append(item)?;
+    }
+    menu.append_items(&[
+        &PredefinedMenuItem::separator(app)?,
+        &profiles,
+        &snooze,
+        &resume,
+        &revert,
+        &PredefinedMenuItem::separator(app)?,
+        &show,
+        &quit,
+    ])?;
+
+    let icon = app.default_window_icon().unwrap().clone();
+    let icons = TrayIcons::new(&icon);
+    TrayIconBuilder::with_id("tray")
+        .icon(icon)
+        .tooltip(APP_NAME)
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| on_menu_event(app, event.id().as_ref()))
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_main_window(tray.app_handle());
+            }
+        })
+        .build(app)?;
+
+    app.manage(TrayMenu {
+        status,
+        profile,
+        features,
+        resume,
+        revert,
+        profiles,
+        profile_items: Mutex::new(Vec::new()),
+        icons,
+        shown: Mutex::new(None),
+    });
+    let handle = app.handle().clone();
+    app.listen_any("game-session-status", move |_| refresh(&handle));
+    refresh(app.handle());
+    Ok(())
+}
+
+fn on_menu_event(app: &AppHandle, id: &str) {
+    let send = |message| {
+        if let Some(session) = app.try_state::<SessionEngine>() {
+            session.send(message);
+        }
+    };
+    match id {
+        "quit" => app.exit(0),
+        "show" => show_main_window(app),
+        "snooze-30" => send(SessionMessage::Snooze(Some(Duration::from_secs(30 * 60)))),
+        "snooze-60" => send(SessionMessage::Snooze(Some(Duration::from_secs(60 * 60)))),
+        "snooze-launch" => send(SessionMessage::Snooze(None)),
+        "resume" => send(SessionMessage::Unsnooze),
+        "revert" => send(SessionMessage::RevertNow),
+        NO_PROFILE_ID => switch_profile(app, None),
+        _ if id.starts_with(USE_PROFILE_ID) => {
+            switch_profile(app, Some(id[USE_PROFILE_ID.len()..].to_string()))
+        }
+        _ => {
+            let Some(menu) = app.try_state::<TrayMenu>() else {
+                return;
+            };
+            // The check mark toggles on click, so it already shows the new state.
+            if let Some((feature, item)) = menu.features.iter().find(|(f, _)| feature_id(*f) == id)
+            {
+                send(SessionMessage::SetFeature {
+                    feature: *feature,
+                    enabled: item.is_checked().unwrap_or(true),
+                });
+            }
+        }
+    }
+}
+
+fn switch_profile(app: &AppHandle, id: Option<String>) {
+    if let Err(e) = profiles::activate(app, id) {
+        let _ = app.emit("log-error", format!("Failed to switch profile: {}", e));
+    }
+}
+
+/// Rebuilds the profile entries if profiles were added, removed or renamed, and checks
+/// the active one.
+fn refresh_profiles(app: &AppHandle, menu: &TrayMenu) -> tauri::Result<()> {
+    let Some(state) = app.try_state::<ProfileState>() else {
+        return Ok(());
+    };
+    let wanted: Vec<(Option<String>, String)> = std::iter::once((None, "None".to_string()))
+        .chain(state.list().into_iter().map(|p| (Some(p.id), p.name)))
+        .collect();
+    let Ok(mut items) = menu.profile_items.lock() else {
+        return Ok(());
+    };
+    let current: Vec<(Option<String>, String)> = items
+        .iter()
+        .map(|entry| (entry.id.clone(), entry.name.clone()))
+        .collect();
+    if current != wanted {
+        for entry in items.drain(..) {
+            menu.profiles.remove(&entry.item)?;
+        }
+        for (id, name) in wanted {
+            let menu_id = match &id {
+                Some(id) => format!("{}{}", USE_PROFILE_ID, id),
+                None => NO_PROFILE_ID.to_string(),
+            };
+            let item = CheckMenuItem::with_id(app, menu_id, &name, true, false, None::<&str>)?;
+            menu.profiles.append(&item)?;
+            items.push(ProfileItem { id, name, item });
+        }
+    }
+    let active = state.active_id();
+    for entry in items.iter() {
+        entry.item.set_checked(entry.id == active)?;
+    }
+    Ok(())
+}
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn refresh(app: &AppHandle) {
+    let (Some(menu), Some(session)) = (
+        app.try_state::<TrayMenu>(),
+        app.try_state::<SessionEngine>(),
+    ) else {
+        return;
+    };
+    let Ok(status) = session.status() else {
+        return;
+    };
+    let _ = menu.status.set_text(status_text(&status));
+    let _ = menu.profile.set_text(profile_text(app, &status));
+    for (feature, item) in &menu.features {
+        let _ = item.set_checked(!status.disabled_features.contains(feature));
+    }
+    let _ = refresh_profiles(app, &menu);
+    let _ = menu.resume.set_enabled(status.snooze.is_some());
+    let _ = menu.revert.set_enabled(!status.active_games.is_empty());
+
+    let Some(tray) = app.tray_by_id("tray") else {
+        return;
+    };
+    let state = icon_state(&status);
+    if let Ok(mut shown) = menu.shown.lock() {
+        if *shown != Some(state) {
+            let _ = tray.set_icon(Some(menu.icons.get(state).clone()));
+            *shown = Some(state);
+        }
+    }
+    let _ = tray.set_tooltip(Some(tooltip(&status)));
+}
+
+/// The profile of the running game, otherwise the default profile as it is now, even if
+/// the session has not published a status since it was switched.
+fn profile_text(app: &AppHandle, status: &SessionStatus) -> String {
+    if !status.active_games.is_empty() {
+        return format!(
+            "Profile: {}",
+            status.active_profile.as_deref().unwrap_or("none")
+        );
+    }
+    let name = app.try_state::<ProfileState>().and_then(|profiles| {
+        profiles
+            .active_id()
+            .and_then(|id| profiles.get(&id))
+            .map(|profile| profile.name)
+    });
+    format!("Profile: {}", name.as_deref().unwrap_or("none"))
+}
+
+fn icon_state(status: &SessionStatus) -> IconState {
+    if status.last_error.is_some() {
+        IconState::Error
+    } else if status.snooze.is_some() {
+        IconState::Snoozed
+    } else if !status.active_games.is_empty() {
+        IconState::Active
+    } else {
+        IconState::Idle
+    }
+}
+
+/// E.g. "Counter-Strike 2 running • High Performance • cores 2–7 • vibrance 80%".
+fn tooltip(status: &SessionStatus) -> String {
+    let mut parts = Vec::new();
+    if status.active_games.is_empty() {
+        parts.push(format!("{} • no game running", APP_NAME));
+    } else {
+        let names: Vec<&str> = status
+            .active_games
+            .iter()
+            .map(|game| game.name.as_str())
+            .collect();
+        parts.push(format!("{} running", names.join(", ")));
+    }
+    if status.snooze.is_some() {
+        parts.push("automation snoozed".to_string());
+    }
+    if let Some(plan) = &status.power_plan {
+        parts.push(plan.clone());
+    }
+    if !status.affinity_cores.is_empty() {
+        parts.push(format!("cores {}", core_ranges(&status.affinity_cores)));
+    }
+    if let Some(level) = status.vibrance {
+        parts.push(format!("vibrance {}%", level));
+    }
+    if let Some(error) = &status.last_error {
+        parts.push(format!("error: {}", error));
+    }
+    let text = parts.join(" • ");
+    if text.chars().count() <= MAX_TOOLTIP_CHARS {
+        return text;
+    }
+    let mut text: String = text.chars().take(MAX_TOOLTIP_CHARS - 1).collect();
+    text.push('…');
+    text
+}
+
+/// "0, 2, 4–7" for cores 0, 2, 4, 5, 6 and 7.
+fn core_ranges(cores: &[u32]) -> String {
+    let mut cores = cores.to_vec();
+    cores.sort_unstable();
+    cores.dedup();
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for core in cores {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == core => *end = core,
+            _ => ranges.push((core, core)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}–{}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn status_text(status: &SessionStatus) -> String {
+    if let Some(snooze) = &status.snooze {
+        return match snooze.ends_at {
+            Some(ends_at) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_millis() as u64)
+                    .unwrap_or(0);
+                let minutes = ends_at.saturating_sub(now).div_ceil(60_000);
+                format!("Automation snoozed, {} min left", minutes)
+            }
+            None => "Automation snoozed until the next game starts".to_string(),
+        };
+    }
+    if status.active_games.is_empty() {
+        return "No game running".to_string();
+    }
+    let names: Vec<&str> = status
+        .active_games
+        .iter()
+        .map(|game| game.name.as_str())
+        .collect();
+    format!("Running: {}", names.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{ActiveGame, SnoozeStatus};
+
+    fn running() -> SessionStatus {
+        SessionStatus {
+            active_games: vec![ActiveGame {
+                game_id: "cs2".to_string(),
+                name: "Counter-Strike 2".to_string(),
+                pids: vec![1234],
+                exe_path: None,
+                started_at: 0,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn joins_consecutive_cores() {
+        assert_eq!(core_ranges(&[2, 3, 4, 5, 6, 7]), "2–7");
+        assert_eq!(core_ranges(&[7, 0, 5, 2, 4, 6, 4]), "0, 2, 4–7");
+        assert_eq!(core_ranges(&[3]), "3");
+        assert_eq!(core_ranges(&[]), "");
+    }
+
+    #[test]
+    fn describes_the_session() {
+        assert_eq!(
+            tooltip(&SessionStatus::default()),
+            format!("{} • no game running", APP_NAME)
+        );
+
+        let mut status = running();
+        status.power_plan = Some("High Performance".to_string());
+        status.affinity_cores = vec![2, 3, 4, 5, 6, 7];
+        status.vibrance = Some(80);
+        assert_eq!(
+            tooltip(&status),
+            "Counter-Strike 2 running • High Performance • cores 2–7 • vibrance 80%"
+        );
+    }
+
+    #[test]
+    fn truncates_long_tooltips() {
+        let mut status = running();
+        status.last_error = Some("é".repeat(200));
+        let text = tooltip(&status);
+        assert_eq!(text.chars().count(), MAX_TOOLTIP_CHARS);
+        assert!(text.ends_with('…'));
+    }
+
+    #[test]
+    fn errors_win_over_snooze_and_snooze_over_activity() {
+        assert_eq!(icon_state(&SessionStatus::default()), IconState::Idle);
+
+        let mut status = running();
+        assert_eq!(icon_state(&status), IconState::Active);
+        status.snooze = Some(SnoozeStatus { ends_at: None });
+        assert_eq!(icon_state(&status), IconState::Snoozed);
+        status.last_error = Some("NvAPI unavailable".to_string());
+        assert_eq!(icon_state(&status), IconState::Error);
+    }
+}
@@ -0,0 +1,37 @@
+use serde::Serialize;
+use std::fmt::Debug;
+
+/// Result of reading a value back after setting it.
+///
+/// The OS call succeeding does not mean the value stuck: group policy can override the
+/// power plan, the driver can clamp the vibrance, and a process can be limited to fewer
+/// cores than requested.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum Verification<T> {
+    Verified,
+    Mismatch { expected: T, actual: T },
+}
+
+impl<T: PartialEq> Verification<T> {
+    pub fn compare(expected: T, actual: T) -> Self {
+        if expected == actual {
+            Self::Verified
+        } else {
+            Self::Mismatch { expected, actual }
+        }
+    }
+}
+
+impl<T: Debug> Verification<T> {
+    /// Turns a mismatch into an error for callers that only care whether it worked.
+    pub fn into_result(self, what: &str) -> Result<(), String> {
+        match self {
+            Self::Verified => Ok(()),
+            Self::Mismatch { expected, actual } => Err(format!(
+                "{} is {:?} instead of {:?}",
+                what, actual, expected
+            )),
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::verification::Verification;
 use anyhow::{anyhow, Result};
 use nvapi::{sys, PhysicalGpu};
 use std::mem;
@@ -39,6 +40,28 @@ impl NV_DISPLAY_DVC_INFO_EX {
             default_level: 0,
         }
     }
+
+    /// Maps 0-100 to the driver's min-max range.
+    fn level_for_percent(&self, percent: u32) -> u32 {
+        // * HINWEIS: Lineare Interpolation
+        // Die API verwendet interne Werte (z.B. 0-63), wir wollen Prozent (0-100).
+        // Formel: min + (prozent * (max - min) / 100)
+        // saturating_sub guards against wrap-around if NvAPI returns min > max.
+        // saturating_mul guards against overflow if NvAPI returns an unexpectedly large range.
+        let range = self.max_level.saturating_sub(self.min_level);
+        self.min_level
+            .saturating_add(percent.saturating_mul(range) / 100)
+    }
+
+    /// The current level in percent, rounded.
+    fn current_percent(&self) -> u32 {
+        let range = self.max_level.saturating_sub(self.min_level);
+        if range == 0 {
+            return 0;
+        }
+        let offset = self.current_level.saturating_sub(self.min_level);
+        (offset.saturating_mul(100) + range / 2) / range
+    }
 }
 
 // Function pointer types for the raw NvAPI calls
@@ -140,66 +163,24 @@ impl NvidiaController {
             return Err(anyhow!("Vibrance level must be between 0 and 100"));
         }
 
-        // * HINWEIS: Unsafe Block
-        // Hier interagieren wir direkt mit C-Pointern und rohem Speicher.
-        // Wir müssen manuell sicherstellen, dass Pointer gültig sind und Speicher korrekt initialisiert ist.
-        unsafe {
-            // Load NvAPI_GetAssociatedNvidiaDisplayHandle
-            // Wir fragen die NvAPI nach der Adresse der Funktion anhand ihrer ID.
-            let get_handle_res =
-                sys::nvapi::nvapi_QueryInterface(NVAPI_GET_ASSOCIATED_NVIDIA_DISPLAY_HANDLE_ID);
-            if get_handle_res.is_err() {
-                return Err(anyhow!("NvAPI_GetAssociatedNvidiaDisplayHandle not found"));
-            }
-            let get_handle_addr = get_handle_res.unwrap();
-
-            // `mem::transmute`: Der gefährlichste Cast in Rust.
-            // Er interpretiert die Bits einer Speicheradresse einfach als einen anderen Typ (hier Funktionszeiger).
-            // Wenn die Signatur nicht stimmt -> Undefined Behavior (Absturz).
-            let get_handle: NvAPIGetAssociatedNvidiaDisplayHandle = mem::transmute(get_handle_addr);
-
-            // Konvertierung von Rust String (`&str`) zu C-String (`CString`).
-            // Rust Strings sind nicht null-terminiert, C Strings schon.
-            let c_name = std::ffi::CString::new(display_name)?;
-
-            // Log output for debugging
-            let _ = app.emit(
-                "log-info",
-                format!("Attempting to get handle for display: '{}'", display_name),
-            );
+        // Log output for debugging
+        let _ = app.emit(
+            "log-info",
+            format!("Attempting to get handle for display: '{}'", display_name),
+        );
 
-            // `mem::zeroed()`: Erstellt ein leeres Handle-Objekt, das von der C-Funktion gefüllt wird.
-            let mut handle: sys::handles::NvDisplayHandle = mem::zeroed();
-
-            // Aufruf der C-Funktion
-            let status = get_handle(c_name.as_ptr(), &mut handle);
-
-            if status != sys::status::NVAPI_OK {
-                let _ = app.emit(
-                    "log-error",
-                    format!(
-                        "NvAPI Error getting handle for '{}': {:?}",
-                        display_name, status
-                    ),
-                );
-                return Err(anyhow!(
-                    "Failed to get handle for display {}: {:?}",
-                    display_name,
-                    status
-                ));
-            }
-
-            let _ = app.emit(
-                "log-info",
-                format!(
-                    "Successfully got handle for '{}'. Setting DVC now...",
-                    display_name
-                ),
-            );
-            self.set_dvc_for_handle(app, handle, level)?;
-        }
+        let handle = display_handle(display_name).inspect_err(|e| {
+            let _ = app.emit("log-error", format!("NvAPI Error: {}", e));
+        })?;
 
-        Ok(())
+        let _ = app.emit(
+            "log-info",
+            format!(
+                "Successfully got handle for '{}'. Setting DVC now...",
+                display_name
+            ),
+        );
+        self.set_dvc_for_handle(app, handle, level)
     }
 
     /// Interne Hilfsfunktion zum Setzen der Vibrance für ein spezifisches Handle.
@@ -209,15 +190,23 @@ impl NvidiaController {
         handle: sys::handles::NvDisplayHandle,
         level_percent: u32,
     ) -> Result<()> {
-        unsafe {
-            // Load NvAPI_Disp_GetDVCInfoEx
-            let get_dvc_res = sys::nvapi::nvapi_QueryInterface(NVAPI_DISP_GET_DVC_INFO_EX_ID);
-            if get_dvc_res.is_err() {
-                return Err(anyhow!("NvAPI_Disp_GetDVCInfoEx not found"));
-            }
-            let get_dvc_addr = get_dvc_res.unwrap();
-            let get_dvc: NvAPIDispGetDVCInfoEx = mem::transmute(get_dvc_addr);
+        // Get current info to find min/max
+        let mut dvc_info = dvc_info(handle)?;
+
+        let _ = app.emit(
+            "log-info",
+            format!(
+                "DVC Info: min={}, max={}, current={}, default={}",
+                dvc_info.min_level,
+                dvc_info.max_level,
+                dvc_info.current_level,
+                dvc_info.default_level
+            ),
+        );
+
+        dvc_info.current_level = dvc_info.level_for_percent(level_percent);
 
+        unsafe {
             // Load NvAPI_Disp_SetDVCLevelEx
             let set_dvc_res = sys::nvapi::nvapi_QueryInterface(NVAPI_DISP_SET_DVC_LEVEL_EX_ID);
             if set_dvc_res.is_err() {
@@ -226,41 +215,6 @@ impl NvidiaController {
             let set_dvc_addr = set_dvc_res.unwrap();
             let set_dvc: NvAPIDispSetDVCLevelEx = mem::transmute(set_dvc_addr);
 
-            // Prepare struct
-            let mut dvc_info = NV_DISPLAY_DVC_INFO_EX::new();
-
-            // Get current info to find min/max
-            // outputId is usually 0 for the default output of the handle
-            let status = get_dvc(handle, 0, &mut dvc_info);
-            if status != sys::status::NVAPI_OK {
-                return Err(anyhow!("Failed to get DVC info: {:?}", status));
-            }
-
-            let _ = app.emit(
-                "log-info",
-                format!(
-                    "DVC Info: min={}, max={}, current={}, default={}",
-                    dvc_info.min_level,
-                    dvc_info.max_level,
-                    dvc_info.current_level,
-                    dvc_info.default_level
-                ),
-            );
-
-            // Calculate new level
-            // Map 0-100 to min-max
-            // * HINWEIS: Lineare Interpolation
-            // Die API verwendet interne Werte (z.B. 0-63), wir wollen Prozent (0-100).
-            // Formel: min + (prozent * (max - min) / 100)
-            // saturating_sub guards against wrap-around if NvAPI returns min > max.
-            // saturating_mul guards against overflow if NvAPI returns an unexpectedly large range.
-            let range = dvc_info.max_level.saturating_sub(dvc_info.min_level);
-            let new_val = dvc_info
-                .min_level
-                .saturating_add(level_percent.saturating_mul(range) / 100);
-
-            dvc_info.current_level = new_val;
-
             // Set new level
             let status = set_dvc(handle, 0, &mut dvc_info);
             if status != sys::status::NVAPI_OK {
@@ -270,6 +224,76 @@ impl NvidiaController {
         Ok(())
     }
 }
+
+/// Looks up the NvAPI handle of a display by its GDI name.
+fn display_handle(display_name: &str) -> Result<sys::handles::NvDisplayHandle> {
+    // * HINWEIS: Unsafe Block
+    // Hier interagieren wir direkt mit C-Pointern und rohem Speicher.
+    // Wir müssen manuell sicherstellen, dass Pointer gültig sind und Speicher korrekt initialisiert ist.
+    unsafe {
+        // Load NvAPI_GetAssociatedNvidiaDisplayHandle
+        // Wir fragen die NvAPI nach der Adresse der Funktion anhand ihrer ID.
+        let get_handle_addr =
+            sys::nvapi::nvapi_QueryInterface(NVAPI_GET_ASSOCIATED_NVIDIA_DISPLAY_HANDLE_ID)
+                .map_err(|_| anyhow!("NvAPI_GetAssociatedNvidiaDisplayHandle not found"))?;
+
+        // `mem::transmute`: Der gefährlichste Cast in Rust.
+        // Er interpretiert die Bits einer Speicheradresse einfach als einen anderen Typ (hier Funktionszeiger).
+        // Wenn die Signatur nicht stimmt -> Undefined Behavior (Absturz).
+        let get_handle: NvAPIGetAssociatedNvidiaDisplayHandle = mem::transmute(get_handle_addr);
+
+        // Konvertierung von Rust String (`&str`) zu C-String (`CString`).
+        // Rust Strings sind nicht null-terminiert, C Strings schon.
+        let c_name = std::ffi::CString::new(display_name)?;
+
+        // `mem::zeroed()`: Erstellt ein leeres Handle-Objekt, das von der C-Funktion gefüllt wird.
+        let mut handle: sys::handles::NvDisplayHandle = mem::zeroed();
+
+        // Aufruf der C-Funktion
+        let status = get_handle(c_name.as_ptr(), &mut handle);
+        if status != sys::status::NVAPI_OK {
+            return Err(anyhow!(
+                "Failed to get handle for display {}: {:?}",
+                display_name,
+                status
+            ));
+        }
+        Ok(handle)
+    }
+}
+
+/// Reads level and range of a display's digital vibrance.
+fn dvc_info(handle: sys::handles::NvDisplayHandle) -> Result<NV_DISPLAY_DVC_INFO_EX> {
+    unsafe {
+        // Load NvAPI_Disp_GetDVCInfoEx
+        let get_dvc_addr = sys::nvapi::nvapi_QueryInterface(NVAPI_DISP_GET_DVC_INFO_EX_ID)
+            .map_err(|_| anyhow!("NvAPI_Disp_GetDVCInfoEx not found"))?;
+        let get_dvc: NvAPIDispGetDVCInfoEx = mem::transmute(get_dvc_addr);
+
+        let mut dvc_info = NV_DISPLAY_DVC_INFO_EX::new();
+        // outputId is usually 0 for the default output of the handle
+        let status = get_dvc(handle, 0, &mut dvc_info);
+        if status != sys::status::NVAPI_OK {
+            return Err(anyhow!("Failed to get DVC info: {:?}", status));
+        }
+        Ok(dvc_info)
+    }
+}
+
+/// Compares a display's vibrance with `level` (0-100).
+///
+/// Returns `None` if the display shows what `apply_vibrance` would set for `level`,
+/// otherwise the level it actually shows. The comparison uses the driver's own units,
+/// because small ranges (0-63) cannot map every percentage back exactly. Logs nothing,
+/// so it can be polled.
+pub fn check_vibrance(display_name: &str, level: u32) -> Result<Option<u32>, String> {
+    nvapi::initialize().map_err(|e| format!("Failed to initialize NvAPI: {}", e))?;
+    let info = display_handle(display_name)
+        .and_then(dvc_info)
+        .map_err(|e| e.to_string())?;
+    Ok((info.current_level != info.level_for_percent(level)).then(|| info.current_percent()))
+}
+
 use winapi::um::winuser::{
     GetForegroundWindow, GetMonitorInfoA, MonitorFromWindow, MONITORINFOEXA,
     MONITOR_DEFAULTTONEAREST,
@@ -313,12 +337,32 @@ pub fn apply_vibrance_to_focused_display(app: AppHandle, level: u32) -> Result<S
     }
 }
 
+/// Sets the vibrance of a display and reads it back, since the driver may clamp it.
 #[tauri::command]
-pub fn apply_vibrance(app: AppHandle, display_name: String, level: u32) -> Result<(), String> {
+pub fn apply_vibrance(
+    app: AppHandle,
+    display_name: String,
+    level: u32,
+) -> Result<Verification<u32>, String> {
     // NvidiaController::new() initializes NvAPI and checks GPU presence in one step.
     let controller = NvidiaController::new(&app).map_err(|e| e.to_string())?;
 
     controller
         .set_vibrance_for_display(&app, &display_name, level)
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+    let verification = match check_vibrance(&display_name, level)? {
+        None => Verification::Verified,
+        Some(actual) => Verification::Mismatch {
+            expected: level,
+            actual,
+        },
+    };
+    if let Verification::Mismatch { actual, .. } = &verification {
+        let _ = app.emit(
+            "log-warn",
+            format!("Vibrance on {} reads back as {}", display_name, actual),
+        );
+    }
+    Ok(verification)
 }
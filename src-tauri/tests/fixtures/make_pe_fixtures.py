@@ -0,0 +1,126 @@
+"""Generates the PE files used by the `pe_version` tests.
+
+Run from this directory: `python make_pe_fixtures.py`. The output is checked in, so this
+only has to run again if a fixture changes.
+"""
+
+import struct
+
+SECTION_VA = 0x1000
+SECTION_RAW = 0x200
+
+
+def align4(data: bytes) -> bytes:
+    return data + b"\0" * (-len(data) % 4)
+
+
+def utf16z(text: str) -> bytes:
+    return (text + "\0").encode("utf-16-le")
+
+
+def node(key: str, value: bytes, is_text: bool, children: bytes = b"") -> bytes:
+    """One VS_VERSIONINFO node; `wValueLength` counts characters for text values."""
+    value_length = len(value) // 2 if is_text else len(value)
+    # Padding is relative to the start of the node, so the header is counted in.
+    data = b"\0" * 6 + utf16z(key)
+    data = align4(data) + value
+    data = align4(data) + children
+    header = struct.pack("<HHH", len(data), value_length, 1 if is_text else 0)
+    return align4(header + data[6:])
+
+
+def version_info() -> bytes:
+    fixed = struct.pack(
+        "<13I",
+        0xFEEF04BD,  # signature
+        0x00010000,  # struct version
+        (1 << 16) | 2,  # file version 1.2.3.4
+        (3 << 16) | 4,
+        (5 << 16) | 6,  # product version 5.6.7.8
+        (7 << 16) | 8,
+        0x3F,
+        0,
+        0x40004,  # VOS_NT_WINDOWS32
+        1,  # VFT_APP
+        0,
+        0,
+        0,
+    )
+    strings = b"".join(
+        node(key, utf16z(value), True)
+        for key, value in [
+            ("CompanyName", "Example Corp"),
+            ("FileDescription", "Fixture Game"),
+            ("FileVersion", "1.2.3.4"),
+            ("ProductName", "Fixture"),
+        ]
+    )
+    table = node("040904b0", b"", True, strings)
+    string_file_info = node("StringFileInfo", b"", True, table)
+    translation = node("Translation", struct.pack("<HH", 0x0409, 1200), False)
+    var_file_info = node("VarFileInfo", b"", True, translation)
+    return node("VS_VERSION_INFO", fixed, False, string_file_info + var_file_info)
+
+
+def resource_section(data: bytes) -> bytes:
+    """Resource tree type 16 (RT_VERSION) -> name 1 -> language 0x409 -> data."""
+
+    def directory(entry_id: int, target: int) -> bytes:
+        return struct.pack("<IIHHHH", 0, 0, 0, 0, 0, 1) + struct.pack("<II", entry_id, target)
+
+    names = 0x18
+    languages = 0x30
+    entry = 0x48
+    payload = 0x58
+    tree = directory(16, 0x80000000 | names)
+    tree += directory(1, 0x80000000 | languages)
+    tree += directory(0x409, entry)
+    tree += struct.pack("<IIII", SECTION_VA + payload, len(data), 0, 0)
+    assert len(tree) == payload
+    return tree + data
+
+
+def pe(section: bytes, with_resources: bool) -> bytes:
+    dos = bytearray(64)
+    dos[0:2] = b"MZ"
+    struct.pack_into("<I", dos, 0x3C, 64)
+
+    optional = bytearray(240)
+    struct.pack_into("<H", optional, 0, 0x20B)
+    struct.pack_into("<I", optional, 108, 16)
+    if with_resources:
+        struct.pack_into("<II", optional, 112 + 2 * 8, SECTION_VA, len(section))
+    coff = b"PE\0\0" + struct.pack("<HHIIIHH", 0x8664, 1, 0, 0, 0, len(optional), 0x22)
+
+    name = b".rsrc\0\0\0" if with_resources else b".data\0\0\0"
+    header = struct.pack(
+        "<8sIIIIIIHHI",
+        name,
+        len(section),
+        SECTION_VA,
+        len(section),
+        SECTION_RAW,
+        0,
+        0,
+        0,
+        0,
+        0x40000040,
+    )
+    image = bytes(dos) + coff + bytes(optional) + header
+    image += b"\0" * (SECTION_RAW - len(image))
+    return image + section
+
+
+def main() -> None:
+    with_version = pe(resource_section(version_info()), True)
+    with open("version_info.exe", "wb") as f:
+        f.write(with_version)
+    with open("no_resources.exe", "wb") as f:
+        f.write(pe(b"\0" * 16, False))
+    # Cut inside the resource tree.
+    with open("truncated.exe", "wb") as f:
+        f.write(with_version[: SECTION_RAW + 0x20])
+
+
+if __name__ == "__main__":
+    main()
@@ -1,53 +1,41 @@
 import { listen } from "@tauri-apps/api/event";
-import { logInfo, logError } from "@lib/logger";
+import { logInfo, logWarn, logError } from "@lib/logger";
 import { runningProcesses } from "@lib/runningProcesses";
+import { gameMonitor, type GameEvent, type SessionReport } from "@lib/games";
 
-import { cpuManager } from "./features/cpuManager";
-import { vibranceManager } from "./features/vibranceManager";
-import { processKiller } from "./features/processKiller";
-import { powerPlanManager } from "./features/powerPlanManager";
-
+// Power plan, affinity, parking, vibrance, process killing and throttling are applied by
+// the backend session engine (`session.rs`), so they also work while the window is closed.
 export async function initCs2EventTracking() {
   const unlisteners: (() => void)[] = [];
 
   try {
-    const processListener = await listen("cs2process", async (event) => {
-      const status = event.payload as "started" | "stopped";
-      logInfo(`[CS2Process] cs2process state changed to: ${status}`);
-
-      try {
-        await runningProcesses.refresh();
-
-        if (status === "started") {
-          await powerPlanManager.onCs2Started();
-          await cpuManager.onCs2Started();
-          await processKiller.onCs2Started();
-        } else if (status === "stopped") {
-          await cpuManager.onCs2Stopped();
-          await powerPlanManager.onCs2Stopped();
-          await vibranceManager.onCs2Stopped();
-        }
-      } catch (error) {
-        logError("Error in handling CS2 process state:", error);
-      }
-    });
-    unlisteners.push(processListener);
-
-    const windowListener = await listen("cs2window", async (event) => {
-      const status = event.payload as "foreground" | "background";
-      logInfo(`[CS2Window] cs2window state changed to: ${status}`);
-
-      try {
-        if (status === "foreground") {
-          await vibranceManager.onForeground();
-        } else {
-          await vibranceManager.onBackground();
+    const status = await gameMonitor.status();
+    for (const game of status.session.activeGames) {
+      logInfo(`[Games] ${game.name} is running (PIDs ${game.pids.join(", ")})`);
+    }
+
+    for (const eventName of ["game-started", "game-stopped"]) {
+      const unlisten = await listen<GameEvent>(eventName, async (event) => {
+        logInfo(`[Games] ${eventName}: ${event.payload.name} (PID ${event.payload.pid})`);
+        try {
+          await runningProcesses.refresh();
+        } catch (error) {
+          logError("Error refreshing processes after game state change:", error);
         }
-      } catch (error) {
-        logError("Error in handling CS2 window state:", error);
+      });
+      unlisteners.push(unlisten);
+    }
+
+    const unlistenReport = await listen<SessionReport>("game-session-report", (event) => {
+      const report = event.payload;
+      const failed = report.steps.filter((step) => step.status === "failed");
+      if (report.rolledBack) {
+        logWarn(`[Games] ${report.gameId}: rolled back after ${failed.map((s) => s.step).join(", ")} failed`);
+      } else if (failed.length > 0) {
+        logWarn(`[Games] ${report.gameId}: ${failed.length} step(s) failed`);
       }
     });
-    unlisteners.push(windowListener);
+    unlisteners.push(unlistenReport);
   } catch (e) {
     logError("Error initializing event tracking:", e);
   }
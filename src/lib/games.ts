@@ -0,0 +1,334 @@
+import { reactive } from "vue";
+import { invoke } from "@tauri-apps/api/core";
+
+export type SessionStep =
+  | "powerPlan"
+  | "coreParking"
+  | "affinity"
+  | "priority"
+  | "killProcesses"
+  | "throttle";
+
+/**
+ * When and how often a step runs. Retries wait `backoffMs`, doubled each time.
+ */
+export interface StepPolicy {
+  step: SessionStep;
+  delayMs: number;
+  retries: number;
+  backoffMs: number;
+  order: number;
+}
+
+export type GameMatcher =
+  | { kind: "exeName"; value: string }
+  | { kind: "pathContains"; value: string }
+  | { kind: "steamAppId"; value: number };
+
+/**
+ * What the backend applies while a game runs.
+ */
+export interface OptimizationProfile {
+  powerPlanGuid: string | null;
+  affinityCores: number[];
+  priority: "idle" | "belowNormal" | "normal" | "aboveNormal" | "high" | null;
+  preventParking: boolean;
+  vibrance: number | null;
+  killProcesses: string[];
+  throttleProcesses: string[];
+  throttleLimits: { cpuPercent: number | null; memoryMb: number | null };
+  /** A failing critical step undoes the steps applied before it. */
+  criticalSteps: SessionStep[];
+  stepPolicies: StepPolicy[];
+}
+
+/**
+ * A watched game. Without a profile the active profile applies, or else the regular
+ * settings pages.
+ */
+export interface GameTarget {
+  id: string;
+  name: string;
+  enabled: boolean;
+  matchers: GameMatcher[];
+  profile: OptimizationProfile | null;
+  /** Named profile for the game; takes precedence over `profile`. */
+  profileId?: string | null;
+}
+
+/**
+ * Payload of `game-started` and `game-stopped`.
+ * `timestamp` is in milliseconds since the app started and never jumps.
+ */
+export interface GameEvent {
+  gameId: string;
+  name: string;
+  pids: number[];
+  exePath: string | null;
+  /** Unix time in milliseconds. */
+  startedAt: number;
+  pid: number;
+  timestamp: number;
+}
+
+/**
+ * Payload of `game-process`, sent for every process of a game.
+ */
+export interface GameProcessEvent {
+  gameId: string;
+  pid: number;
+  running: boolean;
+  exePath: string | null;
+  /** Unix time in seconds. */
+  startTime: number | null;
+  pids: number[];
+  timestamp: number;
+}
+
+export interface Bounds {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+export interface WindowInfo {
+  /** GDI device name, e.g. `\\.\DISPLAY1`. */
+  monitor: string;
+  monitorBounds: Bounds;
+  bounds: Bounds;
+  fullscreen: boolean;
+}
+
+/**
+ * Payload of `game-window`. `gameId` is null when another window has the focus.
+ */
+export interface GameWindowEvent {
+  gameId: string | null;
+  pid: number | null;
+  window: WindowInfo | null;
+  timestamp: number;
+}
+
+/**
+ * Payload of `game-display` (and `cs2display`): the focused game window moved to
+ * another monitor. The vibrance boost follows it.
+ */
+export interface GameDisplayEvent {
+  gameId: string;
+  pid: number;
+  monitor: string;
+  previousMonitor: string | null;
+  window: WindowInfo;
+  timestamp: number;
+}
+
+export interface ActiveGame {
+  gameId: string;
+  name: string;
+  pids: number[];
+  exePath: string | null;
+  startedAt: number;
+}
+
+export type StepStatus =
+  | { status: "applied" }
+  | { status: "skipped" }
+  | { status: "failed"; error: string }
+  | { status: "notRun" }
+  | { status: "rolledBack" }
+  | { status: "rollbackFailed"; error: string };
+
+/**
+ * Payload of `game-session-progress`, sent after every attempt of a step.
+ */
+export type StepProgress = StepStatus & {
+  gameId: string;
+  step: SessionStep;
+  attempt: number;
+  retryInMs: number | null;
+  timestamp: number;
+};
+
+/**
+ * Payload of `game-session-report`: how applying a game's profile went.
+ */
+export interface SessionReport {
+  gameId: string;
+  pid: number;
+  steps: (StepStatus & { step: SessionStep })[];
+  rolledBack: boolean;
+  timestamp: number;
+}
+
+export interface PlannedProcess {
+  pid: number;
+  name: string;
+}
+
+export type PlannedAction =
+  | { action: "powerPlan"; guid: string; name: string | null; current: string | null }
+  | {
+      action: "coreParking";
+      acValue: number;
+      dcValue: number;
+      current: { acValue: number; dcValue: number } | null;
+    }
+  | { action: "affinity"; cores: number[]; mask: string; pids: number[] }
+  | { action: "priority"; priority: NonNullable<OptimizationProfile["priority"]>; pids: number[] }
+  | { action: "killProcesses"; processes: PlannedProcess[] }
+  | {
+      action: "throttle";
+      processes: PlannedProcess[];
+      limits: OptimizationProfile["throttleLimits"];
+    }
+  | { action: "vibrance"; level: number; display: string | null; restoreLevel: number };
+
+/**
+ * Result of `preview_session_plan`.
+ */
+export interface SessionPlan {
+  gameId: string;
+  name: string;
+  usesSettings: boolean;
+  /** Named profile that applies, from the game or the active profile. */
+  profileName: string | null;
+  runningPids: number[];
+  steps: (PlannedAction & { critical: boolean; delayMs: number; retries: number })[];
+}
+
+/**
+ * Parts of the profiles that can be switched off (also from the tray) until the
+ * app restarts.
+ */
+export type SessionFeature = "powerPlan" | "killList" | "affinity" | "vibrance";
+
+/**
+ * Payload of the `system-event` event. After `resumed` and `displaysChanged` the
+ * running session applies its settings again.
+ */
+export type SystemEvent = "suspending" | "resumed" | "displaysChanged" | "sessionEnding";
+
+/**
+ * Debounce for the foreground detection. Windows of the ignored classes and
+ * processes (flyouts, overlays) do not count as the game losing focus.
+ */
+export interface FocusConfig {
+  backgroundDelayMs: number;
+  foregroundDelayMs: number;
+  ignoredWindowClasses: string[];
+  ignoredProcesses: string[];
+}
+
+/**
+ * Result of `get_game_status`, so a reloaded frontend knows what is running.
+ */
+export interface GameStatus {
+  monitor: {
+    running: boolean;
+    paused: boolean;
+    intervalMs: number;
+    focus: FocusConfig;
+    source: string | null;
+  };
+  session: {
+    activeGames: ActiveGame[];
+    foregroundGame: string | null;
+    foregroundDisplay: string | null;
+    lastReport: SessionReport | null;
+    /** Profile that decides the global settings; without a game, the default profile. */
+    activeProfile: string | null;
+    /** `endsAt` is Unix time in ms, `null` until the next game starts. */
+    snooze: { endsAt: number | null } | null;
+    disabledFeatures: SessionFeature[];
+    /** Name of the power plan the session activated. */
+    powerPlan: string | null;
+    affinityCores: number[];
+    vibrance: number | null;
+    /** Most recent error, cleared when the next session starts. */
+    lastError: string | null;
+  };
+}
+
+export const gameMonitor = {
+  status: () => invoke<GameStatus>("get_game_status"),
+  pause: () => invoke("pause_monitor"),
+  resume: () => invoke("resume_monitor"),
+  setInterval: (intervalMs: number) =>
+    invoke("set_monitor_interval", { intervalMs }),
+  setFocusConfig: (config: FocusConfig) =>
+    invoke("set_focus_config", { config }),
+  /** What starting the game would change, without changing anything. */
+  previewPlan: (gameId?: string) =>
+    invoke<SessionPlan>("preview_session_plan", { gameId: gameId ?? null }),
+  /** Handles `event` as if Windows had sent it. Debug builds only. */
+  simulateSystemEvent: (event: SystemEvent) =>
+    invoke("simulate_system_event", { event }),
+  setFeature: (feature: SessionFeature, enabled: boolean) =>
+    invoke("set_session_feature", { feature, enabled }),
+  /** Reverts the running games; `minutes` omitted snoozes until the next game starts. */
+  snooze: (minutes?: number) =>
+    invoke("snooze_automation", { minutes: minutes ?? null }),
+  resumeAutomation: () => invoke("resume_automation"),
+  revertNow: () => invoke("revert_session"),
+};
+
+interface GameState {
+  targets: GameTarget[];
+  error: string | null;
+}
+
+const state = reactive<GameState>({
+  targets: [],
+  error: null,
+});
+
+async function refresh() {
+  try {
+    state.targets = await invoke<GameTarget[]>("get_game_targets");
+    state.error = null;
+  } catch (err: any) {
+    console.error("Failed to fetch game targets:", err);
+    state.error = String(err);
+  }
+}
+
+async function save(targets: GameTarget[]) {
+  try {
+    await invoke("set_game_targets", { targets });
+    state.targets = targets;
+    state.error = null;
+  } catch (err: any) {
+    state.error = String(err);
+    throw err;
+  }
+}
+
+export const games = {
+  get targets() {
+    return state.targets;
+  },
+  get error() {
+    return state.error;
+  },
+  refresh,
+  save,
+  setEnabled: async (id: string, enabled: boolean) => {
+    await save(
+      state.targets.map((target) =>
+        target.id === id ? { ...target, enabled } : target,
+      ),
+    );
+  },
+  /** Assigns a named profile to the game; `null` goes back to its inline profile. */
+  setProfile: async (id: string, profileId: string | null) => {
+    await save(
+      state.targets.map((target) =>
+        target.id === id ? { ...target, profileId } : target,
+      ),
+    );
+  },
+  reset: async () => {
+    state.targets = await invoke<GameTarget[]>("reset_game_targets");
+  },
+};
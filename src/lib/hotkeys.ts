@@ -0,0 +1,39 @@
+/**
+ * @file hotkeys.ts
+ * @description Global hotkeys, registered by the backend so they work while a game
+ * has the focus.
+ */
+
+import { invoke } from "@tauri-apps/api/core";
+
+export type HotkeyAction =
+  | "toggleVibrance"
+  | "cycleProfile"
+  | "pauseAutomation"
+  | "forceApply"
+  | "forceRevert";
+
+export interface HotkeyBinding {
+  action: HotkeyAction;
+  /** E.g. "Ctrl+Shift+F9". Letters and digits need a modifier. */
+  shortcut: string;
+}
+
+/** A stored shortcut that is not active, usually because another app owns it. */
+export interface HotkeyConflict {
+  action: HotkeyAction;
+  shortcut: string;
+  reason: string;
+}
+
+export interface HotkeyStatus {
+  bindings: HotkeyBinding[];
+  conflicts: HotkeyConflict[];
+}
+
+export const hotkeys = {
+  get: () => invoke<HotkeyStatus>("get_hotkeys"),
+  /** Rejects invalid bindings; resolves with the ones that could not be registered. */
+  set: (bindings: HotkeyBinding[]) =>
+    invoke<HotkeyConflict[]>("set_hotkeys", { bindings }),
+};
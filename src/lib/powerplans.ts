@@ -1,5 +1,7 @@
 import { reactive } from "vue";
 import { invoke } from "@tauri-apps/api/core";
+import { logWarn } from "@lib/logger";
+import type { Verification } from "@lib/verification";
 
 export interface PowerPlan {
   guid: string;
@@ -53,7 +55,10 @@ export const powerPlans = {
   refresh: _fetchAndUpdatePowerPlansInternal,
   setActive: async (guid: string) => {
     try {
-      await invoke("set_active_power_plan", { guid });
+      const result = await invoke<Verification<string>>("set_active_power_plan", { guid });
+      if (result.status === "mismatch") {
+        logWarn(`Power plan ${result.actual} is still active instead of ${result.expected}`);
+      }
       await _fetchAndUpdatePowerPlansInternal();
     } catch (err) {
       console.error("Failed to set active power plan:", err);
@@ -0,0 +1,108 @@
+/**
+ * @file profiles.ts
+ * @description Named optimization profiles ("Competitive", "Streaming", ...). Stored
+ * and validated by the backend; also switchable from the tray, a hotkey or with
+ * `--profile <name>` on the command line.
+ */
+
+import { reactive } from "vue";
+import { invoke } from "@tauri-apps/api/core";
+import type { OptimizationProfile } from "@lib/games";
+
+export interface NamedProfile extends OptimizationProfile {
+  id: string;
+  name: string;
+}
+
+export interface ProfileList {
+  profiles: NamedProfile[];
+  /**
+   * Used for games without a profile of their own; `null` leaves them on the settings
+   * pages. A game's own profile always wins.
+   */
+  activeProfileId: string | null;
+}
+
+/** A profile that changes nothing, as a starting point for the editor. */
+export function emptyProfile(name: string, existing: NamedProfile[]): NamedProfile {
+  const base = name.trim().toLowerCase().replace(/[^a-z0-9]+/g, "-").replace(/^-|-$/g, "") || "profile";
+  let id = base;
+  for (let n = 2; existing.some((profile) => profile.id === id) || id === "none"; n++) {
+    id = `${base}-${n}`;
+  }
+  return {
+    id,
+    name: name.trim(),
+    powerPlanGuid: null,
+    affinityCores: [],
+    priority: null,
+    preventParking: false,
+    vibrance: null,
+    killProcesses: [],
+    throttleProcesses: [],
+    throttleLimits: { cpuPercent: null, memoryMb: null },
+    criticalSteps: [],
+    stepPolicies: [],
+  };
+}
+
+interface ProfileState {
+  list: NamedProfile[];
+  activeProfileId: string | null;
+  error: string | null;
+}
+
+const state = reactive<ProfileState>({
+  list: [],
+  activeProfileId: null,
+  error: null,
+});
+
+async function refresh() {
+  try {
+    const result = await invoke<ProfileList>("get_profiles");
+    state.list = result.profiles;
+    state.activeProfileId = result.activeProfileId;
+    state.error = null;
+  } catch (err: any) {
+    console.error("Failed to fetch profiles:", err);
+    state.error = String(err);
+  }
+}
+
+export const profiles = {
+  get list() {
+    return state.list;
+  },
+  get activeProfileId() {
+    return state.activeProfileId;
+  },
+  get error() {
+    return state.error;
+  },
+  refresh,
+  /** Rejects invalid profiles and removing a profile a game still uses. */
+  set: async (list: NamedProfile[]) => {
+    try {
+      await invoke("set_profiles", { profiles: list });
+      state.error = null;
+    } catch (err: any) {
+      state.error = String(err);
+      throw err;
+    } finally {
+      // Removing the active profile also clears it.
+      await refresh();
+    }
+  },
+  /** Sets the profile for games without their own; `null` clears it. */
+  activate: async (profileId: string | null) => {
+    try {
+      await invoke("set_active_profile", { profileId });
+      state.activeProfileId = profileId;
+      state.error = null;
+    } catch (err: any) {
+      state.error = String(err);
+      throw err;
+    }
+  },
+};
@@ -1,14 +1,51 @@
 import { reactive } from "vue";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke, Channel } from "@tauri-apps/api/core";
+
+/**
+ * Version info read from the executable (only present if requested).
+ */
+export interface ExecutableMetadata {
+  fileDescription?: string | null;
+  companyName?: string | null;
+  productName?: string | null;
+  fileVersion?: string | null;
+  productVersion?: string | null;
+}
 
 export interface ProcessInfo {
   name: string;
   pid: number;
   memory: number;
+  cpuUsage: number;
+  metadata?: ExecutableMetadata;
+}
+
+/**
+ * Options for the backend process stream (`subscribe_processes`).
+ * Filtering and sorting happen in Rust, so only the matching processes are sent, in order.
+ */
+export interface ProcessStreamOptions {
+  intervalMs?: number;
+  filter?: string;
+  sortKey?: "pid" | "name" | "memory" | "cpuUsage";
+  sortOrder?: "asc" | "desc";
 }
 
-export type FilterType = "all";
-export type SortKey = keyof ProcessInfo | null;
+/**
+ * Messages pushed by the backend. The first one is always a full snapshot,
+ * followed by incremental deltas.
+ */
+type ProcessUpdate =
+  | { kind: "snapshot"; processes: ProcessInfo[] }
+  | {
+      kind: "delta";
+      added: ProcessInfo[];
+      changed: ProcessInfo[];
+      removed: number[];
+      order?: number[];
+    };
+
+export type SortKey = NonNullable<ProcessStreamOptions["sortKey"]>;
 export type SortOrder = "asc" | "desc";
 
 interface ProcessStoreState {
@@ -21,6 +58,38 @@ const state = reactive<ProcessStoreState>({
   errorMessage: "",
 });
 
+let subscriptionId: number | null = null;
+/**
+ * Bumped by every subscribe and unsubscribe, so a subscription that is still being set
+ * up knows it was replaced or cancelled in the meantime.
+ */
+let generation = 0;
+/** Options of the active stream, so `refresh` can restart it instead of mixing lists. */
+let activeOptions: ProcessStreamOptions | null = null;
+
+/**
+ * Applies a backend update to the local process list.
+ * @param update - Snapshot or delta sent over the channel.
+ */
+function applyUpdate(update: ProcessUpdate) {
+  if (update.kind === "snapshot") {
+    state.processes = update.processes;
+    return;
+  }
+
+  const byPid = new Map(state.processes.map((p) => [p.pid, p]));
+  update.removed.forEach((pid) => byPid.delete(pid));
+  update.added.forEach((p) => byPid.set(p.pid, p));
+  update.changed.forEach((p) => byPid.set(p.pid, p));
+
+  // * NOTE: Without `order` the backend did not sort, so insertion order is fine.
+  state.processes = update.order
+    ? update.order
+        .map((pid) => byPid.get(pid))
+        .filter((p): p is ProcessInfo => !!p)
+    : Array.from(byPid.values());
+}
+
 export const runningProcesses = {
   get processes() {
     return state.processes;
@@ -29,7 +98,15 @@ export const runningProcesses = {
     return state.errorMessage;
   },
 
+  /**
+   * Reloads the list. While a stream is active it is restarted, so the list keeps the
+   * stream's filter and order.
+   */
   refresh: async () => {
+    if (activeOptions) {
+      await runningProcesses.subscribe(activeOptions);
+      return;
+    }
     try {
       const processes = await invoke<ProcessInfo[]>("get_processes");
       state.processes = processes;
@@ -41,55 +118,63 @@ export const runningProcesses = {
     }
   },
 
-  get: (): ProcessInfo[] => {
-    return state.processes;
-  },
+  /**
+   * Starts streaming process updates from the backend instead of polling.
+   * Any previous subscription is replaced.
+   * @param options - Interval, filter and sort options for the stream.
+   */
+  subscribe: async (options: ProcessStreamOptions = {}) => {
+    await runningProcesses.unsubscribe();
+    const current = ++generation;
 
-  getFiltered: (
-    filter: FilterType = "all",
-    sortKey: SortKey = "name",
-    sortOrder: SortOrder = "asc",
-  ): ProcessInfo[] => {
-    let tempProcesses = [...state.processes];
-
-    // Sort
-    if (sortKey) {
-      tempProcesses.sort((a, b) => {
-        const valA = a[sortKey!];
-        const valB = b[sortKey!];
-
-        let comparison = 0;
-        if (typeof valA === "string" && typeof valB === "string") {
-          comparison = valA.localeCompare(valB);
-        } else if (typeof valA === "number" && typeof valB === "number") {
-          comparison = valA - valB;
-        }
-        return sortOrder === "asc" ? comparison : -comparison;
+    const channel = new Channel<ProcessUpdate>();
+    channel.onmessage = (update) => {
+      if (current === generation) applyUpdate(update);
+    };
+
+    try {
+      const id = await invoke<number>("subscribe_processes", {
+        channel,
+        options,
       });
+      if (current !== generation) {
+        // Unsubscribed or replaced while the call was pending; stop the backend thread.
+        await invoke("unsubscribe_processes", { id }).catch(() => {});
+        return;
+      }
+      subscriptionId = id;
+      activeOptions = options;
+      state.errorMessage = "";
+    } catch (err: any) {
+      console.error(
+        "[runningProcesses] Error subscribing to process list:",
+        err,
+      );
+      state.errorMessage = `Failed to subscribe to process list: ${err.message || err}`;
     }
-
-    return tempProcesses;
   },
 
-  getBySearch: (
-    searchTerm: string,
-    filter: FilterType = "all",
-    sortKey: SortKey = "name",
-    sortOrder: SortOrder = "asc",
-  ): ProcessInfo[] => {
-    let tempProcesses = runningProcesses.getFiltered(
-      filter,
-      sortKey,
-      sortOrder,
-    );
-
-    if (searchTerm.trim() !== "") {
-      const lowerSearchTerm = searchTerm.toLowerCase();
-      tempProcesses = tempProcesses.filter((p) =>
-        p.name.toLowerCase().includes(lowerSearchTerm),
+  /**
+   * Stops the backend process stream, if one is active.
+   */
+  unsubscribe: async () => {
+    generation++;
+    activeOptions = null;
+    if (subscriptionId === null) return;
+    const id = subscriptionId;
+    subscriptionId = null;
+    try {
+      await invoke("unsubscribe_processes", { id });
+    } catch (err) {
+      console.error(
+        "[runningProcesses] Error unsubscribing from process list:",
+        err,
       );
     }
-    return tempProcesses;
+  },
+
+  get: (): ProcessInfo[] => {
+    return state.processes;
   },
 
   isProcessRunning: (processName: string): boolean => {
@@ -31,6 +31,12 @@ export const defaultAppSettings = {
     defaultAcParking: null as number | null,
     defaultDcParking: null as number | null,
   },
+  throttleSettings: {
+    enabled: false,
+    processes: [] as string[],
+    cpuPercent: 10 as number | null,
+    memoryMb: null as number | null,
+  },
 };
 
 export type AppSettings = typeof defaultAppSettings;
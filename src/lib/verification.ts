@@ -0,0 +1,6 @@
+/**
+ * What a backend setter read back after applying a value.
+ */
+export type Verification<T> =
+  | { status: "verified" }
+  | { status: "mismatch"; expected: T; actual: T };